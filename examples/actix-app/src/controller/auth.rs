@@ -30,19 +30,23 @@ pub async fn login(mut req: Request) -> Result {
 }
 
 pub async fn refresh(req: Request) -> Result {
-    let claims = req.parse_jwt_claims(JwtClaims::shared_key())?;
+    let claims = req.verify_jwt_claims()?;
     let data = User::refresh_token(&claims).await.extract(&req)?;
     let mut res = Response::default().context(&req);
     res.set_json_data(data);
     Ok(res.into())
 }
 
-pub async fn logout(req: Request) -> Result {
+pub async fn logout(mut req: Request) -> Result {
     let user_session = req
         .get_data::<UserSession<_>>()
         .ok_or_else(|| warn!("401 Unauthorized: the user session is invalid"))
         .extract(&req)?;
 
+    let claims = req.verify_jwt_claims()?;
+    let body: Map = req.parse_body().await.unwrap_or_default();
+    User::logout(&claims, &body).await.extract(&req)?;
+
     let mut mutations = Map::from_entry("status", "SignedOut");
     let user_id = user_session.user_id();
     let (validation, user) = User::update_by_id(user_id, &mut mutations, None)
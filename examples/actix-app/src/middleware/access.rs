@@ -44,7 +44,7 @@ where
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let mut req = Request::from(req);
-        match req.parse_jwt_claims(JwtClaims::shared_key()) {
+        match req.verify_jwt_claims() {
             Ok(claims) => {
                 if let Ok(mut user_session) = UserSession::<Uuid>::try_from_jwt_claims(claims) {
                     if let Ok(session_id) = req.parse_session_id() {
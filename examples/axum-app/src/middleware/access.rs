@@ -5,7 +5,7 @@ use zino_model::user::JwtAuthService;
 
 pub async fn init_user_session(mut req: Request, next: Next<Body>) -> Result<Response> {
     let claims = req
-        .parse_jwt_claims(JwtClaims::shared_key())
+        .verify_jwt_claims()
         .map_err(|rejection| rejection.context(&req))?;
     match User::verify_jwt_claims(&claims).await {
         Ok(verified) => {
@@ -3,6 +3,7 @@
 use clap::Parser;
 
 mod init;
+mod seed;
 
 /// CLI tool for developing Zino applications.
 #[derive(Parser)]
@@ -25,6 +26,12 @@ impl Cli {
     pub fn action(self) -> Subcommands {
         self.action
     }
+
+    /// Returns the bin target.
+    #[inline]
+    pub fn bin(&self) -> Option<&str> {
+        self.bin.as_deref()
+    }
 }
 
 /// CLI subcommands.
@@ -32,4 +39,6 @@ impl Cli {
 pub enum Subcommands {
     /// Initialize the project for Zino.
     Init(init::Init),
+    /// Load the seed data for the models.
+    Seed(seed::Seed),
 }
@@ -0,0 +1,34 @@
+use clap::Parser;
+use std::process::Command;
+use zino_core::error::Error;
+
+/// Load the seed data for the models.
+#[derive(Parser)]
+#[clap(name = "seed")]
+pub struct Seed {
+    /// Build the bin target in release mode.
+    #[clap(long)]
+    release: bool,
+}
+
+impl Seed {
+    /// Runs the `seed` subcommand. The bin target is started with the `--seed` argument,
+    /// so that it exits after the registered seeder has finished.
+    pub fn run(self, bin: Option<&str>) -> Result<(), Error> {
+        let mut command = Command::new("cargo");
+        command.arg("run");
+        if let Some(bin) = bin {
+            command.args(["--bin", bin]);
+        }
+        if self.release {
+            command.arg("--release");
+        }
+
+        let status = command.args(["--", "--seed"]).status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(Error::new(format!("fail to run the seeder: {status}")))
+        }
+    }
+}
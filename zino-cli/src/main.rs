@@ -2,8 +2,11 @@ use clap::Parser;
 use zino_cli::{Cli, Subcommands::*};
 
 fn main() {
-    let result = match Cli::parse().action() {
+    let cli = Cli::parse();
+    let bin = cli.bin().map(|bin| bin.to_owned());
+    let result = match cli.action() {
         Init(opts) => opts.run(),
+        Seed(opts) => opts.run(bin.as_deref()),
     };
     if let Err(err) = result {
         log::error!("Failed to run the command: {err}");
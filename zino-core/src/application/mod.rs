@@ -106,6 +106,18 @@ pub trait Application {
         self.register_with(ServerTag::Debug, routes)
     }
 
+    /// Registers a seeder to load the seed data at boot. It runs when the `--seed` argument
+    /// is provided or `seed-on-boot` is enabled in the database configuration.
+    #[cfg(feature = "orm")]
+    #[inline]
+    fn seed(self, seeder: crate::orm::Seeder) -> Self
+    where
+        Self: Sized,
+    {
+        seeder.register();
+        self
+    }
+
     /// Gets the system’s information.
     #[inline]
     fn sysinfo() -> Map {
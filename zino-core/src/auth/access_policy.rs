@@ -0,0 +1,481 @@
+use super::UserSession;
+use crate::{
+    datetime::DateTime,
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    state::State,
+    BoxFuture, JsonValue, Map, Uuid,
+};
+use parking_lot::RwLock;
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        LazyLock, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// Effect of an access policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyEffect {
+    /// Allows the access.
+    Allow,
+    /// Denies the access.
+    Deny,
+}
+
+/// An access policy compiled from the data of a `Policy` model.
+///
+/// The following fields are recognized:
+///
+/// - `resource`: a resource pattern like `user:*`, where `*` matches any characters.
+/// - `actions`: a list of action patterns like `view`, `list` or `*`.
+/// - `effect`: `Allow` or `Deny` (default: `Allow`).
+/// - `tenant_id`: the tenant which the policy belongs to. A nil UUID matches all the tenants.
+/// - `valid_from`, `expires_at`: the time window when the policy is in effect.
+///   An `expires_at` which is not later than `created_at` means the policy never expires.
+/// - `content.subjects`: a list of subjects like `role:admin`, `user:{user_id}` or `*`
+///   (default: `*`).
+/// - `content.conditions`: attribute conditions for the context. A condition value can be
+///   a literal value, an array of candidates, or a reference to the subject attributes
+///   `$subject.user_id`, `$subject.tenant_id` and `$subject.roles`.
+///   If an attribute is absent in the context, the condition is satisfied for a `Deny` policy
+///   but not for an `Allow` policy, so that the access is denied in both cases.
+///
+/// In the default controller, the resource of a single model is `{model_name}:{id}`,
+/// and the resource of a model list is `{model_name}`.
+#[derive(Debug, Clone)]
+pub struct AccessPolicy {
+    /// Policy ID.
+    id: String,
+    /// Resource pattern.
+    resource: String,
+    /// Action patterns.
+    actions: Vec<String>,
+    /// Effect.
+    effect: PolicyEffect,
+    /// Tenant ID.
+    tenant_id: Option<String>,
+    /// Start time of the validity.
+    valid_from: Option<DateTime>,
+    /// End time of the validity.
+    expires_at: Option<DateTime>,
+    /// Subject patterns.
+    subjects: Vec<String>,
+    /// Attribute conditions.
+    conditions: Map,
+}
+
+impl AccessPolicy {
+    /// Compiles the policy from the model data.
+    pub fn from_map(data: &Map) -> Self {
+        let id = data.parse_string("id").unwrap_or_default().into_owned();
+        let resource = data.get_str("resource").unwrap_or_default().to_owned();
+        let actions = data
+            .parse_str_array("actions")
+            .unwrap_or_default()
+            .into_iter()
+            .map(|s| s.to_owned())
+            .collect();
+        let effect = if data
+            .get_str("effect")
+            .is_some_and(|s| s.eq_ignore_ascii_case("deny"))
+        {
+            PolicyEffect::Deny
+        } else {
+            PolicyEffect::Allow
+        };
+        let tenant_id = data
+            .parse_string("tenant_id")
+            .filter(|s| !s.is_empty() && s.parse::<Uuid>().map_or(true, |id| !id.is_nil()))
+            .map(|s| s.into_owned());
+        let valid_from = data.get_datetime("valid_from");
+        let expires_at = data.get_datetime("expires_at").filter(|expires_at| {
+            data.get_datetime("created_at").map_or(true, |created_at| {
+                expires_at.timestamp() > created_at.timestamp()
+            })
+        });
+        let content = data.get_object("content");
+        let subjects = content
+            .and_then(|content| content.parse_str_array("subjects"))
+            .map(|subjects| subjects.into_iter().map(|s| s.to_owned()).collect())
+            .unwrap_or_else(|| vec!["*".to_owned()]);
+        let conditions = content
+            .and_then(|content| content.get_object("conditions"))
+            .cloned()
+            .unwrap_or_default();
+        Self {
+            id,
+            resource,
+            actions,
+            effect,
+            tenant_id,
+            valid_from,
+            expires_at,
+            subjects,
+            conditions,
+        }
+    }
+
+    /// Returns the policy ID.
+    #[inline]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the effect.
+    #[inline]
+    pub fn effect(&self) -> PolicyEffect {
+        self.effect
+    }
+
+    /// Returns `true` if the policy is applicable to the access request.
+    pub fn matches<U, T>(
+        &self,
+        session: &UserSession<U, String, T>,
+        action: &str,
+        resource: &str,
+        context: &Map,
+    ) -> bool
+    where
+        U: Display,
+        T: Display,
+    {
+        let now = DateTime::now();
+        if self.valid_from.is_some_and(|valid_from| valid_from > now)
+            || self.expires_at.is_some_and(|expires_at| expires_at <= now)
+        {
+            return false;
+        }
+        if let Some(tenant_id) = self.tenant_id.as_deref()
+            && session.tenant_id().map(|id| id.to_string()).as_deref() != Some(tenant_id)
+        {
+            return false;
+        }
+        if !match_wildcard(&self.resource, resource)
+            || !self
+                .actions
+                .iter()
+                .any(|pattern| match_wildcard(pattern, action))
+        {
+            return false;
+        }
+
+        let user_id = session.user_id().to_string();
+        let roles = session.roles();
+        let subject_matched = self.subjects.iter().any(|subject| {
+            if let Some(pattern) = subject.strip_prefix("role:") {
+                roles.iter().any(|role| match_wildcard(pattern, role))
+            } else if let Some(pattern) = subject.strip_prefix("user:") {
+                match_wildcard(pattern, &user_id)
+            } else {
+                subject == "*"
+            }
+        });
+        if !subject_matched {
+            return false;
+        }
+
+        self.conditions.iter().all(|(key, expected)| {
+            let Some(value) = context.get(key) else {
+                return self.effect == PolicyEffect::Deny;
+            };
+            match expected {
+                JsonValue::String(s) if s.starts_with("$subject.") => {
+                    match s.trim_start_matches("$subject.") {
+                        "user_id" => value.as_str() == Some(user_id.as_str()),
+                        "tenant_id" => session
+                            .tenant_id()
+                            .is_some_and(|id| value.as_str() == Some(id.to_string().as_str())),
+                        "roles" => value.as_str().is_some_and(|s| roles.iter().any(|r| r == s)),
+                        _ => false,
+                    }
+                }
+                JsonValue::Array(candidates) => candidates.contains(value),
+                _ => value == expected,
+            }
+        })
+    }
+}
+
+/// A loader which fetches the policies from the data source.
+pub type PolicyLoader = fn() -> BoxFuture<'static, Result<Vec<Map>, Error>>;
+
+/// An authorization engine which evaluates the access requests against the cached policies.
+/// The `Deny` policies take precedence over the `Allow` policies,
+/// and an access request is denied if there is no applicable policy.
+///
+/// The cached policies are reloaded by the [`PolicyLoader`] when they are older than
+/// the reload interval, so that the changes made on other instances are also applied.
+/// The interval is specified by the `[access-control]` configuration:
+///
+/// ```toml
+/// [access-control]
+/// reload-interval = "1m"
+/// ```
+#[derive(Debug)]
+pub struct PolicyEngine {
+    /// Compiled policies and the loaded time.
+    /// It is `None` if the policies have not been loaded.
+    policies: RwLock<Option<(Vec<AccessPolicy>, Instant)>>,
+    /// Reload interval.
+    reload_interval: Duration,
+    /// Policy loader.
+    loader: OnceLock<PolicyLoader>,
+    /// A flag to indicate whether the policies are being reloaded.
+    reloading: AtomicBool,
+}
+
+impl Default for PolicyEngine {
+    #[inline]
+    fn default() -> Self {
+        Self::with_reload_interval(Duration::from_secs(60))
+    }
+}
+
+impl PolicyEngine {
+    /// Creates a new instance without any policies.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new instance with the reload interval.
+    #[inline]
+    pub fn with_reload_interval(reload_interval: Duration) -> Self {
+        Self {
+            policies: RwLock::new(None),
+            reload_interval,
+            loader: OnceLock::new(),
+            reloading: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the shared policy engine.
+    #[inline]
+    pub fn shared() -> &'static Self {
+        LazyLock::force(&SHARED_POLICY_ENGINE)
+    }
+
+    /// Sets the loader for reloading the policies. It takes effect only once.
+    #[inline]
+    pub fn set_loader(&self, loader: PolicyLoader) {
+        self.loader.get_or_init(|| loader);
+    }
+
+    /// Compiles the policies and replaces the cached ones.
+    pub fn load(&self, policies: &[Map]) {
+        let policies = policies
+            .iter()
+            .map(AccessPolicy::from_map)
+            .collect::<Vec<_>>();
+        tracing::info!(
+            num_policies = policies.len(),
+            "the access policies have been loaded"
+        );
+        *self.policies.write() = Some((policies, Instant::now()));
+    }
+
+    /// Reloads the policies by the loader if they are absent or older than the reload interval.
+    /// Returns `true` if the policies have been reloaded.
+    pub async fn reload_if_stale(&self) -> Result<bool, Error> {
+        let Some(loader) = self.loader.get() else {
+            return Ok(false);
+        };
+        if !self.is_stale() || self.reloading.swap(true, Relaxed) {
+            return Ok(false);
+        }
+
+        // The flag is reset even if the future is dropped or the loader panics.
+        let _guard = ReloadGuard(&self.reloading);
+        let policies = loader().await?;
+        self.load(&policies);
+        Ok(true)
+    }
+
+    /// Clears the cached policies.
+    #[inline]
+    pub fn clear(&self) {
+        *self.policies.write() = None;
+    }
+
+    /// Returns `true` if the policies have been loaded.
+    #[inline]
+    pub fn is_loaded(&self) -> bool {
+        self.policies.read().is_some()
+    }
+
+    /// Returns `true` if the policies are absent or older than the reload interval.
+    #[inline]
+    pub fn is_stale(&self) -> bool {
+        self.policies
+            .read()
+            .as_ref()
+            .map_or(true, |(_, loaded_at)| {
+                loaded_at.elapsed() >= self.reload_interval
+            })
+    }
+
+    /// Evaluates the access request of the subject performing the action on the resource.
+    /// The context contains the attributes of the resource for the conditions.
+    pub fn evaluate<U, T>(
+        &self,
+        session: &UserSession<U, String, T>,
+        action: &str,
+        resource: &str,
+        context: &Map,
+    ) -> bool
+    where
+        U: Display,
+        T: Display,
+    {
+        let policies = self.policies.read();
+        let Some((policies, _)) = policies.as_ref() else {
+            tracing::warn!("the access policies have not been loaded");
+            return false;
+        };
+
+        let mut allowed = false;
+        for policy in policies {
+            if policy.matches(session, action, resource, context) {
+                match policy.effect() {
+                    PolicyEffect::Allow => allowed = true,
+                    PolicyEffect::Deny => {
+                        tracing::warn!(policy_id = policy.id(), action, resource, "access denied");
+                        return false;
+                    }
+                }
+            }
+        }
+        allowed
+    }
+}
+
+/// Shared policy engine.
+static SHARED_POLICY_ENGINE: LazyLock<PolicyEngine> = LazyLock::new(|| {
+    let reload_interval = State::shared()
+        .get_config("access-control")
+        .and_then(|config| config.get_duration("reload-interval"))
+        .unwrap_or_else(|| Duration::from_secs(60));
+    PolicyEngine::with_reload_interval(reload_interval)
+});
+
+/// A guard to reset the reloading flag of the policy engine.
+struct ReloadGuard<'a>(&'a AtomicBool);
+
+impl Drop for ReloadGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.store(false, Relaxed);
+    }
+}
+
+/// Returns `true` if the value matches the pattern, where `*` matches any characters.
+fn match_wildcard(pattern: &str, value: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern == value;
+    };
+    let Some(mut remaining) = value.strip_prefix(prefix) else {
+        return false;
+    };
+
+    let mut parts = rest.split('*').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return remaining.ends_with(part);
+        }
+        match remaining.find(part) {
+            Some(index) => remaining = &remaining[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{match_wildcard, PolicyEngine};
+    use crate::{auth::UserSession, error::Error, extension::JsonObjectExt, Map};
+    use futures::{future, FutureExt};
+    use serde_json::json;
+    use std::sync::atomic::Ordering::Relaxed;
+
+    #[test]
+    fn it_matches_wildcards() {
+        assert!(match_wildcard("*", "user:1"));
+        assert!(match_wildcard("user:*", "user:1"));
+        assert!(match_wildcard("user:*:name", "user:1:name"));
+        assert!(match_wildcard("*:1", "user:1"));
+        assert!(!match_wildcard("user:*", "policy:1"));
+        assert!(!match_wildcard("user", "user:1"));
+    }
+
+    #[test]
+    fn it_evaluates_policies() {
+        let policies = [
+            json!({
+                "resource": "order:*",
+                "actions": ["view", "list"],
+                "effect": "Allow",
+                "content": { "subjects": ["role:user"] },
+            }),
+            json!({
+                "resource": "order:*",
+                "actions": ["update"],
+                "content": {
+                    "subjects": ["role:user"],
+                    "conditions": { "owner_id": "$subject.user_id" },
+                },
+            }),
+            json!({
+                "resource": "order:archived",
+                "actions": ["*"],
+                "effect": "Deny",
+            }),
+            json!({
+                "resource": "order:*",
+                "actions": ["archive"],
+                "effect": "Deny",
+                "content": {
+                    "conditions": { "locked": true },
+                },
+            }),
+            json!({
+                "resource": "order:*",
+                "actions": ["archive"],
+                "content": { "subjects": ["role:user"] },
+            }),
+        ]
+        .into_iter()
+        .filter_map(|value| value.as_object().cloned())
+        .collect::<Vec<_>>();
+        let engine = PolicyEngine::new();
+        engine.load(&policies);
+
+        let mut session = UserSession::<String, String, String>::new("alice".to_owned(), None);
+        session.set_roles(vec!["user".to_owned()]);
+        let context = Map::from_entry("owner_id", "alice");
+        assert!(engine.evaluate(&session, "view", "order:1", &Map::new()));
+        assert!(engine.evaluate(&session, "update", "order:1", &context));
+        assert!(!engine.evaluate(&session, "update", "order:1", &Map::new()));
+        assert!(!engine.evaluate(&session, "delete", "order:1", &context));
+        assert!(!engine.evaluate(&session, "view", "order:archived", &context));
+
+        // A `Deny` policy with conditions applies if the attribute is absent.
+        let unlocked = Map::from_entry("locked", false);
+        let locked = Map::from_entry("locked", true);
+        assert!(engine.evaluate(&session, "archive", "order:1", &unlocked));
+        assert!(!engine.evaluate(&session, "archive", "order:1", &locked));
+        assert!(!engine.evaluate(&session, "archive", "order:1", &Map::new()));
+        assert!(engine.is_loaded() && !engine.is_stale());
+    }
+
+    #[test]
+    fn it_resets_reloading_flag() {
+        let engine = PolicyEngine::new();
+        engine.set_loader(|| Box::pin(future::pending::<Result<Vec<Map>, Error>>()));
+        assert!(engine.reload_if_stale().now_or_never().is_none());
+        assert!(!engine.reloading.load(Relaxed));
+    }
+}
@@ -1,15 +1,17 @@
+use super::JwtKey;
 use crate::{
-    crypto,
+    bail, crypto,
     datetime::DateTime,
     error::Error,
     extension::{JsonObjectExt, TomlTableExt},
     state::State,
-    JsonValue, Map,
+    JsonValue, Map, Uuid,
 };
 use jwt_simple::{
     algorithms::MACLike,
     claims::{self, Claims, JWTClaims},
     common::VerificationOptions,
+    token::Token,
 };
 use serde::{de::DeserializeOwned, Serialize};
 use std::{env, sync::LazyLock, time::Duration};
@@ -24,6 +26,7 @@ impl<T: Default + Serialize + DeserializeOwned> JwtClaims<T> {
         let mut claims = Claims::with_custom_claims(T::default(), (*DEFAULT_MAX_AGE).into());
         claims.invalid_before = None;
         claims.subject = Some(subject.to_string());
+        claims.jwt_id = Some(Uuid::now_v7().to_string());
         Self(claims)
     }
 
@@ -32,26 +35,46 @@ impl<T: Default + Serialize + DeserializeOwned> JwtClaims<T> {
         let mut claims = Claims::with_custom_claims(T::default(), max_age.into());
         claims.invalid_before = None;
         claims.subject = Some(subject.to_string());
+        claims.jwt_id = Some(Uuid::now_v7().to_string());
         Self(claims)
     }
 
-    /// Generates an access token signed with the shared secret access key.
+    /// Generates a refresh token in a new token family.
+    #[inline]
     pub fn refresh_token(&self) -> Result<String, Error> {
+        self.rotate_refresh_token(&Uuid::now_v7().to_string())
+    }
+
+    /// Generates a refresh token in the token family, which is stored as the nonce.
+    /// All the refresh tokens in a family can be revoked together
+    /// when the reuse of a refresh token has been detected.
+    pub fn rotate_refresh_token(&self, family_id: &str) -> Result<String, Error> {
         let mut claims = Claims::create((*DEFAULT_REFRESH_INTERVAL).into());
         claims.invalid_before = self
             .0
             .expires_at
             .map(|max_age| max_age - (*DEFAULT_TIME_TOLERANCE).into());
         claims.subject = self.0.subject.as_ref().cloned();
-        JwtClaims::shared_key()
-            .authenticate(claims)
-            .map_err(|err| Error::new(err.to_string()))
+        claims.jwt_id = Some(Uuid::now_v7().to_string());
+        claims.nonce = Some(family_id.to_owned());
+        if let Some(key) = JwtKey::signing_key() {
+            key.sign(claims)
+        } else {
+            JwtClaims::shared_key()
+                .authenticate(claims)
+                .map_err(|err| Error::new(err.to_string()))
+        }
     }
 
-    /// Generates an access token signed with the shared secret access key.
+    /// Generates an access token signed with the shared signing key if it has been configured,
+    /// or the shared secret access key otherwise.
     #[inline]
     pub fn access_token(self) -> Result<String, Error> {
-        self.sign_with(JwtClaims::shared_key())
+        if let Some(key) = JwtKey::signing_key() {
+            self.sign_with_key(key)
+        } else {
+            self.sign_with(JwtClaims::shared_key())
+        }
     }
 
     /// Generates a signature with the secret access key.
@@ -60,6 +83,42 @@ impl<T: Default + Serialize + DeserializeOwned> JwtClaims<T> {
         key.authenticate(self.0)
             .map_err(|err| Error::new(err.to_string()))
     }
+
+    /// Generates a signature with the asymmetric key.
+    /// The key ID is included in the header of the token.
+    #[inline]
+    pub fn sign_with_key(self, key: &JwtKey) -> Result<String, Error> {
+        key.sign(self.0)
+    }
+
+    /// Verifies the token with the default verification options.
+    #[inline]
+    pub fn verify(token: &str) -> Result<Self, Error> {
+        Self::verify_with_options(token, default_verification_options())
+    }
+
+    /// Verifies the token with the verification options.
+    /// The token is verified by the shared key with the `kid` in the header,
+    /// or the shared secret access key if it is signed by the HMAC algorithm.
+    pub(crate) fn verify_with_options(
+        token: &str,
+        options: VerificationOptions,
+    ) -> Result<Self, Error> {
+        let metadata = Token::decode_metadata(token).map_err(|err| Error::new(err.to_string()))?;
+        let claims = if let Some(key_id) = metadata.key_id()
+            && let Some(key) = JwtKey::get(key_id)
+        {
+            key.verify(token, Some(options))?
+        } else if metadata.algorithm() == JwtHmacKey::jwt_alg_name() {
+            JwtClaims::shared_key()
+                .verify_token(token, Some(options))
+                .map_err(|err| Error::new(err.to_string()))?
+        } else {
+            let key_id = metadata.key_id().unwrap_or_default();
+            bail!("the JWT key `{}` is unknown", key_id);
+        };
+        Ok(Self(claims))
+    }
 }
 
 impl<T> JwtClaims<T> {
@@ -108,6 +167,12 @@ impl<T> JwtClaims<T> {
         self.0.subject.as_deref()
     }
 
+    /// Returns the JWT ID.
+    #[inline]
+    pub fn jwt_id(&self) -> Option<&str> {
+        self.0.jwt_id.as_deref()
+    }
+
     /// Returns the nonce.
     #[inline]
     pub fn nonce(&self) -> Option<&str> {
@@ -135,6 +200,12 @@ impl JwtClaims<()> {
     pub fn shared_key() -> &'static JwtHmacKey {
         LazyLock::force(&SECRET_KEY)
     }
+
+    /// Returns the refresh interval for the refresh token.
+    #[inline]
+    pub fn refresh_interval() -> Duration {
+        *DEFAULT_REFRESH_INTERVAL
+    }
 }
 
 /// Returns the default time tolerance.
@@ -0,0 +1,309 @@
+use crate::{
+    application::PROJECT_DIR,
+    bail,
+    encoding::base64,
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    state::State,
+    JsonValue, Map,
+};
+use jwt_simple::{
+    algorithms::{
+        ECDSAP256KeyPairLike, ECDSAP256PublicKeyLike, ES256KeyPair, ES256PublicKey, Ed25519KeyPair,
+        Ed25519PublicKey, EdDSAKeyPairLike, EdDSAPublicKeyLike, RS256KeyPair, RS256PublicKey,
+        RSAKeyPairLike, RSAPublicKeyLike,
+    },
+    claims::JWTClaims,
+    common::VerificationOptions,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs, sync::LazyLock};
+use toml::value::Table;
+
+/// Private key for signing the tokens.
+enum SigningKey {
+    /// RSA-PKCS1v1.5 with SHA-256.
+    RS256(RS256KeyPair),
+    /// ECDSA on the P-256 curve with SHA-256.
+    ES256(ES256KeyPair),
+    /// EdDSA on the Ed25519 curve.
+    EdDSA(Ed25519KeyPair),
+}
+
+/// Public key for verifying the tokens.
+enum VerifyingKey {
+    /// RSA-PKCS1v1.5 with SHA-256.
+    RS256(RS256PublicKey),
+    /// ECDSA on the P-256 curve with SHA-256.
+    ES256(ES256PublicKey),
+    /// EdDSA on the Ed25519 curve.
+    EdDSA(Ed25519PublicKey),
+}
+
+/// An asymmetric key for JWT identified by the key ID.
+/// Supported algorithms: `RS256` | `ES256` | `EdDSA`.
+///
+/// The keys are loaded from the PEM files in the `[[jwt.keys]]` configuration.
+/// A retired key only needs the `public-key` to verify the tokens issued before the rotation,
+/// and the tokens are signed by the key specified by `signing-key`
+/// (default: the first key with a `private-key`). It panics at startup if a key
+/// can not be loaded or the `signing-key` is invalid, instead of falling back to
+/// the HMAC key silently.
+///
+/// ```toml
+/// [jwt]
+/// signing-key = "2024-10"
+///
+/// [[jwt.keys]]
+/// kid = "2024-10"
+/// algorithm = "ES256"
+/// private-key = "config/jwt/es256.pem"
+///
+/// [[jwt.keys]]
+/// kid = "2024-04"
+/// algorithm = "RS256"
+/// public-key = "config/jwt/rs256.pub.pem"
+/// ```
+pub struct JwtKey {
+    /// Key ID.
+    key_id: String,
+    /// Signing key.
+    signing_key: Option<SigningKey>,
+    /// Verifying key.
+    verifying_key: VerifyingKey,
+}
+
+impl JwtKey {
+    /// Creates a new instance from a PEM-encoded private key.
+    pub fn from_private_pem(algorithm: &str, key_id: &str, pem: &str) -> Result<Self, Error> {
+        let (signing_key, verifying_key) = match algorithm {
+            "RS256" => {
+                let key_pair = RS256KeyPair::from_pem(pem)
+                    .map_err(|err| Error::new(err.to_string()))?
+                    .with_key_id(key_id);
+                let public_key = key_pair.public_key();
+                (SigningKey::RS256(key_pair), VerifyingKey::RS256(public_key))
+            }
+            "ES256" => {
+                let key_pair = ES256KeyPair::from_pem(pem)
+                    .map_err(|err| Error::new(err.to_string()))?
+                    .with_key_id(key_id);
+                let public_key = key_pair.public_key();
+                (SigningKey::ES256(key_pair), VerifyingKey::ES256(public_key))
+            }
+            "EdDSA" | "Ed25519" => {
+                let key_pair = Ed25519KeyPair::from_pem(pem)
+                    .map_err(|err| Error::new(err.to_string()))?
+                    .with_key_id(key_id);
+                let public_key = key_pair.public_key();
+                (SigningKey::EdDSA(key_pair), VerifyingKey::EdDSA(public_key))
+            }
+            _ => bail!("unsupported JWT algorithm `{}`", algorithm),
+        };
+        Ok(Self {
+            key_id: key_id.to_owned(),
+            signing_key: Some(signing_key),
+            verifying_key,
+        })
+    }
+
+    /// Creates a new instance from a PEM-encoded public key,
+    /// which can only be used to verify the tokens.
+    pub fn from_public_pem(algorithm: &str, key_id: &str, pem: &str) -> Result<Self, Error> {
+        let verifying_key = match algorithm {
+            "RS256" => RS256PublicKey::from_pem(pem)
+                .map(VerifyingKey::RS256)
+                .map_err(|err| Error::new(err.to_string()))?,
+            "ES256" => ES256PublicKey::from_pem(pem)
+                .map(VerifyingKey::ES256)
+                .map_err(|err| Error::new(err.to_string()))?,
+            "EdDSA" | "Ed25519" => Ed25519PublicKey::from_pem(pem)
+                .map(VerifyingKey::EdDSA)
+                .map_err(|err| Error::new(err.to_string()))?,
+            _ => bail!("unsupported JWT algorithm `{}`", algorithm),
+        };
+        Ok(Self {
+            key_id: key_id.to_owned(),
+            signing_key: None,
+            verifying_key,
+        })
+    }
+
+    /// Attempts to load the key from the config table.
+    /// The paths of the PEM files are relative to the project directory.
+    pub fn try_from_config(config: &Table) -> Result<Self, Error> {
+        let Some(key_id) = config.get_str("kid") else {
+            bail!("the `kid` of the JWT key should be specified");
+        };
+        let algorithm = config.get_str("algorithm").unwrap_or("RS256");
+        if let Some(path) = config.get_str("private-key") {
+            let pem = fs::read_to_string(PROJECT_DIR.join(path))?;
+            Self::from_private_pem(algorithm, key_id, &pem)
+        } else if let Some(path) = config.get_str("public-key") {
+            let pem = fs::read_to_string(PROJECT_DIR.join(path))?;
+            Self::from_public_pem(algorithm, key_id, &pem)
+        } else {
+            bail!(
+                "the JWT key `{}` should have a `private-key` or `public-key`",
+                key_id
+            );
+        }
+    }
+
+    /// Returns the key ID.
+    #[inline]
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Returns the JWT algorithm name.
+    #[inline]
+    pub fn algorithm(&self) -> &'static str {
+        match self.verifying_key {
+            VerifyingKey::RS256(_) => "RS256",
+            VerifyingKey::ES256(_) => "ES256",
+            VerifyingKey::EdDSA(_) => "EdDSA",
+        }
+    }
+
+    /// Returns `true` if the key has a private key for signing.
+    #[inline]
+    pub fn can_sign(&self) -> bool {
+        self.signing_key.is_some()
+    }
+
+    /// Signs the claims and generates a token with the key ID in the header.
+    pub(crate) fn sign<T>(&self, claims: JWTClaims<T>) -> Result<String, Error>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let result = match &self.signing_key {
+            Some(SigningKey::RS256(key_pair)) => key_pair.sign(claims),
+            Some(SigningKey::ES256(key_pair)) => key_pair.sign(claims),
+            Some(SigningKey::EdDSA(key_pair)) => key_pair.sign(claims),
+            None => bail!("the JWT key `{}` can not be used for signing", self.key_id),
+        };
+        result.map_err(|err| Error::new(err.to_string()))
+    }
+
+    /// Verifies the token and returns the claims.
+    pub(crate) fn verify<T>(
+        &self,
+        token: &str,
+        options: Option<VerificationOptions>,
+    ) -> Result<JWTClaims<T>, Error>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let result = match &self.verifying_key {
+            VerifyingKey::RS256(public_key) => public_key.verify_token(token, options),
+            VerifyingKey::ES256(public_key) => public_key.verify_token(token, options),
+            VerifyingKey::EdDSA(public_key) => public_key.verify_token(token, options),
+        };
+        result.map_err(|err| Error::new(err.to_string()))
+    }
+
+    /// Returns the public key as a JSON Web Key defined in
+    /// [RFC 7517](https://www.rfc-editor.org/rfc/rfc7517).
+    pub fn to_jwk(&self) -> Map {
+        let mut jwk = Map::new();
+        match &self.verifying_key {
+            VerifyingKey::RS256(public_key) => {
+                let components = public_key.to_components();
+                jwk.upsert("kty", "RSA");
+                jwk.upsert("n", base64::encode_url_safe(components.n));
+                jwk.upsert("e", base64::encode_url_safe(components.e));
+            }
+            VerifyingKey::ES256(public_key) => {
+                // The uncompressed point is `0x04 || x || y`.
+                let bytes = public_key.to_bytes_uncompressed();
+                let (x, y) = bytes[1..].split_at(32);
+                jwk.upsert("kty", "EC");
+                jwk.upsert("crv", "P-256");
+                jwk.upsert("x", base64::encode_url_safe(x));
+                jwk.upsert("y", base64::encode_url_safe(y));
+            }
+            VerifyingKey::EdDSA(public_key) => {
+                jwk.upsert("kty", "OKP");
+                jwk.upsert("crv", "Ed25519");
+                jwk.upsert("x", base64::encode_url_safe(public_key.to_bytes()));
+            }
+        }
+        jwk.upsert("kid", self.key_id.clone());
+        jwk.upsert("alg", self.algorithm());
+        jwk.upsert("use", "sig");
+        jwk
+    }
+
+    /// Returns the shared JWT keys.
+    #[inline]
+    pub fn shared_keys() -> &'static [JwtKey] {
+        SHARED_JWT_KEYS.as_slice()
+    }
+
+    /// Returns the shared key for signing the tokens.
+    #[inline]
+    pub fn signing_key() -> Option<&'static JwtKey> {
+        *SIGNING_KEY
+    }
+
+    /// Gets the shared key with the key ID.
+    #[inline]
+    pub fn get(key_id: &str) -> Option<&'static JwtKey> {
+        SHARED_JWT_KEYS.iter().find(|key| key.key_id == key_id)
+    }
+
+    /// Returns the JSON Web Key Set of the shared keys, which can be served
+    /// as `/.well-known/jwks.json` for the verification of the tokens.
+    pub fn jwks() -> Map {
+        let keys = SHARED_JWT_KEYS
+            .iter()
+            .map(|key| JsonValue::Object(key.to_jwk()))
+            .collect::<Vec<_>>();
+        let mut jwks = Map::new();
+        jwks.upsert("keys", keys);
+        jwks
+    }
+}
+
+/// Shared JWT keys.
+static SHARED_JWT_KEYS: LazyLock<Vec<JwtKey>> = LazyLock::new(|| {
+    let mut keys = Vec::new();
+    if let Some(config) = State::shared().get_config("jwt")
+        && let Some(entries) = config.get_array("keys")
+    {
+        for entry in entries.iter().filter_map(|v| v.as_table()) {
+            match JwtKey::try_from_config(entry) {
+                Ok(key) => {
+                    tracing::info!(
+                        key_id = key.key_id(),
+                        algorithm = key.algorithm(),
+                        "the JWT key has been loaded"
+                    );
+                    keys.push(key);
+                }
+                Err(err) => panic!("fail to load the JWT key: {err}"),
+            }
+        }
+    }
+    keys
+});
+
+/// Shared JWT key for signing the tokens.
+static SIGNING_KEY: LazyLock<Option<&'static JwtKey>> = LazyLock::new(|| {
+    let keys = JwtKey::shared_keys();
+    if let Some(key_id) = State::shared()
+        .get_config("jwt")
+        .and_then(|config| config.get_str("signing-key"))
+    {
+        let Some(key) = keys.iter().find(|key| key.key_id == key_id) else {
+            panic!("the JWT signing key `{key_id}` does not exist");
+        };
+        if !key.can_sign() {
+            panic!("the JWT signing key `{key_id}` should have a `private-key`");
+        }
+        Some(key)
+    } else {
+        keys.iter().find(|key| key.can_sign())
+    }
+});
@@ -15,12 +15,16 @@
 //! [`totp-rs`]: https://crates.io/crates/totp-rs
 
 mod access_key;
+mod access_policy;
 mod authentication;
 mod authorization_provider;
 mod client_credentials;
 mod jwt_claims;
+mod jwt_key;
+mod revocation_store;
 mod security_token;
 mod session_id;
+mod session_store;
 mod user_session;
 
 #[cfg(feature = "auth-oauth2")]
@@ -33,12 +37,16 @@ pub(crate) use jwt_claims::{default_time_tolerance, default_verification_options
 pub(crate) use security_token::ParseSecurityTokenError;
 
 pub use access_key::{AccessKeyId, SecretAccessKey};
+pub use access_policy::{AccessPolicy, PolicyEffect, PolicyEngine, PolicyLoader};
 pub use authentication::Authentication;
 pub use authorization_provider::AuthorizationProvider;
 pub use client_credentials::ClientCredentials;
 pub use jwt_claims::{JwtClaims, JwtHmacKey};
+pub use jwt_key::JwtKey;
+pub use revocation_store::RevocationStore;
 pub use security_token::SecurityToken;
 pub use session_id::SessionId;
+pub use session_store::{GlobalSessionStore, MemorySessionStore, SessionData, SessionStore};
 pub use user_session::UserSession;
 
 #[cfg(feature = "auth-oauth2")]
@@ -46,3 +54,9 @@ pub use oauth2_client::OAuth2Client;
 
 #[cfg(feature = "auth-oidc")]
 pub use oidc_client::OidcClient;
+
+#[cfg(feature = "accessor")]
+pub use session_store::AccessorSessionStore;
+
+#[cfg(feature = "orm")]
+pub use session_store::DatabaseSessionStore;
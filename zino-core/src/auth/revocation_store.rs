@@ -0,0 +1,338 @@
+use crate::{datetime::DateTime, error::Error, extension::TomlTableExt, state::State};
+use parking_lot::RwLock;
+use std::{collections::HashMap, sync::LazyLock};
+
+#[cfg(feature = "accessor")]
+use crate::accessor::GlobalAccessor;
+
+#[cfg(feature = "orm")]
+use crate::orm::{ConnectionPool, GlobalConnection};
+
+/// A store for the revoked token IDs, which is used for the token revocation
+/// and the reuse detection of refresh tokens.
+///
+/// The store is specified by the `[jwt.revocation]` configuration:
+///
+/// ```toml
+/// [jwt.revocation]
+/// store = "accessor" # or "memory" | "database"
+/// name = "redis"
+/// ```
+///
+/// The `name` is the name of the connection pool for the `database` store,
+/// or the name of the storage accessor for the `accessor` store. It panics
+/// if the store is unsupported or does not exist, instead of falling back to
+/// the `memory` store which does not work across instances.
+pub enum RevocationStore {
+    /// In-memory store for a single instance.
+    Memory(RwLock<HashMap<String, DateTime>>),
+    /// Database table `zino_revoked_tokens` in the connection pool.
+    #[cfg(feature = "orm")]
+    Database(&'static ConnectionPool),
+    /// Key-value store backed by a storage accessor.
+    #[cfg(feature = "accessor")]
+    Accessor(&'static opendal::Operator),
+}
+
+impl RevocationStore {
+    /// Creates a new in-memory store.
+    #[inline]
+    pub fn with_memory() -> Self {
+        Self::Memory(RwLock::new(HashMap::new()))
+    }
+
+    /// Returns the shared revocation store.
+    #[inline]
+    pub fn shared() -> &'static Self {
+        LazyLock::force(&SHARED_REVOCATION_STORE)
+    }
+
+    /// Revokes the token ID until the expiration time.
+    pub async fn revoke(&self, token_id: &str, expires_at: DateTime) -> Result<(), Error> {
+        match self {
+            Self::Memory(tokens) => {
+                let now = DateTime::now();
+                let mut tokens = tokens.write();
+                tokens.retain(|_, expires_at| *expires_at > now);
+                tokens.insert(token_id.to_owned(), expires_at);
+            }
+            #[cfg(feature = "orm")]
+            Self::Database(connection_pool) => {
+                database::revoke(connection_pool, token_id, expires_at).await?;
+            }
+            #[cfg(feature = "accessor")]
+            Self::Accessor(operator) => {
+                let path = format!("{REVOCATION_PATH_PREFIX}{token_id}");
+                operator
+                    .write(&path, expires_at.timestamp().to_string())
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Revokes the token ID until the expiration time if it has not been revoked.
+    /// It returns `false` if the token ID has already been revoked.
+    ///
+    /// The check and the insertion are atomic for the `memory` and `database` stores.
+    /// For the `accessor` store, they are only serialized in the current process.
+    pub async fn try_revoke(&self, token_id: &str, expires_at: DateTime) -> Result<bool, Error> {
+        match self {
+            Self::Memory(tokens) => {
+                let now = DateTime::now();
+                let mut tokens = tokens.write();
+                tokens.retain(|_, expires_at| *expires_at > now);
+                if tokens.contains_key(token_id) {
+                    return Ok(false);
+                }
+                tokens.insert(token_id.to_owned(), expires_at);
+                Ok(true)
+            }
+            #[cfg(feature = "orm")]
+            Self::Database(connection_pool) => {
+                database::try_revoke(connection_pool, token_id, expires_at).await
+            }
+            #[cfg(feature = "accessor")]
+            Self::Accessor(_) => {
+                let _guard = ACCESSOR_REVOCATION_LOCK.lock().await;
+                if self.is_revoked(token_id).await? {
+                    return Ok(false);
+                }
+                self.revoke(token_id, expires_at).await?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Returns `true` if the token ID has been revoked and the revocation has not expired.
+    pub async fn is_revoked(&self, token_id: &str) -> Result<bool, Error> {
+        let expires_at = match self {
+            Self::Memory(tokens) => tokens.read().get(token_id).copied(),
+            #[cfg(feature = "orm")]
+            Self::Database(connection_pool) => {
+                database::get_expires_at(connection_pool, token_id).await?
+            }
+            #[cfg(feature = "accessor")]
+            Self::Accessor(operator) => {
+                let path = format!("{REVOCATION_PATH_PREFIX}{token_id}");
+                if operator.is_exist(&path).await? {
+                    let bytes = operator.read(&path).await?;
+                    let timestamp = String::from_utf8(bytes)?.parse()?;
+                    Some(DateTime::from_timestamp(timestamp))
+                } else {
+                    None
+                }
+            }
+        };
+        Ok(expires_at.is_some_and(|expires_at| expires_at > DateTime::now()))
+    }
+}
+
+/// Path prefix of the revoked tokens in the storage accessor.
+#[cfg(feature = "accessor")]
+const REVOCATION_PATH_PREFIX: &str = "zino/revoked-tokens/";
+
+/// A lock to serialize the revocations in the storage accessor.
+#[cfg(feature = "accessor")]
+static ACCESSOR_REVOCATION_LOCK: futures::lock::Mutex<()> = futures::lock::Mutex::new(());
+
+/// Shared revocation store.
+static SHARED_REVOCATION_STORE: LazyLock<RevocationStore> = LazyLock::new(|| {
+    let Some(config) = State::shared()
+        .get_config("jwt")
+        .and_then(|config| config.get_table("revocation"))
+    else {
+        return RevocationStore::with_memory();
+    };
+    let store = config.get_str("store").unwrap_or("memory");
+    let name = config.get_str("name").unwrap_or("main");
+    match store {
+        #[cfg(feature = "orm")]
+        "database" => {
+            let Some(connection_pool) = GlobalConnection::get(name) else {
+                panic!("the connection pool `{name}` for the revoked tokens does not exist");
+            };
+            RevocationStore::Database(connection_pool)
+        }
+        #[cfg(feature = "accessor")]
+        "accessor" => {
+            let Some(operator) = GlobalAccessor::get(name) else {
+                panic!("the storage accessor `{name}` for the revoked tokens does not exist");
+            };
+            RevocationStore::Accessor(operator)
+        }
+        "memory" => RevocationStore::with_memory(),
+        _ => panic!("unsupported revocation store `{store}`"),
+    }
+});
+
+#[cfg(feature = "orm")]
+mod database {
+    use crate::{
+        datetime::DateTime,
+        error::Error,
+        model::Query,
+        orm::{ConnectionPool, QueryExt},
+    };
+    use sqlx::Row;
+    use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+    /// Name of the table which stores the revoked tokens.
+    static REVOCATION_TABLE_NAME: &str = "zino_revoked_tokens";
+
+    /// A flag to indicate whether the revocation table has been created.
+    static REVOCATION_TABLE_CREATED: AtomicBool = AtomicBool::new(false);
+
+    /// Creates the revocation table if it does not exist.
+    async fn create_table(connection_pool: &ConnectionPool) -> Result<(), Error> {
+        if !REVOCATION_TABLE_CREATED.load(Relaxed) {
+            let sql = format!(
+                "CREATE TABLE IF NOT EXISTS {REVOCATION_TABLE_NAME} (\n  \
+                    token_id VARCHAR(255) PRIMARY KEY,\n  \
+                    expires_at BIGINT NOT NULL\n\
+                );"
+            );
+            sqlx::query(&sql).execute(connection_pool.pool()).await?;
+            REVOCATION_TABLE_CREATED.store(true, Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Inserts the token ID into the revocation table, and removes the expired ones.
+    pub(super) async fn revoke(
+        connection_pool: &ConnectionPool,
+        token_id: &str,
+        expires_at: DateTime,
+    ) -> Result<(), Error> {
+        create_table(connection_pool).await?;
+
+        let pool = connection_pool.pool();
+        let sql = format!(
+            "DELETE FROM {REVOCATION_TABLE_NAME} WHERE expires_at < {};",
+            Query::placeholder(1)
+        );
+        sqlx::query(&sql)
+            .bind(DateTime::current_timestamp())
+            .execute(pool)
+            .await?;
+
+        let placeholders = format!("{}, {}", Query::placeholder(1), Query::placeholder(2));
+        let sql = if cfg!(any(
+            feature = "orm-mariadb",
+            feature = "orm-mysql",
+            feature = "orm-tidb"
+        )) {
+            format!(
+                "INSERT INTO {REVOCATION_TABLE_NAME} (token_id, expires_at) \
+                    VALUES ({placeholders}) \
+                    ON DUPLICATE KEY UPDATE expires_at = VALUES(expires_at);"
+            )
+        } else {
+            format!(
+                "INSERT INTO {REVOCATION_TABLE_NAME} (token_id, expires_at) \
+                    VALUES ({placeholders}) \
+                    ON CONFLICT (token_id) DO UPDATE SET expires_at = excluded.expires_at;"
+            )
+        };
+        sqlx::query(&sql)
+            .bind(token_id)
+            .bind(expires_at.timestamp())
+            .execute(pool)
+            .await?;
+        connection_pool.record_write();
+        Ok(())
+    }
+
+    /// Inserts the token ID into the revocation table if it does not exist,
+    /// and returns `false` if no rows are affected.
+    pub(super) async fn try_revoke(
+        connection_pool: &ConnectionPool,
+        token_id: &str,
+        expires_at: DateTime,
+    ) -> Result<bool, Error> {
+        create_table(connection_pool).await?;
+
+        let pool = connection_pool.pool();
+        let sql = format!(
+            "DELETE FROM {REVOCATION_TABLE_NAME} \
+                WHERE token_id = {} AND expires_at < {};",
+            Query::placeholder(1),
+            Query::placeholder(2)
+        );
+        sqlx::query(&sql)
+            .bind(token_id)
+            .bind(DateTime::current_timestamp())
+            .execute(pool)
+            .await?;
+
+        let placeholders = format!("{}, {}", Query::placeholder(1), Query::placeholder(2));
+        let sql = if cfg!(any(
+            feature = "orm-mariadb",
+            feature = "orm-mysql",
+            feature = "orm-tidb"
+        )) {
+            format!(
+                "INSERT IGNORE INTO {REVOCATION_TABLE_NAME} (token_id, expires_at) \
+                    VALUES ({placeholders});"
+            )
+        } else {
+            format!(
+                "INSERT INTO {REVOCATION_TABLE_NAME} (token_id, expires_at) \
+                    VALUES ({placeholders}) ON CONFLICT (token_id) DO NOTHING;"
+            )
+        };
+        let query_result = sqlx::query(&sql)
+            .bind(token_id)
+            .bind(expires_at.timestamp())
+            .execute(pool)
+            .await?;
+        connection_pool.record_write();
+        Ok(query_result.rows_affected() > 0)
+    }
+
+    /// Gets the expiration time of the revoked token ID.
+    pub(super) async fn get_expires_at(
+        connection_pool: &ConnectionPool,
+        token_id: &str,
+    ) -> Result<Option<DateTime>, Error> {
+        create_table(connection_pool).await?;
+
+        let sql = format!(
+            "SELECT expires_at FROM {REVOCATION_TABLE_NAME} WHERE token_id = {};",
+            Query::placeholder(1)
+        );
+        let row = sqlx::query(&sql)
+            .bind(token_id)
+            .fetch_optional(connection_pool.pool())
+            .await?;
+        if let Some(row) = row {
+            let timestamp: i64 = row.try_get("expires_at")?;
+            Ok(Some(DateTime::from_timestamp(timestamp)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RevocationStore;
+    use crate::datetime::DateTime;
+    use futures::executor::block_on;
+    use std::time::Duration;
+
+    #[test]
+    fn it_revokes_tokens_in_memory() {
+        let store = RevocationStore::with_memory();
+        let expires_at = DateTime::now() + Duration::from_secs(60);
+        block_on(store.revoke("token-1", expires_at)).unwrap();
+        block_on(store.revoke("token-2", DateTime::now())).unwrap();
+        assert!(block_on(store.is_revoked("token-1")).unwrap());
+        assert!(!block_on(store.is_revoked("token-2")).unwrap());
+        assert!(!block_on(store.is_revoked("token-3")).unwrap());
+
+        assert!(block_on(store.try_revoke("token-3", expires_at)).unwrap());
+        assert!(!block_on(store.try_revoke("token-3", expires_at)).unwrap());
+        assert!(block_on(store.try_revoke("token-2", expires_at)).unwrap());
+    }
+}
@@ -0,0 +1,851 @@
+use crate::{datetime::DateTime, error::Error, extension::TomlTableExt, state::State, Map, Uuid};
+use cookie::{Cookie, SameSite};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::LazyLock, time::Duration};
+
+#[cfg(feature = "accessor")]
+use crate::{accessor::GlobalAccessor, bail};
+
+#[cfg(feature = "orm")]
+use crate::orm::{ConnectionPool, GlobalConnection};
+
+/// Server-side session data with a sliding idle timeout and an absolute lifetime limit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionData {
+    /// Session ID.
+    session_id: String,
+    /// User ID.
+    user_id: String,
+    /// Custom data.
+    data: Map,
+    /// Creation time.
+    created_at: DateTime,
+    /// Last access time.
+    last_accessed_at: DateTime,
+    /// Expiration time.
+    expires_at: DateTime,
+}
+
+impl SessionData {
+    /// Creates a new session for the user with a random session ID.
+    pub fn new(user_id: impl ToString) -> Self {
+        let now = DateTime::now();
+        let mut session = Self {
+            session_id: Uuid::new_v4().simple().to_string(),
+            user_id: user_id.to_string(),
+            data: Map::new(),
+            created_at: now,
+            last_accessed_at: now,
+            expires_at: now,
+        };
+        session.touch();
+        session
+    }
+
+    /// Returns `true` if the session ID is in the valid format, i.e. 32 hex digits.
+    #[inline]
+    pub fn is_valid_session_id(session_id: &str) -> bool {
+        session_id.len() == 32 && session_id.bytes().all(|b| b.is_ascii_hexdigit())
+    }
+
+    /// Returns the session ID.
+    #[inline]
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Returns the user ID.
+    #[inline]
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// Returns a reference to the custom data.
+    #[inline]
+    pub fn data(&self) -> &Map {
+        &self.data
+    }
+
+    /// Returns a mutable reference to the custom data.
+    #[inline]
+    pub fn data_mut(&mut self) -> &mut Map {
+        &mut self.data
+    }
+
+    /// Returns the creation time.
+    #[inline]
+    pub fn created_at(&self) -> DateTime {
+        self.created_at
+    }
+
+    /// Returns the last access time.
+    #[inline]
+    pub fn last_accessed_at(&self) -> DateTime {
+        self.last_accessed_at
+    }
+
+    /// Returns the expiration time.
+    #[inline]
+    pub fn expires_at(&self) -> DateTime {
+        self.expires_at
+    }
+
+    /// Returns `true` if the session has expired.
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= DateTime::now()
+    }
+
+    /// Records an access to the session. The expiration time slides to the idle timeout
+    /// after now, but it never exceeds the max lifetime since the creation.
+    pub fn touch(&mut self) {
+        let now = DateTime::now();
+        let idle_expires_at = now + *SESSION_IDLE_TIMEOUT;
+        let max_expires_at = self.created_at + *SESSION_MAX_LIFETIME;
+        self.last_accessed_at = now;
+        self.expires_at = idle_expires_at.min(max_expires_at);
+    }
+
+    /// Creates a session cookie which expires at the same time as the session.
+    pub fn to_cookie(&self) -> Cookie<'static> {
+        let mut cookie_builder =
+            Cookie::build((GlobalSessionStore::cookie_name(), self.session_id.clone()))
+                .http_only(true)
+                .secure(true)
+                .same_site(SameSite::Lax)
+                .path("/");
+        if let Some(max_age) = self
+            .expires_at
+            .span_after_now()
+            .and_then(|d| d.try_into().ok())
+        {
+            cookie_builder = cookie_builder.max_age(max_age);
+        }
+        cookie_builder.build()
+    }
+}
+
+/// A store for the server-side sessions.
+pub trait SessionStore {
+    /// Loads the session with the session ID. Expired sessions are not returned.
+    async fn load(&self, session_id: &str) -> Result<Option<SessionData>, Error>;
+
+    /// Saves the session.
+    async fn save(&self, session: &SessionData) -> Result<(), Error>;
+
+    /// Updates the last access time and the expiration time of an active session.
+    /// It returns `false` if the session does not exist or has expired,
+    /// and the session will never be inserted.
+    async fn touch(&self, session: &SessionData) -> Result<bool, Error>;
+
+    /// Removes the session with the session ID.
+    async fn remove(&self, session_id: &str) -> Result<(), Error>;
+
+    /// Lists the active sessions of the user.
+    async fn list_sessions(&self, user_id: &str) -> Result<Vec<SessionData>, Error>;
+
+    /// Removes all the sessions of the user, returning the number of removed sessions.
+    async fn remove_sessions(&self, user_id: &str) -> Result<usize, Error> {
+        let sessions = self.list_sessions(user_id).await?;
+        for session in sessions.iter() {
+            self.remove(session.session_id()).await?;
+        }
+        Ok(sessions.len())
+    }
+}
+
+/// In-memory session store for a single instance.
+#[derive(Debug, Default)]
+pub struct MemorySessionStore {
+    /// Sessions indexed by the session ID.
+    sessions: RwLock<HashMap<String, SessionData>>,
+}
+
+impl MemorySessionStore {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    async fn load(&self, session_id: &str) -> Result<Option<SessionData>, Error> {
+        let session = self.sessions.read().get(session_id).cloned();
+        Ok(session.filter(|session| !session.is_expired()))
+    }
+
+    async fn save(&self, session: &SessionData) -> Result<(), Error> {
+        let mut sessions = self.sessions.write();
+        sessions.retain(|_, session| !session.is_expired());
+        sessions.insert(session.session_id.clone(), session.clone());
+        Ok(())
+    }
+
+    async fn touch(&self, session: &SessionData) -> Result<bool, Error> {
+        let mut sessions = self.sessions.write();
+        if let Some(active_session) = sessions.get_mut(&session.session_id)
+            && !active_session.is_expired()
+        {
+            active_session.last_accessed_at = session.last_accessed_at;
+            active_session.expires_at = session.expires_at;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    async fn remove(&self, session_id: &str) -> Result<(), Error> {
+        self.sessions.write().remove(session_id);
+        Ok(())
+    }
+
+    async fn list_sessions(&self, user_id: &str) -> Result<Vec<SessionData>, Error> {
+        let sessions = self
+            .sessions
+            .read()
+            .values()
+            .filter(|session| session.user_id == user_id && !session.is_expired())
+            .cloned()
+            .collect();
+        Ok(sessions)
+    }
+}
+
+/// Session store backed by the table `zino_sessions` in a connection pool.
+#[cfg(feature = "orm")]
+#[derive(Debug, Clone, Copy)]
+pub struct DatabaseSessionStore {
+    /// Connection pool.
+    connection_pool: &'static ConnectionPool,
+}
+
+#[cfg(feature = "orm")]
+impl DatabaseSessionStore {
+    /// Creates a new instance with the connection pool.
+    #[inline]
+    pub fn new(connection_pool: &'static ConnectionPool) -> Self {
+        Self { connection_pool }
+    }
+}
+
+#[cfg(feature = "orm")]
+impl SessionStore for DatabaseSessionStore {
+    #[inline]
+    async fn load(&self, session_id: &str) -> Result<Option<SessionData>, Error> {
+        database::load(self.connection_pool, session_id).await
+    }
+
+    #[inline]
+    async fn save(&self, session: &SessionData) -> Result<(), Error> {
+        database::save(self.connection_pool, session).await
+    }
+
+    #[inline]
+    async fn touch(&self, session: &SessionData) -> Result<bool, Error> {
+        database::touch(self.connection_pool, session).await
+    }
+
+    #[inline]
+    async fn remove(&self, session_id: &str) -> Result<(), Error> {
+        database::remove(self.connection_pool, session_id).await
+    }
+
+    #[inline]
+    async fn list_sessions(&self, user_id: &str) -> Result<Vec<SessionData>, Error> {
+        database::list_sessions(self.connection_pool, user_id).await
+    }
+}
+
+/// Session store backed by a storage accessor, such as `redis` or `moka`.
+/// The session IDs of a user are indexed in a separate entry, whose updates are
+/// serialized in the current process.
+#[cfg(feature = "accessor")]
+#[derive(Debug, Clone, Copy)]
+pub struct AccessorSessionStore {
+    /// Storage operator.
+    operator: &'static opendal::Operator,
+}
+
+#[cfg(feature = "accessor")]
+impl AccessorSessionStore {
+    /// Creates a new instance with the storage operator.
+    #[inline]
+    pub fn new(operator: &'static opendal::Operator) -> Self {
+        Self { operator }
+    }
+
+    /// Reads the session IDs of the user.
+    async fn read_user_index(&self, user_id: &str) -> Result<Vec<String>, Error> {
+        let path = format!("zino/user-sessions/{user_id}");
+        if self.operator.is_exist(&path).await? {
+            let bytes = self.operator.read(&path).await?;
+            Ok(serde_json::from_slice(&bytes)?)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Writes the session IDs of the user.
+    async fn write_user_index(&self, user_id: &str, session_ids: &[String]) -> Result<(), Error> {
+        let path = format!("zino/user-sessions/{user_id}");
+        if session_ids.is_empty() {
+            self.operator.delete(&path).await?;
+        } else {
+            self.operator
+                .write(&path, serde_json::to_vec(session_ids)?)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "accessor")]
+impl SessionStore for AccessorSessionStore {
+    async fn load(&self, session_id: &str) -> Result<Option<SessionData>, Error> {
+        if !SessionData::is_valid_session_id(session_id) {
+            bail!("invalid session ID `{}`", session_id);
+        }
+
+        let path = format!("zino/sessions/{session_id}");
+        if self.operator.is_exist(&path).await? {
+            let bytes = self.operator.read(&path).await?;
+            let session = serde_json::from_slice::<SessionData>(&bytes)?;
+            Ok(Some(session).filter(|session| !session.is_expired()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn save(&self, session: &SessionData) -> Result<(), Error> {
+        let path = format!("zino/sessions/{}", session.session_id);
+        self.operator
+            .write(&path, serde_json::to_vec(session)?)
+            .await?;
+
+        let user_id = session.user_id.as_str();
+        let _guard = USER_INDEX_LOCK.lock().await;
+        let mut session_ids = self.read_user_index(user_id).await?;
+        if !session_ids.contains(&session.session_id) {
+            session_ids.push(session.session_id.clone());
+            self.write_user_index(user_id, &session_ids).await?;
+        }
+        Ok(())
+    }
+
+    async fn touch(&self, session: &SessionData) -> Result<bool, Error> {
+        let Some(mut active_session) = self.load(&session.session_id).await? else {
+            return Ok(false);
+        };
+        active_session.last_accessed_at = session.last_accessed_at;
+        active_session.expires_at = session.expires_at;
+
+        let path = format!("zino/sessions/{}", session.session_id);
+        self.operator
+            .write(&path, serde_json::to_vec(&active_session)?)
+            .await?;
+        Ok(true)
+    }
+
+    async fn remove(&self, session_id: &str) -> Result<(), Error> {
+        if let Some(session) = self.load(session_id).await? {
+            let user_id = session.user_id.as_str();
+            let _guard = USER_INDEX_LOCK.lock().await;
+            let mut session_ids = self.read_user_index(user_id).await?;
+            session_ids.retain(|id| id != session_id);
+            self.write_user_index(user_id, &session_ids).await?;
+        }
+        self.operator
+            .delete(&format!("zino/sessions/{session_id}"))
+            .await?;
+        Ok(())
+    }
+
+    async fn list_sessions(&self, user_id: &str) -> Result<Vec<SessionData>, Error> {
+        let session_ids = self.read_user_index(user_id).await?;
+        let mut sessions = Vec::with_capacity(session_ids.len());
+        for session_id in session_ids.iter() {
+            if let Some(session) = self.load(session_id).await? {
+                sessions.push(session);
+            }
+        }
+        if sessions.len() < session_ids.len() {
+            let expired_ids = session_ids
+                .iter()
+                .filter(|id| !sessions.iter().any(|session| &session.session_id == *id))
+                .collect::<Vec<_>>();
+
+            // Reads the index again to keep the sessions saved concurrently.
+            let _guard = USER_INDEX_LOCK.lock().await;
+            let mut active_ids = self.read_user_index(user_id).await?;
+            active_ids.retain(|id| !expired_ids.contains(&id));
+            self.write_user_index(user_id, &active_ids).await?;
+        }
+        Ok(sessions)
+    }
+}
+
+/// A lock to serialize the updates of the user index in the storage accessor.
+#[cfg(feature = "accessor")]
+static USER_INDEX_LOCK: futures::lock::Mutex<()> = futures::lock::Mutex::new(());
+
+/// Global session store specified by the `[session]` configuration:
+///
+/// ```toml
+/// [session]
+/// store = "accessor" # or "memory" | "database"
+/// name = "redis"
+/// cookie-name = "zino-session"
+/// idle-timeout = "30m"
+/// max-lifetime = "12h"
+/// ```
+///
+/// The `name` is the name of the connection pool for the `database` store,
+/// or the name of the storage accessor for the `accessor` store. It panics
+/// if the store is unsupported or does not exist, instead of falling back to
+/// the `memory` store which does not work across instances.
+pub enum GlobalSessionStore {
+    /// In-memory store.
+    Memory(MemorySessionStore),
+    /// Database store.
+    #[cfg(feature = "orm")]
+    Database(DatabaseSessionStore),
+    /// Storage accessor store.
+    #[cfg(feature = "accessor")]
+    Accessor(AccessorSessionStore),
+}
+
+impl GlobalSessionStore {
+    /// Returns the shared session store.
+    #[inline]
+    pub fn shared() -> &'static Self {
+        LazyLock::force(&SHARED_SESSION_STORE)
+    }
+
+    /// Returns the name of the session cookie.
+    #[inline]
+    pub fn cookie_name() -> &'static str {
+        *SESSION_COOKIE_NAME
+    }
+
+    /// Returns the idle timeout of the sessions.
+    #[inline]
+    pub fn idle_timeout() -> Duration {
+        *SESSION_IDLE_TIMEOUT
+    }
+
+    /// Returns `true` if the server-side sessions have been enabled.
+    #[inline]
+    pub fn is_enabled() -> bool {
+        State::shared().get_config("session").is_some()
+    }
+
+    /// Resumes an active session with the session ID in the cookie.
+    /// Invalid session IDs are rejected before accessing the store,
+    /// and errors of the store are logged and treated as the absence of the session.
+    ///
+    /// Nothing is written to the store, and the expiration time should be slid
+    /// by [`refresh()`](Self::refresh) after the request is handled.
+    pub async fn resume(&self, session_id: &str) -> Option<SessionData> {
+        if !SessionData::is_valid_session_id(session_id) {
+            return None;
+        }
+        match self.load(session_id).await {
+            Ok(session) => session,
+            Err(err) => {
+                tracing::error!(session_id, "fail to load the session: {err}");
+                None
+            }
+        }
+    }
+
+    /// Slides the expiration time of an active session. It returns `false`
+    /// if the session has been removed or has expired, so that it will not be resurrected.
+    pub async fn refresh(&self, session: &mut SessionData) -> bool {
+        session.touch();
+        match self.touch(session).await {
+            Ok(active) => active,
+            Err(err) => {
+                let session_id = session.session_id();
+                tracing::error!(session_id, "fail to refresh the session: {err}");
+                false
+            }
+        }
+    }
+}
+
+impl SessionStore for GlobalSessionStore {
+    async fn load(&self, session_id: &str) -> Result<Option<SessionData>, Error> {
+        match self {
+            Self::Memory(store) => store.load(session_id).await,
+            #[cfg(feature = "orm")]
+            Self::Database(store) => store.load(session_id).await,
+            #[cfg(feature = "accessor")]
+            Self::Accessor(store) => store.load(session_id).await,
+        }
+    }
+
+    async fn save(&self, session: &SessionData) -> Result<(), Error> {
+        match self {
+            Self::Memory(store) => store.save(session).await,
+            #[cfg(feature = "orm")]
+            Self::Database(store) => store.save(session).await,
+            #[cfg(feature = "accessor")]
+            Self::Accessor(store) => store.save(session).await,
+        }
+    }
+
+    async fn touch(&self, session: &SessionData) -> Result<bool, Error> {
+        match self {
+            Self::Memory(store) => store.touch(session).await,
+            #[cfg(feature = "orm")]
+            Self::Database(store) => store.touch(session).await,
+            #[cfg(feature = "accessor")]
+            Self::Accessor(store) => store.touch(session).await,
+        }
+    }
+
+    async fn remove(&self, session_id: &str) -> Result<(), Error> {
+        match self {
+            Self::Memory(store) => store.remove(session_id).await,
+            #[cfg(feature = "orm")]
+            Self::Database(store) => store.remove(session_id).await,
+            #[cfg(feature = "accessor")]
+            Self::Accessor(store) => store.remove(session_id).await,
+        }
+    }
+
+    async fn list_sessions(&self, user_id: &str) -> Result<Vec<SessionData>, Error> {
+        match self {
+            Self::Memory(store) => store.list_sessions(user_id).await,
+            #[cfg(feature = "orm")]
+            Self::Database(store) => store.list_sessions(user_id).await,
+            #[cfg(feature = "accessor")]
+            Self::Accessor(store) => store.list_sessions(user_id).await,
+        }
+    }
+}
+
+/// Shared session store.
+static SHARED_SESSION_STORE: LazyLock<GlobalSessionStore> = LazyLock::new(|| {
+    let memory_store = || GlobalSessionStore::Memory(MemorySessionStore::new());
+    let Some(config) = State::shared().get_config("session") else {
+        return memory_store();
+    };
+    let store = config.get_str("store").unwrap_or("memory");
+    let name = config.get_str("name").unwrap_or("main");
+    match store {
+        #[cfg(feature = "orm")]
+        "database" => {
+            let Some(connection_pool) = GlobalConnection::get(name) else {
+                panic!("the connection pool `{name}` for the sessions does not exist");
+            };
+            GlobalSessionStore::Database(DatabaseSessionStore::new(connection_pool))
+        }
+        #[cfg(feature = "accessor")]
+        "accessor" => {
+            let Some(operator) = GlobalAccessor::get(name) else {
+                panic!("the storage accessor `{name}` for the sessions does not exist");
+            };
+            GlobalSessionStore::Accessor(AccessorSessionStore::new(operator))
+        }
+        "memory" => memory_store(),
+        _ => panic!("unsupported session store `{store}`"),
+    }
+});
+
+/// Name of the session cookie.
+static SESSION_COOKIE_NAME: LazyLock<&'static str> = LazyLock::new(|| {
+    State::shared()
+        .get_config("session")
+        .and_then(|config| config.get_str("cookie-name"))
+        .unwrap_or("zino-session")
+});
+
+/// Idle timeout of the sessions.
+static SESSION_IDLE_TIMEOUT: LazyLock<Duration> = LazyLock::new(|| {
+    State::shared()
+        .get_config("session")
+        .and_then(|config| config.get_duration("idle-timeout"))
+        .unwrap_or_else(|| Duration::from_secs(60 * 30))
+});
+
+/// Max lifetime of the sessions.
+static SESSION_MAX_LIFETIME: LazyLock<Duration> = LazyLock::new(|| {
+    State::shared()
+        .get_config("session")
+        .and_then(|config| config.get_duration("max-lifetime"))
+        .unwrap_or_else(|| Duration::from_secs(60 * 60 * 12))
+});
+
+#[cfg(feature = "orm")]
+mod database {
+    use super::SessionData;
+    use crate::{
+        datetime::DateTime,
+        error::Error,
+        model::Query,
+        orm::{ConnectionPool, QueryExt},
+        JsonValue, Map,
+    };
+    use sqlx::Row;
+    use std::sync::atomic::{AtomicBool, AtomicI64, Ordering::Relaxed};
+
+    /// Interval in seconds for purging the expired sessions.
+    const SESSION_PURGE_INTERVAL: i64 = 60 * 10;
+
+    /// Timestamp when the expired sessions were purged last time.
+    static SESSIONS_PURGED_AT: AtomicI64 = AtomicI64::new(0);
+
+    /// Name of the table which stores the sessions.
+    static SESSION_TABLE_NAME: &str = "zino_sessions";
+
+    /// A flag to indicate whether the session table has been created.
+    static SESSION_TABLE_CREATED: AtomicBool = AtomicBool::new(false);
+
+    /// Columns of the session table.
+    static SESSION_COLUMNS: &str =
+        "session_id, user_id, data, created_at, last_accessed_at, expires_at";
+
+    /// Creates the session table if it does not exist.
+    async fn create_table(connection_pool: &ConnectionPool) -> Result<(), Error> {
+        if !SESSION_TABLE_CREATED.load(Relaxed) {
+            let sql = format!(
+                "CREATE TABLE IF NOT EXISTS {SESSION_TABLE_NAME} (\n  \
+                    session_id VARCHAR(255) PRIMARY KEY,\n  \
+                    user_id VARCHAR(255) NOT NULL,\n  \
+                    data TEXT,\n  \
+                    created_at BIGINT NOT NULL,\n  \
+                    last_accessed_at BIGINT NOT NULL,\n  \
+                    expires_at BIGINT NOT NULL\n\
+                );"
+            );
+            sqlx::query(&sql).execute(connection_pool.pool()).await?;
+            SESSION_TABLE_CREATED.store(true, Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Decodes a row of the session table.
+    fn decode_session(row: &crate::orm::DatabaseRow) -> Result<SessionData, Error> {
+        let data = row
+            .try_get::<Option<String>, _>("data")?
+            .map(|s| serde_json::from_str::<Map>(&s))
+            .transpose()?
+            .unwrap_or_default();
+        Ok(SessionData {
+            session_id: row.try_get("session_id")?,
+            user_id: row.try_get("user_id")?,
+            data,
+            created_at: DateTime::from_timestamp(row.try_get("created_at")?),
+            last_accessed_at: DateTime::from_timestamp(row.try_get("last_accessed_at")?),
+            expires_at: DateTime::from_timestamp(row.try_get("expires_at")?),
+        })
+    }
+
+    /// Loads an active session.
+    pub(super) async fn load(
+        connection_pool: &ConnectionPool,
+        session_id: &str,
+    ) -> Result<Option<SessionData>, Error> {
+        create_table(connection_pool).await?;
+
+        let sql = format!(
+            "SELECT {SESSION_COLUMNS} FROM {SESSION_TABLE_NAME} \
+                WHERE session_id = {} AND expires_at > {};",
+            Query::placeholder(1),
+            Query::placeholder(2),
+        );
+        let row = sqlx::query(&sql)
+            .bind(session_id)
+            .bind(DateTime::current_timestamp())
+            .fetch_optional(connection_pool.pool())
+            .await?;
+        row.as_ref().map(decode_session).transpose()
+    }
+
+    /// Removes the expired sessions if they have not been purged in the interval.
+    async fn purge_expired(connection_pool: &ConnectionPool) -> Result<(), Error> {
+        let now = DateTime::current_timestamp();
+        let purged_at = SESSIONS_PURGED_AT.load(Relaxed);
+        if now - purged_at < SESSION_PURGE_INTERVAL
+            || SESSIONS_PURGED_AT
+                .compare_exchange(purged_at, now, Relaxed, Relaxed)
+                .is_err()
+        {
+            return Ok(());
+        }
+
+        let sql = format!(
+            "DELETE FROM {SESSION_TABLE_NAME} WHERE expires_at <= {};",
+            Query::placeholder(1)
+        );
+        sqlx::query(&sql)
+            .bind(now)
+            .execute(connection_pool.pool())
+            .await?;
+        Ok(())
+    }
+
+    /// Saves the session, and removes the expired ones periodically.
+    pub(super) async fn save(
+        connection_pool: &ConnectionPool,
+        session: &SessionData,
+    ) -> Result<(), Error> {
+        create_table(connection_pool).await?;
+        purge_expired(connection_pool).await?;
+
+        let pool = connection_pool.pool();
+
+        let placeholders = (1..=6)
+            .map(Query::placeholder)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mutations = ["data", "last_accessed_at", "expires_at"];
+        let sql = if cfg!(any(
+            feature = "orm-mariadb",
+            feature = "orm-mysql",
+            feature = "orm-tidb"
+        )) {
+            let mutations = mutations
+                .iter()
+                .map(|col| format!("{col} = VALUES({col})"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!(
+                "INSERT INTO {SESSION_TABLE_NAME} ({SESSION_COLUMNS}) VALUES ({placeholders}) \
+                    ON DUPLICATE KEY UPDATE {mutations};"
+            )
+        } else {
+            let mutations = mutations
+                .iter()
+                .map(|col| format!("{col} = excluded.{col}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!(
+                "INSERT INTO {SESSION_TABLE_NAME} ({SESSION_COLUMNS}) VALUES ({placeholders}) \
+                    ON CONFLICT (session_id) DO UPDATE SET {mutations};"
+            )
+        };
+        sqlx::query(&sql)
+            .bind(session.session_id.as_str())
+            .bind(session.user_id.as_str())
+            .bind(JsonValue::from(session.data.clone()).to_string())
+            .bind(session.created_at.timestamp())
+            .bind(session.last_accessed_at.timestamp())
+            .bind(session.expires_at.timestamp())
+            .execute(pool)
+            .await?;
+        connection_pool.record_write();
+        Ok(())
+    }
+
+    /// Updates the access time of an active session without inserting it.
+    pub(super) async fn touch(
+        connection_pool: &ConnectionPool,
+        session: &SessionData,
+    ) -> Result<bool, Error> {
+        create_table(connection_pool).await?;
+
+        let sql = format!(
+            "UPDATE {SESSION_TABLE_NAME} SET last_accessed_at = {}, expires_at = {} \
+                WHERE session_id = {} AND expires_at > {};",
+            Query::placeholder(1),
+            Query::placeholder(2),
+            Query::placeholder(3),
+            Query::placeholder(4),
+        );
+        let query_result = sqlx::query(&sql)
+            .bind(session.last_accessed_at.timestamp())
+            .bind(session.expires_at.timestamp())
+            .bind(session.session_id.as_str())
+            .bind(DateTime::current_timestamp())
+            .execute(connection_pool.pool())
+            .await?;
+        connection_pool.record_write();
+        Ok(query_result.rows_affected() > 0)
+    }
+
+    /// Removes the session.
+    pub(super) async fn remove(
+        connection_pool: &ConnectionPool,
+        session_id: &str,
+    ) -> Result<(), Error> {
+        create_table(connection_pool).await?;
+
+        let sql = format!(
+            "DELETE FROM {SESSION_TABLE_NAME} WHERE session_id = {};",
+            Query::placeholder(1)
+        );
+        sqlx::query(&sql)
+            .bind(session_id)
+            .execute(connection_pool.pool())
+            .await?;
+        connection_pool.record_write();
+        Ok(())
+    }
+
+    /// Lists the active sessions of the user.
+    pub(super) async fn list_sessions(
+        connection_pool: &ConnectionPool,
+        user_id: &str,
+    ) -> Result<Vec<SessionData>, Error> {
+        create_table(connection_pool).await?;
+
+        let sql = format!(
+            "SELECT {SESSION_COLUMNS} FROM {SESSION_TABLE_NAME} \
+                WHERE user_id = {} AND expires_at > {} ORDER BY last_accessed_at DESC;",
+            Query::placeholder(1),
+            Query::placeholder(2),
+        );
+        let rows = sqlx::query(&sql)
+            .bind(user_id)
+            .bind(DateTime::current_timestamp())
+            .fetch_all(connection_pool.pool())
+            .await?;
+        rows.iter().map(decode_session).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemorySessionStore, SessionData, SessionStore};
+    use futures::executor::block_on;
+
+    #[test]
+    fn it_manages_sessions_in_memory() {
+        let store = MemorySessionStore::new();
+        let mut session = SessionData::new("alice");
+        assert!(!session.is_expired());
+        assert!(session.expires_at() > session.created_at());
+
+        session.data_mut().insert("theme".to_owned(), "dark".into());
+        block_on(store.save(&session)).unwrap();
+        block_on(store.save(&SessionData::new("alice"))).unwrap();
+        block_on(store.save(&SessionData::new("bob"))).unwrap();
+
+        let loaded = block_on(store.load(session.session_id())).unwrap().unwrap();
+        assert_eq!(loaded.data().get("theme"), session.data().get("theme"));
+        assert_eq!(block_on(store.list_sessions("alice")).unwrap().len(), 2);
+
+        session.touch();
+        assert!(block_on(store.touch(&session)).unwrap());
+
+        block_on(store.remove(session.session_id())).unwrap();
+        assert!(block_on(store.load(session.session_id()))
+            .unwrap()
+            .is_none());
+        assert!(!block_on(store.touch(&session)).unwrap());
+        assert!(block_on(store.load(session.session_id()))
+            .unwrap()
+            .is_none());
+        assert!(SessionData::is_valid_session_id(session.session_id()));
+        assert!(!SessionData::is_valid_session_id(
+            "../../zino/user-sessions/alice"
+        ));
+        assert_eq!(block_on(store.remove_sessions("alice")).unwrap(), 1);
+        assert!(block_on(store.list_sessions("alice")).unwrap().is_empty());
+        assert_eq!(block_on(store.list_sessions("bob")).unwrap().len(), 1);
+    }
+}
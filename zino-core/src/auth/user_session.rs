@@ -2,7 +2,7 @@ use super::{AccessKeyId, JwtClaims, SessionId};
 use crate::{
     application::APP_DOMAIN, crypto::Digest, error::Error, extension::JsonObjectExt, warn,
 };
-use std::str::FromStr;
+use std::{fmt::Display, str::FromStr};
 
 /// Role-based user sessions.
 #[derive(Debug, Clone)]
@@ -247,3 +247,16 @@ impl<U, T> UserSession<U, String, T> {
         true
     }
 }
+
+impl<U: Display, T: Display> UserSession<U, String, T> {
+    /// Converts the user session into the one whose user ID and tenant ID are strings.
+    pub fn to_string_session(&self) -> UserSession<String> {
+        UserSession {
+            user_id: self.user_id.to_string(),
+            session_id: self.session_id.clone(),
+            access_key_id: self.access_key_id.clone(),
+            roles: self.roles.clone(),
+            tenant_id: self.tenant_id.as_ref().map(ToString::to_string),
+        }
+    }
+}
@@ -3,6 +3,7 @@ use crate::{
     datetime::{self, DateTime},
     helper, openapi, JsonValue, Map, Record, Uuid,
 };
+use csv::{ByteRecord, Writer};
 use std::{
     borrow::Cow,
     net::{AddrParseError, IpAddr, Ipv4Addr, Ipv6Addr},
@@ -185,6 +186,15 @@ pub trait JsonObjectExt {
     /// Serializes the map into a query string.
     fn to_query_string(&self) -> String;
 
+    /// Serializes the values of the fields as a CSV record, and appends it to the buffer.
+    /// The header record will be written before it if `with_headers` is `true`.
+    fn to_csv_record(
+        &self,
+        fields: &[String],
+        with_headers: bool,
+        buffer: Vec<u8>,
+    ) -> Result<Vec<u8>, csv::Error>;
+
     /// Consumes `self` and constructs an Avro record value.
     fn into_avro_record(self) -> Record;
 
@@ -563,6 +573,28 @@ impl JsonObjectExt for Map {
         serde_qs::to_string(&self).unwrap_or_default()
     }
 
+    fn to_csv_record(
+        &self,
+        fields: &[String],
+        with_headers: bool,
+        buffer: Vec<u8>,
+    ) -> Result<Vec<u8>, csv::Error> {
+        let mut wtr = Writer::from_writer(buffer);
+        if with_headers {
+            wtr.write_record(fields)?;
+        }
+
+        let num_fields = fields.len();
+        let mut record = ByteRecord::with_capacity(num_fields * 8, num_fields);
+        for field in fields {
+            let value = self.parse_string(field).unwrap_or("".into());
+            record.push_field(value.as_ref().as_bytes());
+        }
+        wtr.write_byte_record(&record)?;
+        wtr.flush()?;
+        wtr.into_inner().map_err(|err| err.into_error().into())
+    }
+
     fn into_avro_record(self) -> Record {
         let mut record = Record::with_capacity(self.len());
         for (field, value) in self.into_iter() {
@@ -632,4 +664,19 @@ mod tests {
             Some("alice")
         );
     }
+
+    #[test]
+    fn it_serializes_csv_records() {
+        let mut map = Map::new();
+        map.upsert("name", "alice, bob");
+        map.upsert("total", 2);
+
+        let fields = ["name".to_owned(), "total".to_owned()];
+        let buffer = map.to_csv_record(&fields, true, Vec::new()).unwrap();
+        let buffer = map.to_csv_record(&fields, false, buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "name,total\n\"alice, bob\",2\n\"alice, bob\",2\n"
+        );
+    }
 }
@@ -0,0 +1,225 @@
+use crate::{
+    extension::{JsonObjectExt, JsonValueExt},
+    validation::Validation,
+    JsonValue, Map,
+};
+
+/// Supported units for the date buckets.
+const DATE_BUCKET_UNITS: [&str; 6] = ["year", "month", "week", "day", "hour", "minute"];
+
+/// Reserved keys of the aggregation pipeline.
+const PIPELINE_KEYS: [&str; 12] = [
+    "group",
+    "group_by",
+    "sum",
+    "avg",
+    "min",
+    "max",
+    "count",
+    "count_distinct",
+    "having",
+    "order_by",
+    "sort_by",
+    "limit",
+];
+
+#[derive(Debug, Clone, Default)]
+/// An aggregation type for models.
+///
+/// ```rust,ignore
+/// use zino_core::{model::{Aggregation, Query}, orm::Schema};
+///
+/// let query = Query::new(json!({ "status": "Paid" }));
+/// let aggregation = Aggregation::new(json!({
+///     "$group": ["customer_id", "created_at|day"],
+///     "$sum": ["amount"],
+///     "$count_distinct": ["product_id"],
+///     "$having": { "amount_sum": { "$gt": 100 } },
+///     "order_by": ["amount_sum|desc"],
+/// }));
+/// let data = Order::aggregate::<Map>(&query, &aggregation).await?;
+/// ```
+pub struct Aggregation {
+    // Group fields with optional date bucket units.
+    groups: Vec<(String, Option<String>)>,
+    // Accumulators with the operator and the field.
+    accumulators: Vec<(String, String)>,
+    // Filters on the aggregated values.
+    having: Map,
+    // Sort order: `false` for ascending and `true` for descending.
+    sort_order: Vec<(String, bool)>,
+    // Limit.
+    limit: usize,
+}
+
+impl Aggregation {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(pipeline: impl Into<JsonValue>) -> Self {
+        let mut aggregation = Self::default();
+        if let Some(pipeline) = pipeline.into().as_object() {
+            let validation = aggregation.read_map(pipeline);
+            if !validation.is_success() {
+                tracing::warn!("invalid aggregation pipeline: {validation:?}");
+            }
+        }
+        aggregation
+    }
+
+    /// Updates the aggregation using the json object and returns the validation result.
+    #[must_use]
+    pub fn read_map(&mut self, data: &Map) -> Validation {
+        let mut validation = Validation::new();
+        for (key, value) in data.iter().filter(|(_, v)| !v.is_ignorable()) {
+            let key = key.trim_start_matches('$');
+            match key {
+                "group" | "group_by" => {
+                    if let Some(groups) = value.parse_str_array() {
+                        for group in groups {
+                            if let Some((field, unit)) = group.split_once('|') {
+                                if DATE_BUCKET_UNITS.contains(&unit) {
+                                    self.groups.push((field.to_owned(), Some(unit.to_owned())));
+                                } else {
+                                    let message = format!("unsupported date bucket `{unit}`");
+                                    validation.record("group", message);
+                                }
+                            } else {
+                                self.groups.push((group.to_owned(), None));
+                            }
+                        }
+                    }
+                }
+                "sum" | "avg" | "min" | "max" | "count" | "count_distinct" => {
+                    if let Some(fields) = value.parse_str_array() {
+                        for field in fields {
+                            self.accumulators.push((key.to_owned(), field.to_owned()));
+                        }
+                    }
+                }
+                "having" => {
+                    if let Some(having) = value.as_object() {
+                        for (field, value) in having {
+                            let value = if let Some(value) = value.as_str()
+                                && value.starts_with('$')
+                                && let Some((operator, value)) = value.split_once('.')
+                            {
+                                Map::from_entry(operator, value).into()
+                            } else {
+                                value.clone()
+                            };
+                            self.having.upsert(field, value);
+                        }
+                    }
+                }
+                "order_by" | "sort_by" => {
+                    if let Some(sort_order) = value.parse_str_array() {
+                        self.sort_order = sort_order
+                            .into_iter()
+                            .map(|s| {
+                                if let Some(sort) = s.strip_suffix("|asc") {
+                                    (sort.to_owned(), false)
+                                } else if let Some(sort) = s.strip_suffix("|desc") {
+                                    (sort.to_owned(), true)
+                                } else {
+                                    (s.to_owned(), true)
+                                }
+                            })
+                            .collect::<Vec<_>>();
+                    }
+                }
+                "limit" => {
+                    if let Some(result) = value.parse_usize() {
+                        match result {
+                            Ok(limit) => self.limit = limit,
+                            Err(err) => validation.record_fail("limit", err),
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        if self.groups.is_empty() && self.accumulators.is_empty() {
+            validation.record("group", "there should be at least one group or accumulator");
+        }
+        validation
+    }
+
+    /// Returns `true` if the key is reserved for the aggregation pipeline,
+    /// with or without the `$` prefix.
+    #[inline]
+    pub fn is_pipeline_key(key: &str) -> bool {
+        PIPELINE_KEYS.contains(&key.trim_start_matches('$'))
+    }
+
+    /// Adds a group field.
+    #[inline]
+    pub fn add_group(&mut self, field: impl Into<String>) {
+        self.groups.push((field.into(), None));
+    }
+
+    /// Adds a group field with a date bucket.
+    /// Supported units: `year` | `month` | `week` | `day` | `hour` | `minute`.
+    #[inline]
+    pub fn add_date_group(&mut self, field: impl Into<String>, unit: &str) {
+        if DATE_BUCKET_UNITS.contains(&unit) {
+            self.groups.push((field.into(), Some(unit.to_owned())));
+        }
+    }
+
+    /// Adds an accumulator for the field.
+    /// Supported operators: `sum` | `avg` | `min` | `max` | `count` | `count_distinct`.
+    #[inline]
+    pub fn add_accumulator(&mut self, operator: &str, field: impl Into<String>) {
+        self.accumulators.push((operator.to_owned(), field.into()));
+    }
+
+    /// Adds a filter on the aggregated value.
+    #[inline]
+    pub fn add_having(&mut self, key: impl Into<String>, value: impl Into<JsonValue>) {
+        self.having.upsert(key, value);
+    }
+
+    /// Sets the sort order.
+    #[inline]
+    pub fn set_sort_order(&mut self, field: impl Into<String>, descending: bool) {
+        let field = field.into();
+        self.sort_order.retain(|(s, _)| s != &field);
+        self.sort_order.push((field, descending));
+    }
+
+    /// Sets the limit.
+    #[inline]
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+
+    /// Returns a reference to the group fields with optional date bucket units.
+    #[inline]
+    pub fn groups(&self) -> &[(String, Option<String>)] {
+        self.groups.as_slice()
+    }
+
+    /// Returns a reference to the accumulators.
+    #[inline]
+    pub fn accumulators(&self) -> &[(String, String)] {
+        self.accumulators.as_slice()
+    }
+
+    /// Returns a reference to the filters on the aggregated values.
+    #[inline]
+    pub fn having(&self) -> &Map {
+        &self.having
+    }
+
+    /// Returns the sort order.
+    #[inline]
+    pub fn sort_order(&self) -> &[(String, bool)] {
+        &self.sort_order
+    }
+
+    /// Returns the limit.
+    #[inline]
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
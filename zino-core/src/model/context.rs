@@ -1,5 +1,8 @@
-use crate::{SharedString, Uuid};
-use std::time::Instant;
+use crate::{extension::TomlTableExt, state::State, SharedString, Uuid};
+use std::{
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
 
 /// Data associated with a query.
 #[derive(Debug, Clone)]
@@ -114,6 +117,30 @@ impl QueryContext {
         self.arguments().join(", ")
     }
 
+    /// Returns `true` if the execution time exceeds the `slow-query-threshold`
+    /// in the database configuration.
+    #[inline]
+    pub fn is_slow_query(&self) -> bool {
+        SLOW_QUERY_THRESHOLD.is_some_and(|threshold| self.start_time.elapsed() >= threshold)
+    }
+
+    /// Records a slow query with its bound arguments and the optional query plan.
+    #[inline]
+    pub fn record_slow_query(&self, plan: Option<&str>) {
+        let query_id = self.query_id().to_string();
+        let query = self.query();
+        let arguments = self.format_arguments();
+        let execution_time_millis = self.start_time().elapsed().as_millis();
+        tracing::warn!(
+            query_id,
+            query,
+            arguments,
+            execution_time_millis,
+            plan,
+            "slow query detected"
+        );
+    }
+
     /// Records an error message for the query.
     #[inline]
     pub fn record_error(&self, message: impl AsRef<str>) {
@@ -140,3 +167,10 @@ impl Default for QueryContext {
         Self::new()
     }
 }
+
+/// Threshold of the execution time for a slow query.
+static SLOW_QUERY_THRESHOLD: LazyLock<Option<Duration>> = LazyLock::new(|| {
+    State::shared()
+        .get_config("database")
+        .and_then(|config| config.get_duration("slow-query-threshold"))
+});
@@ -1,5 +1,6 @@
 use super::QueryContext;
 use crate::{
+    auth::UserSession,
     error::Error,
     model::{Model, Mutation, Query},
     Map,
@@ -15,6 +16,14 @@ pub trait ModelHooks: Model {
     /// Extension data.
     type Extension: Clone + Send + Sync + 'static = ();
 
+    /// Returns the user session in the extension data. It is used by the default controller
+    /// to enforce the access policies and tenant scopes, and to record the acting user
+    /// in the audit logs.
+    #[inline]
+    fn extension_session(_extension: &Self::Extension) -> Option<UserSession<String>> {
+        None
+    }
+
     /// A hook running before extracting the model data.
     #[inline]
     async fn before_extract() -> Result<(), Error> {
@@ -0,0 +1,72 @@
+/// An index declared on the model, which can span multiple columns.
+#[derive(Debug, Clone)]
+pub struct Index<'a> {
+    /// Optional custom index name.
+    name: Option<&'a str>,
+    /// Fields of the index.
+    fields: Vec<&'a str>,
+    /// A flag which indicates whether the index is unique.
+    unique: bool,
+    /// Predicate of a partial index.
+    filter: Option<&'a str>,
+}
+
+impl<'a> Index<'a> {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(fields: Vec<&'a str>) -> Self {
+        Self {
+            name: None,
+            fields,
+            unique: false,
+            filter: None,
+        }
+    }
+
+    /// Sets the index name.
+    #[inline]
+    pub fn set_name(&mut self, name: &'a str) {
+        self.name = (!name.is_empty()).then_some(name);
+    }
+
+    /// Sets the flag for the unique index.
+    #[inline]
+    pub fn set_unique(&mut self, unique: bool) {
+        self.unique = unique;
+    }
+
+    /// Sets the predicate of a partial index.
+    #[inline]
+    pub fn set_filter(&mut self, filter: &'a str) {
+        self.filter = (!filter.is_empty()).then_some(filter);
+    }
+
+    /// Returns the fields.
+    #[inline]
+    pub fn fields(&self) -> &[&'a str] {
+        &self.fields
+    }
+
+    /// Returns `true` if the index is unique.
+    #[inline]
+    pub fn is_unique(&self) -> bool {
+        self.unique
+    }
+
+    /// Returns the predicate of a partial index.
+    #[inline]
+    pub fn filter(&self) -> Option<&'a str> {
+        self.filter
+    }
+
+    /// Returns the index name for the table.
+    /// The default name is obtained by a concatenation of the table name and the fields.
+    pub fn index_name(&self, table_name: &str) -> String {
+        if let Some(name) = self.name {
+            name.to_owned()
+        } else {
+            let fields = self.fields.join("_");
+            format!("{table_name}_{fields}_index")
+        }
+    }
+}
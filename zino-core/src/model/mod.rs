@@ -2,9 +2,11 @@
 use crate::{validation::Validation, AvroValue, JsonValue, Map, Record};
 use serde::{de::DeserializeOwned, Serialize};
 
+mod aggregation;
 mod column;
 mod context;
 mod hook;
+mod index;
 mod mutation;
 mod query;
 mod reference;
@@ -14,9 +16,11 @@ mod translation;
 #[doc(no_inline)]
 pub use apache_avro::schema;
 
+pub use aggregation::Aggregation;
 pub use column::{Column, EncodeColumn};
 pub use context::QueryContext;
 pub use hook::ModelHooks;
+pub use index::Index;
 pub use mutation::Mutation;
 pub use query::Query;
 pub use reference::Reference;
@@ -1,4 +1,7 @@
 use crate::{
+    bail,
+    encoding::base64,
+    error::Error,
     extension::{JsonObjectExt, JsonValueExt},
     validation::Validation,
     JsonValue, Map, SharedString,
@@ -90,7 +93,40 @@ impl Query {
                         }
                     }
                 }
-                "populate" | "translate" | "show_deleted" | "validate_only" => {
+                "cursor" | "after" | "before" => {
+                    if let Some(cursor) = value.as_str() {
+                        match Self::decode_cursor(cursor) {
+                            Some(values) => {
+                                extra.remove("after");
+                                extra.remove("before");
+                                if key == "before" {
+                                    extra.upsert("before", values);
+                                } else {
+                                    extra.upsert("after", values);
+                                }
+                            }
+                            None => validation.record(key.to_owned(), "invalid cursor"),
+                        }
+                    }
+                }
+                "populate" => {
+                    if let Some(flag) = value.as_bool() {
+                        extra.upsert(key, flag);
+                    } else if let Some(paths) = value.as_str() {
+                        if let Ok(flag) = paths.parse::<bool>() {
+                            extra.upsert(key, flag);
+                        } else {
+                            extra.upsert(key, true);
+                            extra.upsert("populate_paths", Self::parse_populate_paths(paths));
+                        }
+                    } else if let Some(paths) = value.parse_str_array() {
+                        extra.upsert(key, true);
+                        extra.upsert("populate_paths", paths);
+                    } else {
+                        validation.record(key.to_owned(), "invalid populate paths");
+                    }
+                }
+                "translate" | "show_deleted" | "validate_only" => {
                     if let Some(result) = value.parse_bool() {
                         match result {
                             Ok(flag) => {
@@ -127,9 +163,65 @@ impl Query {
         if let Some(current_page) = pagination_current_page {
             self.offset = self.limit * current_page.saturating_sub(1);
         }
+        if self.cursor().is_some() {
+            self.offset = 0;
+        }
         validation
     }
 
+    /// Parses the populate paths separated by commas.
+    /// The commas in the parentheses of projection fields are not separators.
+    fn parse_populate_paths(paths: &str) -> Vec<&str> {
+        let mut populate_paths = Vec::new();
+        let mut depth = 0;
+        let mut start = 0;
+        for (index, c) in paths.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    populate_paths.push(paths[start..index].trim());
+                    start = index + 1;
+                }
+                _ => (),
+            }
+        }
+        populate_paths.push(paths[start..].trim());
+        populate_paths.retain(|path| !path.is_empty());
+        populate_paths
+    }
+
+    /// Decodes an opaque cursor as the values of the sort fields.
+    fn decode_cursor(cursor: &str) -> Option<Vec<JsonValue>> {
+        let bytes = base64::decode_url_safe(cursor).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    /// Encodes an opaque cursor from the values of the sort fields and the primary key
+    /// in the model data. It fails if any of the fields is absent in the model data.
+    pub fn encode_cursor(&self, data: &Map, primary_key_name: &str) -> Result<String, Error> {
+        let mut fields = self
+            .sort_order
+            .iter()
+            .map(|(field, _)| field.as_ref() as &str)
+            .collect::<Vec<_>>();
+        if !fields.contains(&primary_key_name) {
+            fields.push(primary_key_name);
+        }
+
+        let mut values = Vec::with_capacity(fields.len());
+        for field in fields {
+            let Some(value) = data.get(field) else {
+                bail!(
+                    "the field `{}` for the cursor is absent in the model data",
+                    field
+                );
+            };
+            values.push(value.clone());
+        }
+        Ok(base64::encode_url_safe(JsonValue::from(values).to_string()))
+    }
+
     /// Parses the query expression with logical operators.
     fn parse_logical_query(expr: &str) -> Vec<Map> {
         let mut filters = Vec::new();
@@ -162,6 +254,16 @@ impl Query {
         }
     }
 
+    /// Adds a projection field if it is absent.
+    /// It has no effect if the projection fields are empty, i.e. all the fields are selected.
+    #[inline]
+    pub fn add_field(&mut self, field: impl Into<String>) {
+        let field = field.into();
+        if !self.fields.is_empty() && !self.fields.contains(&field) {
+            self.fields.push(field);
+        }
+    }
+
     /// Removes the projection fields in the deny list.
     #[inline]
     pub fn deny_fields(&mut self, fields: &[&str]) {
@@ -231,6 +333,19 @@ impl Query {
         self.limit
     }
 
+    /// Returns the cursor values and a flag indicating whether
+    /// the pagination is backward or not.
+    #[inline]
+    pub fn cursor(&self) -> Option<(&[JsonValue], bool)> {
+        if let Some(values) = self.extra.get_array("after") {
+            Some((values.as_slice(), false))
+        } else {
+            self.extra
+                .get_array("before")
+                .map(|values| (values.as_slice(), true))
+        }
+    }
+
     /// Returns `true` if the `flag` has been enabled.
     #[inline]
     pub fn enabled(&self, flag: &str) -> bool {
@@ -243,6 +358,18 @@ impl Query {
         self.enabled("populate")
     }
 
+    /// Returns the paths of the references to be populated.
+    /// A path consists of reference fields separated by `.`, each of which may be followed by
+    /// the projection fields of the populated model in parentheses,
+    /// e.g. `project_id(name,status).manager_id`.
+    #[inline]
+    pub fn populate_paths(&self) -> Vec<&str> {
+        self.extra
+            .get_array("populate_paths")
+            .map(|paths| paths.iter().filter_map(|path| path.as_str()).collect())
+            .unwrap_or_default()
+    }
+
     /// Returns `true` if the `translate` flag has been enabled.
     #[inline]
     pub fn translate_enabled(&self) -> bool {
@@ -250,9 +377,43 @@ impl Query {
     }
 
     /// Returns `true` if the `show_deleted` flag has been enabled.
+    /// The flag specified by the query parameters takes effect only if
+    /// it has been allowed by [`allow_show_deleted()`](Self::allow_show_deleted).
     #[inline]
     pub fn show_deleted(&self) -> bool {
-        self.enabled("show_deleted")
+        self.enabled("show_deleted") && self.enabled("show_deleted_allowed")
+    }
+
+    /// Allows the `show_deleted` flag specified by the query parameters,
+    /// which is an opt-in of the server to include the soft-deleted rows.
+    #[inline]
+    pub fn allow_show_deleted(&mut self) {
+        self.extra.upsert("show_deleted_allowed", true);
+    }
+
+    /// Sets the tenant ID used to constrain the models with a tenant scope.
+    #[inline]
+    pub fn set_tenant_id(&mut self, tenant_id: impl Into<JsonValue>) {
+        self.extra.upsert("tenant_id", tenant_id);
+    }
+
+    /// Returns the tenant ID used to constrain the models with a tenant scope.
+    #[inline]
+    pub fn tenant_id(&self) -> Option<&JsonValue> {
+        self.extra.get("tenant_id")
+    }
+
+    /// Disables the default scopes of models, i.e. the soft-delete scope and the tenant scope.
+    /// It can not be enabled by the query parameters.
+    #[inline]
+    pub fn disable_scopes(&mut self) {
+        self.extra.upsert("unscoped", true);
+    }
+
+    /// Returns `true` if the default scopes of models are enabled.
+    #[inline]
+    pub fn scopes_enabled(&self) -> bool {
+        !self.enabled("unscoped")
     }
 
     /// Returns `true` if the `validate_only` flag has been enabled.
@@ -275,3 +436,52 @@ impl Default for Query {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Query;
+    use crate::{extension::JsonObjectExt, Map};
+
+    #[test]
+    fn it_encodes_query_cursor() {
+        let mut query = Query::default();
+        query.set_sort_order("created_at", true);
+
+        let mut data = Map::new();
+        data.upsert("id", 10);
+        data.upsert("created_at", "2024-01-01T00:00:00Z");
+
+        let cursor = query.encode_cursor(&data, "id").unwrap();
+        let mut params = Map::new();
+        params.upsert("before", cursor);
+        assert!(query.read_map(&params).is_success());
+
+        let (values, backward) = query.cursor().unwrap();
+        assert!(backward);
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0], "2024-01-01T00:00:00Z");
+        assert_eq!(values[1], 10);
+
+        query.set_sort_order("updated_at", true);
+        assert!(query.encode_cursor(&data, "id").is_err());
+    }
+
+    #[test]
+    fn it_parses_populate_paths() {
+        let mut query = Query::default();
+        let mut params = Map::new();
+        params.upsert("populate", "tags(name,color), project_id.manager_id(name)");
+        assert!(query.read_map(&params).is_success());
+        assert!(query.populate_enabled());
+        assert_eq!(
+            query.populate_paths(),
+            ["tags(name,color)", "project_id.manager_id(name)"]
+        );
+
+        let mut query = Query::default();
+        params.upsert("populate", "true");
+        assert!(query.read_map(&params).is_success());
+        assert!(query.populate_enabled());
+        assert!(query.populate_paths().is_empty());
+    }
+}
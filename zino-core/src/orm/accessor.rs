@@ -1,14 +1,15 @@
-use super::{ModelHelper, Schema};
+use super::{AuditLog, DatabaseConnection, ModelHelper, Schema};
 use crate::{
     bail,
     datetime::DateTime,
     error::Error,
     extension::JsonObjectExt,
-    model::{ModelHooks, Mutation, Query},
+    model::{ModelHooks, Mutation, Query, QueryContext},
     validation::Validation,
     warn, JsonValue, Map,
 };
-use std::fmt::Display;
+use futures_timer::Delay;
+use std::{fmt::Display, time::Duration};
 
 /// Access model fields.
 ///
@@ -245,9 +246,12 @@ where
     }
 
     /// Constructs the `Query` for the model of the current version.
+    /// The default scopes are disabled since the model has been selected
+    /// by the primary key, so that a soft-deleted model can be restored.
     fn current_version_query(&self) -> Query {
         let mut query = Self::default_query();
         query.append_filters(&mut self.current_version_filters());
+        query.disable_scopes();
         query
     }
 
@@ -290,9 +294,12 @@ where
     }
 
     /// Constructs the `Query` for the model of the current edition.
+    /// The default scopes are disabled since the model has been selected
+    /// by the primary key, so that a soft-deleted model can be restored.
     fn current_edition_query(&self) -> Query {
         let mut query = Self::default_query();
         query.append_filters(&mut self.current_edition_filters());
+        query.disable_scopes();
         query
     }
 
@@ -385,9 +392,17 @@ where
         let mut models = Self::find(query).await?;
         let translate_enabled = query.translate_enabled();
         for model in models.iter_mut() {
+            Self::decrypt_model(model)?;
             Self::after_decode(model).await?;
             translate_enabled.then(|| Self::translate_model(model));
         }
+
+        let populate_paths = query.populate_paths();
+        if !populate_paths.is_empty() {
+            let tenant_id = query.tenant_id();
+            Self::populate_paths(&mut models, &populate_paths, translate_enabled, tenant_id)
+                .await?;
+        }
         Ok(models)
     }
 
@@ -396,19 +411,135 @@ where
         let mut model = Self::find_by_id::<Map>(id)
             .await?
             .ok_or_else(|| warn!("404 Not Found: cannot find the model `{}`", id))?;
+        Self::decrypt_model(&mut model)?;
         Self::after_decode(&mut model).await?;
         Self::translate_model(&mut model);
         Ok(model)
     }
 
+    /// Populates the references for the paths in the data of models.
+    /// It is implemented by `zino_derive::ModelAccessor` for the `reference` fields
+    /// and the `reverse_reference` relations. The tenant ID is used to constrain
+    /// the referenced models with a tenant scope.
+    async fn populate_paths(
+        _models: &mut Vec<Map>,
+        _paths: &[&str],
+        _translate_enabled: bool,
+        _tenant_id: Option<&JsonValue>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Returns the projection fields and the nested paths if the `field`
+    /// is the first segment of any of the populate paths.
+    fn parse_populate_paths<'a>(
+        paths: &[&'a str],
+        field: &str,
+    ) -> Option<(Vec<&'a str>, Vec<&'a str>)> {
+        let mut matched = false;
+        let mut fields = Vec::new();
+        let mut nested_paths = Vec::new();
+        for &path in paths {
+            let mut depth = 0;
+            let index = path.char_indices().find_map(|(index, c)| {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    '.' if depth == 0 => return Some(index),
+                    _ => (),
+                }
+                None
+            });
+            let (segment, nested_path) = match index {
+                Some(index) => (&path[..index], Some(&path[index + 1..])),
+                None => (path, None),
+            };
+            let (name, projection) = match segment.split_once('(') {
+                Some((name, projection)) => (name.trim(), projection.trim_end_matches(')')),
+                None => (segment.trim(), ""),
+            };
+            if name == field {
+                matched = true;
+                fields.extend(
+                    projection
+                        .split(',')
+                        .map(|s| s.trim())
+                        .filter(|s| !s.is_empty()),
+                );
+                if let Some(path) = nested_path
+                    && !path.is_empty()
+                {
+                    nested_paths.push(path);
+                }
+            }
+        }
+        matched.then_some((fields, nested_paths))
+    }
+
+    /// Takes the populated models in the `field` out of the data of models,
+    /// leaving empty placeholders which can be restored by [`restore_populated_models`].
+    ///
+    /// [`restore_populated_models`]: ModelAccessor::restore_populated_models
+    fn take_populated_models(models: &mut [Map], field: &str) -> Vec<Map> {
+        let mut populated_models = Vec::new();
+        for model in models.iter_mut() {
+            match model.get_mut(field) {
+                Some(JsonValue::Object(map)) => {
+                    populated_models.push(std::mem::take(map));
+                }
+                Some(JsonValue::Array(vec)) => {
+                    for value in vec.iter_mut() {
+                        if let JsonValue::Object(map) = value {
+                            populated_models.push(std::mem::take(map));
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        populated_models
+    }
+
+    /// Restores the populated models in the `field` for the data of models.
+    fn restore_populated_models(models: &mut [Map], field: &str, populated_models: Vec<Map>) {
+        let mut populated_models = populated_models.into_iter();
+        for model in models.iter_mut() {
+            match model.get_mut(field) {
+                Some(JsonValue::Object(map)) => {
+                    if let Some(populated_model) = populated_models.next() {
+                        *map = populated_model;
+                    }
+                }
+                Some(JsonValue::Array(vec)) => {
+                    for value in vec.iter_mut() {
+                        if let JsonValue::Object(map) = value
+                            && let Some(populated_model) = populated_models.next()
+                        {
+                            *map = populated_model;
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
     /// Deletes a model of the primary key by setting the status as `Deleted`.
     async fn soft_delete_by_id(id: &K) -> Result<(), Error> {
-        let mut model = Self::try_get_model(id).await?;
+        let mut conn = Self::acquire_writer().await?.pool().acquire().await?;
+        Self::soft_delete_by_id_with(&mut conn, id).await
+    }
+
+    /// Deletes a model of the primary key by setting the status as `Deleted`,
+    /// using the specific database connection.
+    async fn soft_delete_by_id_with(conn: &mut DatabaseConnection, id: &K) -> Result<(), Error> {
+        let mut model = Self::try_get_model_with(conn, id).await?;
         let model_data = model.before_soft_delete().await?;
 
         let query = model.current_version_query();
         let mut mutation = model.soft_delete_mutation();
-        let ctx = Self::update_one(&query, &mut mutation).await?;
+        let ctx = Self::update_one_with(conn, &query, &mut mutation).await?;
+        Self::check_version_conflict(id, &ctx)?;
         Self::after_soft_delete(&ctx, model_data).await?;
         Ok(())
     }
@@ -421,6 +552,7 @@ where
         let query = model.current_version_query();
         let mut mutation = model.lock_mutation();
         let ctx = Self::update_one(&query, &mut mutation).await?;
+        Self::check_version_conflict(id, &ctx)?;
         Self::after_lock(&ctx, model_data).await?;
         Ok(())
     }
@@ -433,19 +565,161 @@ where
         let query = model.current_version_query();
         let mut mutation = model.archive_mutation();
         let ctx = Self::update_one(&query, &mut mutation).await?;
+        Self::check_version_conflict(id, &ctx)?;
         Self::after_archive(&ctx, model_data).await?;
         Ok(())
     }
 
+    /// Returns a `409 Conflict` error if the update of the current version
+    /// does not affect any rows, i.e. the model has been modified concurrently.
+    ///
+    /// The check is skipped for the models without a `version` column,
+    /// since MySQL reports no rows affected if the update does not change any values.
+    fn check_version_conflict(id: &K, ctx: &QueryContext) -> Result<(), Error> {
+        if ctx.rows_affected() == Some(0) && Self::has_column("version") {
+            bail!(
+                "409 Conflict: the model `{}` has been modified by another request",
+                id
+            );
+        }
+        Ok(())
+    }
+
+    /// Updates a model of the primary key by applying the closure to the latest version,
+    /// which is retried for at most `max_retries` times on version conflicts
+    /// with an exponential back-off. The closure returns the updates for the model
+    /// or an error to abort the retries. The `before_update` and `after_update` hooks
+    /// are called in the same way as [`update_by_id`](Self::update_by_id).
+    async fn retry_on_conflict<F>(
+        id: &K,
+        max_retries: usize,
+        mut f: F,
+    ) -> Result<QueryContext, Error>
+    where
+        F: FnMut(&Self) -> Result<Map, Error>,
+    {
+        let mut retries = 0;
+        loop {
+            // The latest version is read from the writer to avoid the replication lag,
+            // and the connection is released before the back-off.
+            let mut conn = Self::acquire_writer().await?.pool().acquire().await?;
+            let mut model = Self::try_get_model_with(&mut conn, id).await?;
+            let mut updates = f(&model)?;
+            let query = model.current_version_query();
+            let mut mutation = model.next_version_mutation(&mut updates);
+            let model_data = model.before_update().await?;
+            let ctx = Self::update_one_with(&mut conn, &query, &mut mutation).await?;
+            drop(conn);
+            match Self::check_version_conflict(id, &ctx) {
+                Ok(()) => {
+                    Self::after_update(&ctx, model_data).await?;
+                    return Ok(ctx);
+                }
+                Err(err) if retries >= max_retries => return Err(err),
+                Err(_) => {
+                    retries += 1;
+                    tracing::warn!(retries, "retry the update for the model `{id}`");
+                    Delay::new(conflict_backoff_delay(retries)).await;
+                }
+            }
+        }
+    }
+
+    /// Creates an audit log for the action on a model of the primary key,
+    /// which captures the snapshot and the data of the model before the changes.
+    /// The audit table is also created if it does not exist.
+    async fn new_audit_log(id: &K, action: &'static str) -> Result<AuditLog, Error> {
+        AuditLog::create_table::<Self>().await?;
+
+        let model = Self::try_get_model(id).await?;
+        let mut audit_log = AuditLog::new(id, action);
+        audit_log.set_snapshot(model.snapshot());
+        audit_log.set_previous(model.into_map());
+        Ok(audit_log)
+    }
+
+    /// Restores a model of the primary key to a prior version by reverting the changes
+    /// in the audit logs. It returns the model before the restoration
+    /// and the updates which have been applied.
+    async fn restore_by_id(id: &K, version: u64) -> Result<(Self, Map), Error> {
+        let mut conn = Self::acquire_writer().await?.pool().acquire().await?;
+        Self::restore_by_id_with(&mut conn, id, version).await
+    }
+
+    /// Restores a model of the primary key to a prior version by reverting the changes
+    /// in the audit logs, using the specific database connection.
+    async fn restore_by_id_with(
+        conn: &mut DatabaseConnection,
+        id: &K,
+        version: u64,
+    ) -> Result<(Self, Map), Error> {
+        let mut model = Self::try_get_model_with(conn, id).await?;
+        if version >= model.version() {
+            bail!(
+                "409 Conflict: the version `{}` is not a prior version of the model `{}`",
+                version,
+                id
+            );
+        }
+
+        let mut updates = Map::new();
+        let mut restorable = false;
+        for audit_log in AuditLog::list::<Self>(&id.to_string()).await? {
+            let audit_version = audit_log.get_u64("version").unwrap_or_default();
+            if audit_version < version {
+                break;
+            }
+            if let Some(diff) = audit_log.get_object("diff") {
+                for (field, change) in diff {
+                    if let Some(value) = change.get("old") {
+                        updates.upsert(field, value.clone());
+                    }
+                }
+            }
+            restorable = audit_version == version;
+        }
+        if !restorable {
+            bail!(
+                "404 Not Found: cannot find the audit logs of the version `{}` for the model `{}`",
+                version,
+                id
+            );
+        }
+        for field in [Self::PRIMARY_KEY_NAME, "version", "updated_at"] {
+            updates.remove(field);
+        }
+        Self::decrypt_model(&mut updates)?;
+
+        let query = model.current_version_query();
+        let mut mutation = model.next_version_mutation(&mut updates.clone());
+        let model_data = model.before_update().await?;
+        let ctx = Self::update_one_with(conn, &query, &mut mutation).await?;
+        Self::check_version_conflict(id, &ctx)?;
+        Self::after_update(&ctx, model_data).await?;
+        Ok((model, updates))
+    }
+
     /// Updates a model of the primary key using the json object.
     async fn update_by_id(
         id: &K,
         data: &mut Map,
         extension: Option<<Self as ModelHooks>::Extension>,
+    ) -> Result<(Validation, Self), Error> {
+        let mut conn = Self::acquire_writer().await?.pool().acquire().await?;
+        Self::update_by_id_with(&mut conn, id, data, extension).await
+    }
+
+    /// Updates a model of the primary key using the json object,
+    /// using the specific database connection.
+    async fn update_by_id_with(
+        conn: &mut DatabaseConnection,
+        id: &K,
+        data: &mut Map,
+        extension: Option<<Self as ModelHooks>::Extension>,
     ) -> Result<(Validation, Self), Error> {
         Self::before_extract().await?;
 
-        let mut model = Self::try_get_model(id).await?;
+        let mut model = Self::try_get_model_with(conn, id).await?;
         if let Some(version) = data.get_u64("version")
             && model.version() != version
         {
@@ -481,8 +755,22 @@ where
         let mut mutation = model.next_version_mutation(data);
 
         let model_data = model.before_update().await?;
-        let ctx = Self::update_one(&query, &mut mutation).await?;
+        let ctx = Self::update_one_with(conn, &query, &mut mutation).await?;
+        Self::check_version_conflict(id, &ctx)?;
         Self::after_update(&ctx, model_data).await?;
         Ok((validation, model))
     }
 }
+
+/// Base delay of the exponential back-off for the retries on version conflicts.
+const CONFLICT_BASE_DELAY: Duration = Duration::from_millis(10);
+
+/// Returns the back-off delay before the retry on a version conflict.
+/// The delay is randomized up to `CONFLICT_BASE_DELAY * 2^retries`, which is capped
+/// at 64 times the base delay, so that the concurrent retries are spread out.
+fn conflict_backoff_delay(retries: usize) -> Duration {
+    let factor = 1u32 << retries.min(6);
+    CONFLICT_BASE_DELAY
+        .saturating_mul(factor)
+        .mul_f64(rand::random::<f64>())
+}
@@ -0,0 +1,292 @@
+//! Generates SQL expressions for aggregations.
+use super::{encryption, query::QueryExt, DatabaseDriver, Schema};
+use crate::{
+    extension::JsonObjectExt,
+    model::{Aggregation, Query},
+    JsonValue,
+};
+
+/// Extension trait for [`Aggregation`](crate::model::Aggregation).
+pub(super) trait AggregationExt<DB> {
+    /// Returns a list of the aliases and SQL expressions for the group fields.
+    fn group_expressions<M: Schema>(&self) -> Vec<(String, String)>;
+
+    /// Returns a list of the aliases and SQL expressions for the accumulators.
+    fn accumulator_expressions<M: Schema>(&self) -> Vec<(String, String)>;
+
+    /// Formats the projection fields to generate SQL `SELECT` expression.
+    fn format_projection<M: Schema>(&self) -> String;
+
+    /// Formats the groups to generate SQL `GROUP BY` and `HAVING` expressions.
+    fn format_groups<M: Schema>(&self) -> String;
+
+    /// Formats the sort order and limit to generate SQL `ORDER BY` and `LIMIT` expressions.
+    fn format_sort<M: Schema>(&self) -> String;
+}
+
+impl AggregationExt<DatabaseDriver> for Aggregation {
+    fn group_expressions<M: Schema>(&self) -> Vec<(String, String)> {
+        self.groups()
+            .iter()
+            .filter(|(field, _)| is_aggregatable::<M>(field))
+            .map(|(field, unit)| {
+                let alias = field.rsplit('.').next().unwrap_or(field).to_owned();
+                let field = Query::format_field(field);
+                let expr = if let Some(unit) = unit {
+                    Query::format_date_bucket(&field, unit)
+                } else {
+                    field.into_owned()
+                };
+                (alias, expr)
+            })
+            .collect()
+    }
+
+    fn accumulator_expressions<M: Schema>(&self) -> Vec<(String, String)> {
+        self.accumulators()
+            .iter()
+            .filter_map(|(operator, field)| {
+                if field == "*" {
+                    return (operator == "count")
+                        .then(|| ("count".to_owned(), "count(*)".to_owned()));
+                }
+                if !is_aggregatable::<M>(field) {
+                    return None;
+                }
+
+                let name = field.rsplit('.').next().unwrap_or(field);
+                let alias = format!("{name}_{operator}");
+                let field = Query::format_field(field);
+                let expr = match operator.as_str() {
+                    "sum" => format!("sum({field})"),
+                    "avg" => format!("avg({field})"),
+                    "min" => format!("min({field})"),
+                    "max" => format!("max({field})"),
+                    "count" => format!("count({field})"),
+                    "count_distinct" => format!("count(distinct {field})"),
+                    _ => return None,
+                };
+                Some((alias, expr))
+            })
+            .collect()
+    }
+
+    fn format_projection<M: Schema>(&self) -> String {
+        self.group_expressions::<M>()
+            .into_iter()
+            .chain(self.accumulator_expressions::<M>())
+            .map(|(alias, expr)| format!("{expr} AS {alias}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn format_groups<M: Schema>(&self) -> String {
+        let groups = self.group_expressions::<M>();
+        if groups.is_empty() {
+            return String::new();
+        }
+
+        let mut expression = format!(
+            "GROUP BY {}",
+            groups
+                .iter()
+                .map(|(_, expr)| expr.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        // PostgreSQL does not support the aliases in `HAVING`.
+        let accumulators = self.accumulator_expressions::<M>();
+        let mut conditions = Vec::new();
+        for (key, value) in self.having() {
+            let Some((_, expr)) = accumulators.iter().find(|(alias, _)| alias == key) else {
+                continue;
+            };
+            if let Some(filter) = value.as_object() {
+                for (name, value) in filter {
+                    let operator = match name.as_str() {
+                        "$eq" => "=",
+                        "$ne" => "<>",
+                        "$lt" => "<",
+                        "$le" => "<=",
+                        "$gt" => ">",
+                        "$ge" => ">=",
+                        _ => continue,
+                    };
+                    if let Some(value) = format_aggregated_value(value) {
+                        conditions.push(format!("{expr} {operator} {value}"));
+                    }
+                }
+            } else if let Some(value) = format_aggregated_value(value) {
+                conditions.push(format!("{expr} = {value}"));
+            }
+        }
+        if !conditions.is_empty() {
+            expression += &format!(" HAVING {}", conditions.join(" AND "));
+        }
+        expression
+    }
+
+    fn format_sort<M: Schema>(&self) -> String {
+        let aliases = self
+            .group_expressions::<M>()
+            .into_iter()
+            .chain(self.accumulator_expressions::<M>())
+            .map(|(alias, _)| alias)
+            .collect::<Vec<_>>();
+        let sort_order = self
+            .sort_order()
+            .iter()
+            .filter(|(sort, _)| aliases.contains(sort))
+            .map(|(sort, descending)| {
+                if *descending {
+                    format!("{sort} DESC")
+                } else {
+                    format!("{sort} ASC")
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut expression = String::new();
+        if !sort_order.is_empty() {
+            expression += &format!("ORDER BY {}", sort_order.join(", "));
+        }
+
+        let limit = self.limit();
+        if limit > 0 && limit != usize::MAX {
+            expression += &format!(" LIMIT {limit}");
+        }
+        expression
+    }
+}
+
+/// Returns `true` if the field can be grouped or accumulated in the aggregation.
+/// The write-only columns, the encrypted columns and their blind indexes are excluded,
+/// since they are hidden from the query results.
+fn is_aggregatable<M: Schema>(field: &str) -> bool {
+    if !M::has_column(field) {
+        return false;
+    }
+
+    let name = field.rsplit('.').next().unwrap_or(field);
+    if M::write_only_fields().contains(&name) {
+        return false;
+    }
+    !M::columns().iter().any(|col| {
+        encryption::is_encrypted(col)
+            && (col.name() == name || col.extra().get_str("blind_index") == Some(name))
+    })
+}
+
+/// Formats an aggregated value which is compared as a number if possible.
+fn format_aggregated_value(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::Number(value) => Some(value.to_string()),
+        JsonValue::String(value) => {
+            if value.parse::<f64>().is_ok_and(|v| v.is_finite()) {
+                Some(value.to_owned())
+            } else {
+                Some(Query::escape_string(value))
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AggregationExt;
+    use crate::{
+        bail,
+        error::Error,
+        model::{Aggregation, Column, Model, ModelHooks},
+        orm::{ConnectionPool, Schema},
+        Uuid,
+    };
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+    use std::sync::LazyLock;
+
+    #[derive(Debug, Default, Serialize, Deserialize)]
+    struct Account {
+        id: Uuid,
+        email: String,
+        email_hash: String,
+        password: String,
+        balance: i64,
+    }
+
+    impl Model for Account {}
+
+    impl ModelHooks for Account {}
+
+    impl Schema for Account {
+        const MODEL_NAME: &'static str = "account";
+
+        fn primary_key(&self) -> &Uuid {
+            &self.id
+        }
+
+        fn schema() -> &'static apache_avro::Schema {
+            static SCHEMA: LazyLock<apache_avro::Schema> =
+                LazyLock::new(|| apache_avro::Schema::Null);
+            &SCHEMA
+        }
+
+        fn columns() -> &'static [Column<'static>] {
+            static COLUMNS: LazyLock<Vec<Column<'static>>> = LazyLock::new(|| {
+                let mut email = Column::new("email", "String", false);
+                email.set_extra_attribute("encrypted", true);
+                email.set_extra_attribute("blind_index", "email_hash");
+                vec![
+                    Column::new("id", "Uuid", true),
+                    email,
+                    Column::new("email_hash", "String", false),
+                    Column::new("password", "String", false),
+                    Column::new("balance", "i64", false),
+                ]
+            });
+            &COLUMNS
+        }
+
+        fn fields() -> &'static [&'static str] {
+            &["id", "email", "email_hash", "password", "balance"]
+        }
+
+        fn read_only_fields() -> &'static [&'static str] {
+            &["id"]
+        }
+
+        fn write_only_fields() -> &'static [&'static str] {
+            &["password"]
+        }
+
+        async fn acquire_reader() -> Result<&'static ConnectionPool, Error> {
+            bail!("the connection pool is not available in tests");
+        }
+
+        async fn acquire_writer() -> Result<&'static ConnectionPool, Error> {
+            bail!("the connection pool is not available in tests");
+        }
+    }
+
+    #[test]
+    fn it_excludes_hidden_columns() {
+        let aggregation = Aggregation::new(json!({
+            "$group": ["id", "password", "email", "email_hash"],
+            "$max": ["balance", "password", "account.password"],
+            "$count_distinct": ["email", "email_hash"],
+            "$count": ["*"],
+        }));
+        let groups = aggregation.group_expressions::<Account>();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].0, "id");
+
+        let accumulators = aggregation.accumulator_expressions::<Account>();
+        let mut aliases = accumulators
+            .iter()
+            .map(|(alias, _)| alias.as_str())
+            .collect::<Vec<_>>();
+        aliases.sort_unstable();
+        assert_eq!(aliases, ["balance_max", "count"]);
+    }
+}
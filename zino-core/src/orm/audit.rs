@@ -0,0 +1,305 @@
+use super::{encryption, profile, query::QueryExt, DatabaseConnection, Schema};
+use crate::{
+    bail,
+    error::Error,
+    extension::JsonObjectExt,
+    model::{DecodeRow, Query},
+    JsonValue, Map, Uuid,
+};
+use futures::TryStreamExt;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+/// Name of the table which stores the audit logs.
+static AUDIT_TABLE_NAME: &str = "zino_audit_logs";
+
+/// A flag to indicate whether the audit table has been created.
+static AUDIT_TABLE_CREATED: AtomicBool = AtomicBool::new(false);
+
+/// An audit log which records the changes of a model
+/// for the models declared with `#[schema(audit)]`.
+///
+/// ```rust,ignore
+/// use zino_core::orm::{AuditLog, ModelAccessor};
+///
+/// let mut audit_log = User::new_audit_log(&id, "update").await?;
+/// audit_log.set_mutation(body.clone());
+/// audit_log.set_user_id(session.user_id());
+/// audit_log.set_request_id(req.request_id());
+/// User::update_by_id(&id, &mut body, None).await?;
+///
+/// audit_log.set_current(User::try_get_model(&id).await?.into_map());
+/// audit_log.record::<User>().await?;
+/// ```
+///
+/// The audit log can be written in the same transaction as the changes
+/// with [`record_with`](AuditLog::record_with):
+///
+/// ```rust,ignore
+/// let mut transaction = User::acquire_writer().await?.pool().begin().await?;
+/// User::update_by_id_with(&mut transaction, &id, &mut body, None).await?;
+///
+/// audit_log.set_current(User::try_get_model_with(&mut transaction, &id).await?.into_map());
+/// audit_log.record_with::<User>(&mut transaction).await?;
+/// transaction.commit().await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AuditLog {
+    /// Model ID.
+    model_id: String,
+    /// Action.
+    action: &'static str,
+    /// Version of the model before the changes.
+    version: u64,
+    /// User ID.
+    user_id: Option<String>,
+    /// Request ID.
+    request_id: Option<String>,
+    /// Snapshot of the model before the changes.
+    snapshot: Map,
+    /// Updates in the mutation.
+    mutation: Map,
+    /// Model data before the changes.
+    previous: Map,
+    /// Model data after the changes.
+    current: Map,
+}
+
+impl AuditLog {
+    /// Creates a new instance for the action on the model.
+    #[inline]
+    pub fn new(model_id: impl ToString, action: &'static str) -> Self {
+        Self {
+            model_id: model_id.to_string(),
+            action,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the snapshot of the model before the changes.
+    /// The version of the audit log is obtained from the snapshot.
+    #[inline]
+    pub fn set_snapshot(&mut self, snapshot: Map) {
+        self.version = snapshot.get_u64("version").unwrap_or_default();
+        self.snapshot = snapshot;
+    }
+
+    /// Sets the updates in the mutation.
+    /// The write-only fields will be excluded when the audit log is recorded.
+    #[inline]
+    pub fn set_mutation(&mut self, updates: Map) {
+        self.mutation = updates;
+    }
+
+    /// Sets the model data before the changes.
+    #[inline]
+    pub fn set_previous(&mut self, data: Map) {
+        self.previous = data;
+    }
+
+    /// Sets the model data after the changes.
+    #[inline]
+    pub fn set_current(&mut self, data: Map) {
+        self.current = data;
+    }
+
+    /// Sets the ID of the acting user.
+    #[inline]
+    pub fn set_user_id(&mut self, user_id: impl ToString) {
+        self.user_id = Some(user_id.to_string());
+    }
+
+    /// Sets the request ID.
+    #[inline]
+    pub fn set_request_id(&mut self, request_id: impl ToString) {
+        self.request_id = Some(request_id.to_string());
+    }
+
+    /// Returns the action.
+    #[inline]
+    pub fn action(&self) -> &str {
+        self.action
+    }
+
+    /// Returns the version of the model before the changes.
+    #[inline]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Computes the diff of the model data for the model `M`.
+    /// The write-only fields are excluded, and the values of the `encrypted` columns
+    /// are stored as the ciphertexts.
+    pub fn diff<M: Schema>(&self) -> Result<Map, Error> {
+        let mut diff = diff_changes(&self.previous, &self.current);
+        diff.retain(|field, _| !M::write_only_fields().contains(&field.as_str()));
+        for col in M::columns()
+            .iter()
+            .filter(|col| encryption::is_encrypted(col))
+        {
+            if let Some(JsonValue::Object(change)) = diff.get_mut(col.name()) {
+                for value in change.values_mut() {
+                    if let Some(s) = value.as_str()
+                        && !s.is_empty()
+                    {
+                        *value = encryption::encrypt_value(s)?.into();
+                    }
+                }
+            }
+        }
+        Ok(diff)
+    }
+
+    /// Creates the audit table if it does not exist.
+    pub async fn create_table<M: Schema>() -> Result<(), Error> {
+        if !AUDIT_TABLE_CREATED.load(Relaxed) {
+            let pool = M::acquire_writer().await?.pool();
+            let sql = format!(
+                "CREATE TABLE IF NOT EXISTS {AUDIT_TABLE_NAME} (\n  \
+                    id VARCHAR(36) PRIMARY KEY,\n  \
+                    model_name VARCHAR(255) NOT NULL,\n  \
+                    model_id VARCHAR(255) NOT NULL,\n  \
+                    action VARCHAR(255) NOT NULL,\n  \
+                    version BIGINT NOT NULL,\n  \
+                    user_id VARCHAR(255),\n  \
+                    request_id VARCHAR(255),\n  \
+                    snapshot TEXT,\n  \
+                    mutation TEXT,\n  \
+                    diff TEXT,\n  \
+                    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP\n\
+                );"
+            );
+            sqlx::query(&sql).execute(pool).await?;
+            AUDIT_TABLE_CREATED.store(true, Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Writes the audit log for the model `M` into the audit table.
+    pub async fn record<M: Schema>(&self) -> Result<(), Error> {
+        Self::create_table::<M>().await?;
+
+        let mut conn = M::acquire_writer().await?.pool().acquire().await?;
+        self.record_with::<M>(&mut conn).await
+    }
+
+    /// Writes the audit log for the model `M` into the audit table,
+    /// using the specific database connection.
+    ///
+    /// The audit table should have been created by [`create_table`](Self::create_table),
+    /// since a DDL statement commits the transaction implicitly in MySQL.
+    pub async fn record_with<M: Schema>(&self, conn: &mut DatabaseConnection) -> Result<(), Error> {
+        let diff = self.diff::<M>()?;
+        let mut mutation = self.mutation.clone();
+        mutation.retain(|field, _| !M::write_only_fields().contains(&field.as_str()));
+        encryption::encrypt_model::<M>(&mut mutation)?;
+
+        let placeholders = (1..=10)
+            .map(Query::placeholder)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!(
+            "INSERT INTO {AUDIT_TABLE_NAME} \
+                (id, model_name, model_id, action, version, user_id, request_id, \
+                    snapshot, mutation, diff) \
+                VALUES ({placeholders});"
+        );
+        let mut ctx = M::before_scan(&sql).await?;
+        super::scope::record_write(M::WRITER_NAME);
+        let query_result = sqlx::query(&sql)
+            .bind(Uuid::now_v7().to_string())
+            .bind(M::model_name())
+            .bind(self.model_id.as_str())
+            .bind(self.action)
+            .bind(i64::try_from(self.version)?)
+            .bind(self.user_id.as_deref())
+            .bind(self.request_id.as_deref())
+            .bind(JsonValue::from(self.snapshot.clone()).to_string())
+            .bind(JsonValue::from(mutation).to_string())
+            .bind(JsonValue::from(diff).to_string())
+            .execute(&mut *conn)
+            .await
+            .map_err(|err| profile::profile_error::<M>(&ctx, &sql, "audit", err))?;
+        ctx.set_query(sql);
+        ctx.set_query_result(Some(query_result.rows_affected()), true);
+        M::after_scan(&ctx).await?;
+        profile::profile_query::<M>(&ctx, "audit").await;
+        Ok(())
+    }
+
+    /// Lists the audit logs of a model in the descending order of versions.
+    pub async fn list<M: Schema>(model_id: &str) -> Result<Vec<Map>, Error> {
+        if !M::AUDIT {
+            bail!(
+                "404 Not Found: the audit logs are not enabled for the model `{}`",
+                M::model_name()
+            );
+        }
+
+        Self::create_table::<M>().await?;
+
+        let pool = M::acquire_reader().await?.pool();
+        let sql = format!(
+            "SELECT * FROM {AUDIT_TABLE_NAME} \
+                WHERE model_name = {} AND model_id = {} \
+                    ORDER BY version DESC, created_at DESC;",
+            Query::placeholder(1),
+            Query::placeholder(2),
+        );
+        let mut rows = sqlx::query(&sql)
+            .bind(M::model_name())
+            .bind(model_id)
+            .fetch(pool);
+        let mut audit_logs = Vec::new();
+        while let Some(row) = rows.try_next().await? {
+            let mut audit_log = Map::decode_row(&row)?;
+            for field in ["snapshot", "mutation", "diff"] {
+                if let Some(value) = audit_log.get_str(field) {
+                    let value = serde_json::from_str::<JsonValue>(value)?;
+                    audit_log.upsert(field, value);
+                }
+            }
+            audit_logs.push(audit_log);
+        }
+        Ok(audit_logs)
+    }
+}
+
+/// Computes the changes between the previous data and the current data.
+fn diff_changes(previous: &Map, current: &Map) -> Map {
+    let mut diff = Map::new();
+    for (field, old_value) in previous.iter() {
+        let new_value = current.get(field).unwrap_or(&JsonValue::Null);
+        if old_value != new_value {
+            let mut change = Map::with_capacity(2);
+            change.upsert("old", old_value.clone());
+            change.upsert("new", new_value.clone());
+            diff.upsert(field, change);
+        }
+    }
+    for (field, new_value) in current.iter() {
+        if !previous.contains_key(field) {
+            let mut change = Map::with_capacity(2);
+            change.upsert("old", JsonValue::Null);
+            change.upsert("new", new_value.clone());
+            diff.upsert(field, change);
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff_changes;
+    use serde_json::json;
+
+    #[test]
+    fn it_computes_diff_changes() {
+        let previous = json!({ "name": "alice", "status": "Active", "version": 1 });
+        let current = json!({ "name": "alice", "status": "Locked", "version": 2, "tags": [] });
+        let diff = diff_changes(previous.as_object().unwrap(), current.as_object().unwrap());
+        assert_eq!(diff.len(), 3);
+        assert_eq!(diff["status"], json!({ "old": "Active", "new": "Locked" }));
+        assert_eq!(diff["tags"], json!({ "old": null, "new": [] }));
+        assert!(!diff.contains_key("name"));
+    }
+}
@@ -0,0 +1,144 @@
+use super::{query::QueryExt, Schema};
+use crate::{
+    error::Error,
+    extension::TomlTableExt,
+    model::{Query, QueryContext},
+    state::State,
+    Map,
+};
+use parking_lot::RwLock;
+use std::{collections::HashMap, sync::LazyLock, time::Instant};
+
+/// Decoded rows of a query result in the cache.
+#[derive(Debug)]
+struct CacheEntry {
+    /// Decoded rows.
+    rows: Vec<Map>,
+    /// Expiration time.
+    expires_at: Instant,
+}
+
+/// Cached query results of a table.
+#[derive(Debug, Default)]
+struct TableCache {
+    /// Write generation which is increased whenever the table is modified.
+    generation: u64,
+    /// Cache entries keyed by the formatted SQL.
+    entries: HashMap<String, CacheEntry>,
+}
+
+/// Cached query results grouped by the table name.
+static QUERY_CACHE: LazyLock<RwLock<HashMap<&'static str, TableCache>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Max number of cached query results for each table.
+static QUERY_CACHE_CAPACITY: LazyLock<usize> = LazyLock::new(|| {
+    State::shared()
+        .get_config("database")
+        .and_then(|config| config.get_usize("query-cache-capacity"))
+        .unwrap_or(1000)
+});
+
+/// Formats the cache key of the query for the model `M` with the pagination.
+/// Returns `None` if the query cache is not enabled for the model.
+pub(super) fn format_query_key<M: Schema>(query: &Query, pagination: &str) -> Option<String> {
+    M::cache_ttl()?;
+
+    let table_name = query.format_table_name::<M>();
+    let projection = query.format_table_fields::<M>();
+    let filters = query.format_filters::<M>();
+    let sort = query.format_sort::<M>();
+    Some(format!(
+        "SELECT {projection} FROM {table_name} {filters} {sort} {pagination};"
+    ))
+}
+
+/// Returns the cached rows for the key if they have not expired.
+/// The hits and misses are emitted as metrics.
+pub(super) fn get_rows<M: Schema>(key: &str) -> Option<Vec<Map>> {
+    let rows = QUERY_CACHE
+        .read()
+        .get(M::table_name())
+        .and_then(|table| table.entries.get(key))
+        .filter(|entry| entry.expires_at > Instant::now())
+        .map(|entry| entry.rows.clone());
+    let result = if rows.is_some() { "hit" } else { "miss" };
+    metrics::increment_counter!(
+        "zino_model_query_cache_total",
+        "model" => M::model_name(),
+        "result" => result,
+    );
+    rows
+}
+
+/// Runs the query hooks of the model `M` for the cached rows,
+/// in the same way as the rows are fetched from the table.
+pub(super) async fn run_query_hooks<M: Schema>(
+    query: &Query,
+    key: &str,
+    num_rows: usize,
+) -> Result<(), Error> {
+    M::before_query(query).await?;
+
+    let mut ctx = QueryContext::new();
+    ctx.set_query(key);
+    ctx.set_query_result(Some(u64::try_from(num_rows)?), true);
+    M::after_query(&ctx).await
+}
+
+/// Returns the write generation of the table for the model `M`.
+/// It should be obtained before the rows are fetched from the table.
+pub(super) fn generation<M: Schema>() -> u64 {
+    QUERY_CACHE
+        .read()
+        .get(M::table_name())
+        .map_or(0, |table| table.generation)
+}
+
+/// Puts the rows into the cache with the time-to-live of the model `M`.
+/// The rows are discarded if the table has been modified since the write generation,
+/// which prevents a stale result from being cached after the invalidation.
+/// If the capacity is reached, the expired entries are removed first, and then
+/// the entry which will expire earliest is evicted.
+pub(super) fn put_rows<M: Schema>(key: String, rows: Vec<Map>, generation: u64) {
+    let Some(ttl) = M::cache_ttl() else {
+        return;
+    };
+
+    let now = Instant::now();
+    let mut cache = QUERY_CACHE.write();
+    let table = cache.entry(M::table_name()).or_default();
+    if table.generation != generation {
+        return;
+    }
+
+    let entries = &mut table.entries;
+    if entries.len() >= *QUERY_CACHE_CAPACITY {
+        entries.retain(|_, entry| entry.expires_at > now);
+        if entries.len() >= *QUERY_CACHE_CAPACITY
+            && let Some(key) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.expires_at)
+                .map(|(key, _)| key.clone())
+        {
+            entries.remove(&key);
+        }
+    }
+    entries.insert(
+        key,
+        CacheEntry {
+            rows,
+            expires_at: now + ttl,
+        },
+    );
+}
+
+/// Invalidates the cached query results for the table of the model `M`
+/// and increases the write generation.
+/// The entries are shared by all the models with the same table name.
+pub(super) fn invalidate<M: Schema>() {
+    let mut cache = QUERY_CACHE.write();
+    let table = cache.entry(M::table_name()).or_default();
+    table.generation += 1;
+    table.entries.clear();
+}
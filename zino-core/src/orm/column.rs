@@ -14,6 +14,9 @@ pub(super) trait ColumnExt {
 
     /// Returns the constraints.
     fn constraints(&self) -> Vec<String>;
+
+    /// Returns `true` if the column type is compatible with the one in the database.
+    fn is_compatible_type(&self, column_type: &str) -> bool;
 }
 
 impl<'a> ColumnExt for Column<'a> {
@@ -99,4 +102,47 @@ impl<'a> ColumnExt for Column<'a> {
         }
         constraints
     }
+
+    fn is_compatible_type(&self, column_type: &str) -> bool {
+        let expected_type = self.column_type().to_ascii_lowercase();
+        let actual_type = column_type.to_ascii_lowercase();
+        if cfg!(any(
+            feature = "orm-mariadb",
+            feature = "orm-mysql",
+            feature = "orm-tidb"
+        )) {
+            // MySQL reports `BOOLEAN` as `tinyint(1)` and may omit the display width.
+            let base_type = |s: &str| {
+                let base_type = s.split(['(', ' ']).next().unwrap_or_default().to_owned();
+                match base_type.as_str() {
+                    "boolean" | "bool" => "tinyint".to_owned(),
+                    "numeric" => "decimal".to_owned(),
+                    "integer" => "int".to_owned(),
+                    _ => base_type,
+                }
+            };
+            base_type(&expected_type) == base_type(&actual_type)
+                && expected_type.contains("unsigned") == actual_type.contains("unsigned")
+        } else if cfg!(feature = "orm-postgres") {
+            // PostgreSQL reports the internal type names in `udt_name`.
+            let expected_type = expected_type.split('(').next().unwrap_or_default().trim();
+            let expected_type = match expected_type {
+                "boolean" => "bool",
+                "bigint" | "bigserial" => "int8",
+                "int" | "integer" | "serial" => "int4",
+                "smallint" | "smallserial" => "int2",
+                "double precision" => "float8",
+                "real" => "float4",
+                "character varying" => "varchar",
+                "text[]" => "_text",
+                "uuid[]" => "_uuid",
+                "bigint[]" => "_int8",
+                "int[]" => "_int4",
+                _ => expected_type,
+            };
+            expected_type == actual_type
+        } else {
+            expected_type == actual_type
+        }
+    }
 }
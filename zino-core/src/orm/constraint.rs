@@ -0,0 +1,123 @@
+use super::Schema;
+use crate::{error::Error, validation::Validation};
+
+/// Prefix of the error message for a unique violation.
+const UNIQUE_VIOLATION_PREFIX: &str = "409 Conflict: the unique constraint is violated";
+
+/// Converts the database error into an [`Error`] for the model `M`.
+///
+/// A unique violation is matched against the unique indexes of the model,
+/// and the fields of the index are listed in the error message.
+pub(super) fn map_database_error<M: Schema>(err: sqlx::Error) -> Error {
+    if let sqlx::Error::Database(ref db_err) = err
+        && db_err.is_unique_violation()
+    {
+        let fields = unique_violation_fields::<M>(db_err.constraint(), db_err.message());
+        if fields.is_empty() {
+            Error::with_source(UNIQUE_VIOLATION_PREFIX, err)
+        } else {
+            let fields = fields
+                .iter()
+                .map(|field| format!("`{field}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let message = format!("{UNIQUE_VIOLATION_PREFIX} on the fields {fields}");
+            Error::with_source(message, err)
+        }
+    } else {
+        err.into()
+    }
+}
+
+/// Checks whether the error is a unique violation on some fields,
+/// and converts it into field-level validation errors.
+///
+/// ```rust,ignore
+/// use zino_core::orm::{self, Schema};
+///
+/// if let Err(err) = user.insert().await
+///     && let Some(validation) = orm::check_unique_violation(&err)
+/// {
+///     return Err(Rejection::bad_request(validation).context(&req).into());
+/// }
+/// ```
+pub fn check_unique_violation(err: &Error) -> Option<Validation> {
+    let fields = err
+        .message()
+        .strip_prefix(UNIQUE_VIOLATION_PREFIX)?
+        .strip_prefix(" on the fields ")?
+        .split(", ")
+        .map(|field| field.trim_matches('`'))
+        .collect::<Vec<_>>();
+    let mut validation = Validation::new();
+    if let [field] = fields.as_slice() {
+        validation.record(field.to_string(), "the value is not unique");
+    } else {
+        validation.record(fields.join("_"), "the composite values should be unique");
+    }
+    Some(validation)
+}
+
+/// Returns the fields of the unique index violated by the database operation.
+fn unique_violation_fields<M: Schema>(
+    constraint: Option<&str>,
+    message: &str,
+) -> Vec<&'static str> {
+    let table_name = M::table_name();
+    let matches_index_name = |index_name: &str| {
+        if let Some(constraint) = constraint {
+            constraint == index_name
+        } else {
+            // MySQL reports the index name in the message.
+            message.contains(&format!("'{index_name}'"))
+                || message.contains(&format!(".{index_name}'"))
+        }
+    };
+    if let Some(index) = M::indexes()
+        .iter()
+        .find(|index| index.is_unique() && matches_index_name(&index.index_name(table_name)))
+    {
+        return index.fields().to_vec();
+    }
+    if let Some(col) = M::columns().iter().find(|col| {
+        col.index_type() == Some("unique")
+            && matches_index_name(&format!("{table_name}_{}_index", col.name()))
+    }) {
+        return vec![col.name()];
+    }
+
+    // SQLite reports the columns in the message as `table.column`.
+    if let Some(columns) = message.strip_prefix("UNIQUE constraint failed: ") {
+        return columns
+            .split(", ")
+            .filter_map(|s| {
+                let field = s.rsplit_once('.').map(|(_, field)| field).unwrap_or(s);
+                M::fields().iter().find(|&&f| f == field).copied()
+            })
+            .collect();
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_unique_violation;
+    use crate::error::Error;
+
+    #[test]
+    fn it_checks_unique_violation() {
+        let err = Error::new(
+            "409 Conflict: the unique constraint is violated on the fields `namespace`, `status`",
+        );
+        let validation = check_unique_violation(&err).unwrap();
+        assert!(validation.contains_key("namespace_status"));
+
+        let err =
+            Error::new("409 Conflict: the unique constraint is violated on the fields `email`");
+        let validation = check_unique_violation(&err).unwrap();
+        assert!(validation.contains_key("email"));
+
+        let err = Error::new("409 Conflict: the unique constraint is violated");
+        assert!(check_unique_violation(&err).is_none());
+    }
+}
@@ -0,0 +1,199 @@
+use super::Schema;
+use crate::{
+    application, bail,
+    crypto::{self, Digest},
+    encoding::base64,
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    model::Column,
+    state::State,
+    warn, JsonValue, Map,
+};
+use hmac::{Hmac, Mac};
+use std::sync::{LazyLock, OnceLock};
+
+/// Prefix of the encrypted values.
+const ENCRYPTED_VALUE_PREFIX: &str = "enc:";
+
+/// Operators supported by the blind index.
+const BLIND_INDEX_OPERATORS: [&str; 4] = ["$eq", "$ne", "$in", "$nin"];
+
+/// Encrypts the value of a column with the `encrypted` attribute.
+///
+/// The ciphertext is prefixed with the current key ID in the form `enc:{kid}:{data}`,
+/// so that the values encrypted with a previous key can still be decrypted after rotation.
+pub fn encrypt_value(value: &str) -> Result<String, Error> {
+    let key_id = *ENCRYPTION_KEY_ID;
+    let key = encryption_key(key_id)?;
+    let data = crypto::encrypt(value.as_bytes(), &key)
+        .map_err(|err| warn!("fail to encrypt the column value: {}", err.message()))?;
+    Ok(format!(
+        "{ENCRYPTED_VALUE_PREFIX}{key_id}:{}",
+        base64::encode(data)
+    ))
+}
+
+/// Decrypts the value of a column with the `encrypted` attribute.
+/// Values without the `enc:` prefix are returned as they are.
+pub fn decrypt_value(value: &str) -> Result<String, Error> {
+    let Some(encrypted_value) = value.strip_prefix(ENCRYPTED_VALUE_PREFIX) else {
+        return Ok(value.to_owned());
+    };
+    let Some((key_id, data)) = encrypted_value.split_once(':') else {
+        bail!("the key ID of the encrypted value should be specified");
+    };
+    let key = encryption_key(key_id)?;
+    let data = base64::decode(data)?;
+    let plaintext = crypto::decrypt(&data, &key).map_err(|err| {
+        warn!(
+            "fail to decrypt the column value with the key `{}`: {}",
+            key_id,
+            err.message()
+        )
+    })?;
+    Ok(String::from_utf8(plaintext)?)
+}
+
+/// Computes the blind index of a value for the equality lookup of an encrypted column.
+///
+/// Unlike the ciphertext, the blind index is deterministic and does not depend on the key ID.
+pub fn blind_index(value: &str) -> Result<String, Error> {
+    let mut mac =
+        Hmac::<Digest>::new_from_slice(blind_index_key()?).expect("HMAC can take key of any size");
+    mac.update(value.as_bytes());
+    Ok(base64::encode(mac.finalize().into_bytes()))
+}
+
+/// Encrypts the values of the `encrypted` columns in the model data,
+/// and fills in the corresponding blind indexes.
+///
+/// The values are always treated as plaintext, even if they start with the `enc:` prefix.
+pub(super) fn encrypt_model<M: Schema>(model: &mut Map) -> Result<(), Error> {
+    for col in M::columns().iter().filter(|col| is_encrypted(col)) {
+        let field = col.name();
+        if let Some(value) = model.get_str(field)
+            && !value.is_empty()
+        {
+            let encrypted_value = encrypt_value(value)?;
+            if let Some(blind_index_field) = col.extra().get_str("blind_index") {
+                let blind_index_value = blind_index(value)?;
+                model.upsert(blind_index_field, blind_index_value);
+            }
+            model.upsert(field, encrypted_value);
+        }
+    }
+    Ok(())
+}
+
+/// Decrypts the values of the `encrypted` columns in the model data.
+pub(super) fn decrypt_model<M: Schema>(model: &mut Map) -> Result<(), Error> {
+    for col in M::columns().iter().filter(|col| is_encrypted(col)) {
+        let field = col.name();
+        if let Some(value) = model.get_str(field) {
+            let decrypted_value = decrypt_value(value)?;
+            model.upsert(field, decrypted_value);
+        }
+    }
+    Ok(())
+}
+
+/// Returns `true` if the column has an `encrypted` attribute.
+#[inline]
+pub(super) fn is_encrypted(col: &Column<'_>) -> bool {
+    col.extra().get_bool("encrypted") == Some(true)
+}
+
+/// Encodes the filter value as the blind index.
+/// Returns `None` if the filter can not be evaluated on the blind index.
+pub(super) fn encode_blind_index(value: &JsonValue) -> Option<JsonValue> {
+    match value {
+        JsonValue::String(value) => blind_index(value).ok().map(|s| s.into()),
+        JsonValue::Array(values) => values
+            .iter()
+            .map(|value| {
+                value
+                    .as_str()
+                    .and_then(|s| blind_index(s).ok())
+                    .map(|s| s.into())
+            })
+            .collect::<Option<Vec<JsonValue>>>()
+            .map(|values| values.into()),
+        JsonValue::Object(filter) => {
+            let mut encoded_filter = Map::new();
+            for (operator, value) in filter {
+                if !BLIND_INDEX_OPERATORS.contains(&operator.as_str()) {
+                    return None;
+                }
+                encoded_filter.upsert(operator, encode_blind_index(value)?);
+            }
+            Some(encoded_filter.into())
+        }
+        _ => None,
+    }
+}
+
+/// Derives the encryption key for the key ID.
+/// A configured key should have at least 32 bytes.
+fn encryption_key(key_id: &str) -> Result<[u8; 64], Error> {
+    let info = format!("ZINO:ORM:ENCRYPTION:{key_id}");
+    if let Some(checksum) = State::shared()
+        .get_config("database")
+        .and_then(|config| config.get_table("encryption-keys"))
+        .and_then(|keys| keys.get_str(key_id))
+    {
+        let Some(checksum) = checksum.as_bytes().first_chunk::<32>() else {
+            bail!(
+                "the encryption key `{}` should have at least 32 bytes",
+                key_id
+            );
+        };
+        Ok(crypto::derive_key(&info, checksum))
+    } else {
+        Ok(crypto::derive_key(&info, secret_key()?))
+    }
+}
+
+/// Derives the key for computing the blind indexes.
+fn blind_index_key() -> Result<&'static [u8], Error> {
+    if let Some(key) = BLIND_INDEX_KEY.get() {
+        return Ok(key.as_slice());
+    }
+
+    let key = crypto::derive_key("ZINO:ORM:BLIND-INDEX", secret_key()?);
+    Ok(BLIND_INDEX_KEY.get_or_init(|| key).as_slice())
+}
+
+/// Returns the application secret key.
+/// There is no fallback, so that the keys are the same in all the processes.
+fn secret_key() -> Result<&'static [u8], Error> {
+    application::SECRET_KEY
+        .get()
+        .map(|secret_key| secret_key.as_slice())
+        .ok_or_else(|| warn!("the application secret key has not been initialized"))
+}
+
+/// ID of the current encryption key.
+static ENCRYPTION_KEY_ID: LazyLock<&'static str> = LazyLock::new(|| {
+    State::shared()
+        .get_config("database")
+        .and_then(|config| config.get_str("encryption-key-id"))
+        .unwrap_or("v1")
+});
+
+/// Key for computing the blind indexes.
+static BLIND_INDEX_KEY: OnceLock<[u8; 64]> = OnceLock::new();
+
+#[cfg(test)]
+mod tests {
+    use super::encode_blind_index;
+    use serde_json::json;
+
+    #[test]
+    fn it_encodes_blind_index_filters() {
+        assert!(encode_blind_index(&json!({ "$gt": "alice" })).is_none());
+        assert!(encode_blind_index(&json!({ "$regex": "^alice" })).is_none());
+        assert!(encode_blind_index(&json!({ "$in": [1, 2] })).is_none());
+        assert!(encode_blind_index(&json!(true)).is_none());
+        assert!(encode_blind_index(&json!(1)).is_none());
+    }
+}
@@ -1,7 +1,7 @@
-use super::Schema;
+use super::{encryption, query::QueryExt, Schema};
 use crate::{
-    crypto, encoding::base64, error::Error, extension::TomlTableExt, openapi, state::State, warn,
-    Map,
+    bail, crypto, encoding::base64, error::Error, extension::TomlTableExt, model::Query, openapi,
+    state::State, warn, Map,
 };
 use std::{fmt::Display, sync::LazyLock};
 
@@ -21,16 +21,22 @@ where
         SECRET_KEY.as_slice()
     }
 
+    /// Returns `true` if the password has been hashed by the client,
+    /// i.e. it is a base64-encoded string of 256 bytes.
+    #[inline]
+    fn is_hashed_password(passowrd: &str) -> bool {
+        base64::decode(passowrd).is_ok_and(|bytes| bytes.len() == 256)
+    }
+
     /// Encrypts the password for the model.
     fn encrypt_password(passowrd: &str) -> Result<String, Error> {
         let key = Self::secret_key();
-        let passowrd = passowrd.as_bytes();
-        if let Ok(bytes) = base64::decode(passowrd)
-            && bytes.len() == 256
-        {
+        if Self::is_hashed_password(passowrd) {
+            let passowrd = passowrd.as_bytes();
             crypto::encrypt_hashed_password(passowrd, key)
                 .map_err(|err| warn!("fail to encrypt hashed password: {}", err.message()))
         } else {
+            let passowrd = passowrd.as_bytes();
             crypto::encrypt_raw_password(passowrd, key)
                 .map_err(|err| warn!("fail to encrypt raw password: {}", err.message()))
         }
@@ -39,11 +45,10 @@ where
     /// Verifies the password for the model.
     fn verify_password(passowrd: &str, encrypted_password: &str) -> Result<bool, Error> {
         let key = Self::secret_key();
+        let hashed = Self::is_hashed_password(passowrd);
         let passowrd = passowrd.as_bytes();
         let encrypted_password = encrypted_password.as_bytes();
-        if let Ok(bytes) = base64::decode(passowrd)
-            && bytes.len() == 256
-        {
+        if hashed {
             crypto::verify_hashed_password(passowrd, encrypted_password, key)
                 .map_err(|err| warn!("fail to verify hashed password: {}", err.message()))
         } else {
@@ -52,6 +57,28 @@ where
         }
     }
 
+    /// Checks that the cursor values of the query match the keyset order of the model,
+    /// i.e. the sort fields followed by the primary key as a tie-breaker.
+    fn check_cursor(query: &Query) -> Result<(), Error> {
+        if let Some((values, _)) = query.cursor() {
+            let num_keys = query.keyset_order::<Self>().len();
+            if values.len() != num_keys {
+                bail!(
+                    "the cursor has {} values while the sort order has {} keys",
+                    values.len(),
+                    num_keys
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Decrypts the values of the columns with the `encrypted` attribute in the model data.
+    #[inline]
+    fn decrypt_model(model: &mut Map) -> Result<(), Error> {
+        encryption::decrypt_model::<Self>(model)
+    }
+
     /// Translates the model data.
     #[inline]
     fn translate_model(model: &mut Map) {
@@ -67,7 +94,7 @@ where
 }
 
 /// Secret key.
-static SECRET_KEY: LazyLock<[u8; 64]> = LazyLock::new(|| {
+pub(super) static SECRET_KEY: LazyLock<[u8; 64]> = LazyLock::new(|| {
     let config = State::shared()
         .get_config("database")
         .expect("the `database` field should be a table");
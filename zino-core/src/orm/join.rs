@@ -0,0 +1,122 @@
+use super::{query::QueryExt, Schema};
+use crate::model::{Column, Query};
+
+/// A join to another model used in the [`Schema::join`] query.
+///
+/// ```rust,ignore
+/// use zino_core::{model::Query, orm::{JoinOn, Schema}};
+///
+/// let mut query = Query::new(json!({
+///     "project.status": "Active",
+///     "$or": [
+///         { "task.priority": { "$ge": 3 } },
+///         { "owner.name": { "$in": ["alice", "bob"] } },
+///     ],
+/// }));
+/// query.allow_fields(&[
+///     "task.id",
+///     "task.name",
+///     "project_name:project.name",
+///     "owner_name:owner.name",
+/// ]);
+/// let joins = [
+///     JoinOn::inner_join::<Project>().with("task.project_id", "project.id"),
+///     JoinOn::left_join::<User>()
+///         .with_alias("owner")
+///         .with("project.owner_id", "owner.id"),
+/// ];
+/// let data = Task::join::<Map>(&query, &joins).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct JoinOn {
+    /// Join type.
+    join_type: &'static str,
+    /// Table name.
+    table_name: &'static str,
+    /// Table alias.
+    alias: String,
+    /// Columns of the joined model.
+    columns: &'static [Column<'static>],
+    /// Pairs of the columns used in the `ON` expression.
+    conditions: Vec<(String, String)>,
+}
+
+impl JoinOn {
+    /// Constructs a new instance with the join type.
+    #[inline]
+    fn new<M: Schema>(join_type: &'static str) -> Self {
+        Self {
+            join_type,
+            table_name: M::table_name(),
+            alias: M::model_name().to_owned(),
+            columns: M::columns(),
+            conditions: Vec::new(),
+        }
+    }
+
+    /// Constructs an `INNER JOIN` to the model `M`.
+    #[inline]
+    pub fn inner_join<M: Schema>() -> Self {
+        Self::new::<M>("INNER JOIN")
+    }
+
+    /// Constructs a `LEFT OUTER JOIN` to the model `M`.
+    #[inline]
+    pub fn left_join<M: Schema>() -> Self {
+        Self::new::<M>("LEFT OUTER JOIN")
+    }
+
+    /// Sets the table alias. The default value is the model name.
+    #[inline]
+    pub fn with_alias(mut self, alias: impl Into<String>) -> Self {
+        self.alias = alias.into();
+        self
+    }
+
+    /// Adds a pair of columns which should be equal in the `ON` expression.
+    /// The columns should be prefixed with the model name or the table alias.
+    #[inline]
+    pub fn with(mut self, left_column: impl Into<String>, right_column: impl Into<String>) -> Self {
+        self.conditions
+            .push((left_column.into(), right_column.into()));
+        self
+    }
+
+    /// Returns the table alias.
+    #[inline]
+    pub fn alias(&self) -> &str {
+        &self.alias
+    }
+
+    /// Gets a column of the joined model for the field prefixed with the table alias.
+    pub fn get_column(&self, key: &str) -> Option<&'static Column<'static>> {
+        let (alias, field) = key.split_once('.')?;
+        if alias == self.alias {
+            self.columns.iter().find(|col| col.name() == field)
+        } else {
+            None
+        }
+    }
+
+    /// Formats the join to generate SQL `JOIN` expression.
+    pub(super) fn format_join(&self) -> String {
+        let join_type = self.join_type;
+        let table_name = Query::format_field(self.table_name);
+        let alias = Query::format_field(&self.alias);
+        let on_expressions = self
+            .conditions
+            .iter()
+            .map(|(left_col, right_col)| {
+                let left_col_field = Query::format_field(left_col);
+                let right_col_field = Query::format_field(right_col);
+                format!("{left_col_field} = {right_col_field}")
+            })
+            .collect::<Vec<_>>();
+        if on_expressions.is_empty() {
+            format!("{join_type} {table_name} {alias} ON TRUE")
+        } else {
+            let on_expressions = on_expressions.join(" AND ");
+            format!("{join_type} {table_name} {alias} ON {on_expressions}")
+        }
+    }
+}
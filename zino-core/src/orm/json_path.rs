@@ -0,0 +1,367 @@
+/// Filters and updates on the nested paths of JSON columns.
+use super::{query::QueryExt, Schema};
+use crate::{model::Query, JsonValue};
+
+/// Splits a dotted key into the JSON column of the model `M` and the nested path.
+/// The key can be prefixed by the model name or the table name.
+///
+/// Returns `None` if the key does not refer to a nested path of a `Map` column.
+pub(super) fn parse_json_path<'a, M: Schema>(key: &'a str) -> Option<(&'a str, Vec<&'a str>)> {
+    let (name, path) = key.split_once('.')?;
+    let (column_key, path) = if name == M::model_name() || name == M::table_name() {
+        let (field, path) = path.split_once('.')?;
+        (&key[..name.len() + field.len() + 1], path)
+    } else {
+        (name, path)
+    };
+    M::get_column(column_key).filter(|col| col.type_name() == "Map")?;
+
+    let path = path
+        .split('.')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+    (!path.is_empty()).then_some((column_key, path))
+}
+
+/// Returns `true` if the filter has the JSON operators `$contains` or `$has_key`,
+/// which should be formatted by [`format_json_filter`] for the whole column.
+pub(super) fn has_json_operators(value: &JsonValue) -> bool {
+    value
+        .as_object()
+        .is_some_and(|filter| filter.contains_key("$contains") || filter.contains_key("$has_key"))
+}
+
+/// Formats a filter on the nested path of a JSON column.
+/// An empty path refers to the column itself.
+pub(super) fn format_json_filter(column_key: &str, path: &[&str], value: &JsonValue) -> String {
+    let field = Query::format_field(column_key);
+    let Some(filter) = value.as_object() else {
+        return format_json_comparison(&field, path, "$eq", value);
+    };
+
+    let mut conditions = Vec::with_capacity(filter.len());
+    for (name, value) in filter {
+        let condition = match name.as_str() {
+            "$contains" => format_json_contains(&field, path, value),
+            "$has_key" => {
+                if let Some(keys) = value.as_array() {
+                    let conditions = keys
+                        .iter()
+                        .filter_map(|key| key.as_str())
+                        .map(|key| format_json_has_key(&field, path, key))
+                        .collect::<Vec<_>>();
+                    conditions.join(" AND ")
+                } else if let Some(key) = value.as_str() {
+                    format_json_has_key(&field, path, key)
+                } else {
+                    String::new()
+                }
+            }
+            operator => format_json_comparison(&field, path, operator, value),
+        };
+        if !condition.is_empty() {
+            conditions.push(condition);
+        }
+    }
+    if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("({})", conditions.join(" AND "))
+    }
+}
+
+/// Formats the expression to update the nested path of a JSON column
+/// for the mutation operators `$set`, `$unset` and `$push`.
+/// The `expr` is the current value of the column.
+///
+/// The intermediate objects of the path are not created if they do not exist,
+/// which is consistent with `jsonb_set()` and `json_set()`.
+pub(super) fn format_json_update(
+    expr: &str,
+    operator: &str,
+    path: &[&str],
+    value: &JsonValue,
+) -> String {
+    if cfg!(feature = "orm-postgres") {
+        let path_array = format_postgres_path_array(path);
+        match operator {
+            "$set" => {
+                let value = format_json_literal(value);
+                format!("jsonb_set({expr}, {path_array}, {value}, true)")
+            }
+            "$unset" => format!("({expr} #- {path_array})"),
+            "$push" => {
+                let value = format_json_literal(&JsonValue::Array(vec![value.clone()]));
+                format!(
+                    "jsonb_set({expr}, {path_array}, \
+                        coalesce({expr} #> {path_array}, '[]'::jsonb) || {value}, true)"
+                )
+            }
+            _ => expr.to_owned(),
+        }
+    } else {
+        let json_path = format_json_path(path);
+        match operator {
+            "$set" => {
+                let value = format_json_literal(value);
+                format!("json_set({expr}, {json_path}, {value})")
+            }
+            "$unset" => format!("json_remove({expr}, {json_path})"),
+            "$push" => {
+                let value = format_json_literal(value);
+                if cfg!(feature = "orm-sqlite") {
+                    let array_path = Query::escape_string(format!("{}[#]", path_to_string(path)));
+                    format!(
+                        "json_set(json_insert({expr}, {json_path}, json('[]')), \
+                            {array_path}, {value})"
+                    )
+                } else {
+                    format!(
+                        "json_set({expr}, {json_path}, json_merge_preserve(\
+                            coalesce(json_extract({expr}, {json_path}), json_array()), \
+                            json_array({value})))"
+                    )
+                }
+            }
+            _ => expr.to_owned(),
+        }
+    }
+}
+
+/// Returns the initial expression of a JSON column to be updated,
+/// where a `NULL` value is treated as an empty object.
+pub(super) fn format_json_column(column_key: &str) -> String {
+    let field = Query::format_field(column_key);
+    if cfg!(feature = "orm-postgres") {
+        format!("coalesce({field}, '{{}}'::jsonb)")
+    } else if cfg!(feature = "orm-sqlite") {
+        format!("coalesce({field}, '{{}}')")
+    } else {
+        format!("coalesce({field}, json_object())")
+    }
+}
+
+/// Formats a comparison between the scalar value at the path and the value.
+fn format_json_comparison(field: &str, path: &[&str], operator: &str, value: &JsonValue) -> String {
+    let operator = match operator {
+        "$eq" => "=",
+        "$ne" => "<>",
+        "$lt" => "<",
+        "$le" => "<=",
+        "$gt" => ">",
+        "$ge" => ">=",
+        "$in" => "IN",
+        "$nin" => "NOT IN",
+        "$like" => "LIKE",
+        _ => {
+            if cfg!(debug_assertions) {
+                tracing::warn!("unsupported operator `{operator}` for JSON paths");
+            }
+            return String::new();
+        }
+    };
+    if operator == "IN" || operator == "NOT IN" {
+        let Some(values) = value.as_array() else {
+            return String::new();
+        };
+        if values.is_empty() {
+            let condition = if operator == "IN" { "FALSE" } else { "TRUE" };
+            return condition.to_owned();
+        }
+
+        let expr = format_json_scalar(field, path, &values[0]);
+        let values = values
+            .iter()
+            .map(format_scalar_value)
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{expr} {operator} ({values})")
+    } else if value.is_null() {
+        let expr = format_json_scalar(field, path, value);
+        if operator == "=" {
+            format!("{expr} IS NULL")
+        } else {
+            format!("{expr} IS NOT NULL")
+        }
+    } else {
+        let expr = format_json_scalar(field, path, value);
+        let value = format_scalar_value(value);
+        format!("{expr} {operator} {value}")
+    }
+}
+
+/// Formats a condition that the JSON value at the path contains the value.
+/// For SQLite, an object is matched by its entries and other values are matched
+/// by the elements of the array.
+fn format_json_contains(field: &str, path: &[&str], value: &JsonValue) -> String {
+    if cfg!(feature = "orm-postgres") {
+        let expr = format_postgres_path(field, path, false);
+        let value = match value {
+            JsonValue::Object(_) | JsonValue::Array(_) => format_json_literal(value),
+            _ => format_json_literal(&JsonValue::Array(vec![value.clone()])),
+        };
+        format!("{expr} @> {value}")
+    } else if cfg!(feature = "orm-sqlite") {
+        match value {
+            JsonValue::Object(entries) => entries
+                .iter()
+                .map(|(key, value)| {
+                    let mut path = path.to_vec();
+                    path.push(key);
+                    format_json_comparison(field, &path, "$eq", value)
+                })
+                .collect::<Vec<_>>()
+                .join(" AND "),
+            JsonValue::Array(values) => values
+                .iter()
+                .map(|value| format_json_contains(field, path, value))
+                .collect::<Vec<_>>()
+                .join(" AND "),
+            _ => {
+                let json_path = format_json_path(path);
+                let value = format_scalar_value(value);
+                format!(
+                    "EXISTS (SELECT 1 FROM json_each({field}, {json_path}) WHERE value = {value})"
+                )
+            }
+        }
+    } else {
+        let value = format_json_literal(value);
+        let json_path = format_json_path(path);
+        format!("json_contains({field}, {value}, {json_path})")
+    }
+}
+
+/// Formats a condition that the JSON object at the path has the key.
+fn format_json_has_key(field: &str, path: &[&str], key: &str) -> String {
+    if cfg!(feature = "orm-postgres") {
+        let expr = format_postgres_path(field, path, false);
+        let key = Query::escape_string(key);
+        format!("({expr} ? {key})")
+    } else {
+        let mut path = path.to_vec();
+        path.push(key);
+
+        let json_path = format_json_path(&path);
+        if cfg!(feature = "orm-sqlite") {
+            format!("json_type({field}, {json_path}) IS NOT NULL")
+        } else {
+            format!("json_contains_path({field}, 'one', {json_path})")
+        }
+    }
+}
+
+/// Formats the expression to extract the scalar value at the path,
+/// which is comparable with the value.
+fn format_json_scalar(field: &str, path: &[&str], value: &JsonValue) -> String {
+    if cfg!(feature = "orm-postgres") {
+        let expr = format_postgres_path(field, path, true);
+        match value {
+            JsonValue::Number(_) => format!("({expr})::numeric"),
+            JsonValue::Bool(_) => format!("({expr})::boolean"),
+            _ => expr,
+        }
+    } else if cfg!(feature = "orm-sqlite") {
+        let json_path = format_json_path(path);
+        format!("json_extract({field}, {json_path})")
+    } else {
+        let json_path = format_json_path(path);
+        format!("json_unquote(json_extract({field}, {json_path}))")
+    }
+}
+
+/// Formats a scalar value to be compared with the value extracted from a JSON path.
+fn format_scalar_value(value: &JsonValue) -> String {
+    match value {
+        JsonValue::Number(value) => value.to_string(),
+        JsonValue::Bool(value) => {
+            if cfg!(feature = "orm-postgres") {
+                value.to_string().to_uppercase()
+            } else if cfg!(feature = "orm-sqlite") {
+                i32::from(*value).to_string()
+            } else {
+                Query::escape_string(value)
+            }
+        }
+        JsonValue::String(value) => Query::escape_string(value),
+        _ => Query::escape_string(value),
+    }
+}
+
+/// Formats a JSON literal of the value.
+fn format_json_literal(value: &JsonValue) -> String {
+    let value = Query::escape_string(value);
+    if cfg!(feature = "orm-postgres") {
+        format!("{value}::jsonb")
+    } else if cfg!(feature = "orm-sqlite") {
+        format!("json({value})")
+    } else {
+        format!("cast({value} AS json)")
+    }
+}
+
+/// Formats the path with the operators `->` and `->>` for PostgreSQL.
+/// The array elements are accessed by the integer segments.
+fn format_postgres_path(field: &str, path: &[&str], as_text: bool) -> String {
+    let mut expr = field.to_owned();
+    for (index, segment) in path.iter().enumerate() {
+        let operator = if as_text && index == path.len() - 1 {
+            "->>"
+        } else {
+            "->"
+        };
+        if segment.parse::<i32>().is_ok() {
+            expr = format!("{expr}{operator}{segment}");
+        } else {
+            expr = format!("{expr}{operator}{}", Query::escape_string(segment));
+        }
+    }
+    expr
+}
+
+/// Formats the path as a text array for the functions `jsonb_set()` and `#-` of PostgreSQL.
+fn format_postgres_path_array(path: &[&str]) -> String {
+    let segments = path
+        .iter()
+        .map(|segment| format!(r#""{}""#, segment.replace('"', r#"\""#)))
+        .collect::<Vec<_>>()
+        .join(",");
+    Query::escape_string(format!("{{{segments}}}"))
+}
+
+/// Formats the path as a JSON path string for MySQL and SQLite.
+fn format_json_path(path: &[&str]) -> String {
+    Query::escape_string(path_to_string(path))
+}
+
+/// Converts the path segments to a JSON path such as `$.items[0].name`.
+/// The keys with special characters are quoted.
+fn path_to_string(path: &[&str]) -> String {
+    let mut json_path = "$".to_owned();
+    for segment in path {
+        if segment.parse::<usize>().is_ok() {
+            json_path += &format!("[{segment}]");
+        } else if segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            json_path += &format!(".{segment}");
+        } else {
+            json_path += &format!(r#"."{}""#, segment.replace('"', r#"\""#));
+        }
+    }
+    json_path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::path_to_string;
+
+    #[test]
+    fn it_formats_json_paths() {
+        assert_eq!(path_to_string(&[]), "$");
+        assert_eq!(path_to_string(&["plan", "tier"]), "$.plan.tier");
+        assert_eq!(path_to_string(&["items", "0", "name"]), "$.items[0].name");
+        assert_eq!(path_to_string(&["user-agent"]), r#"$."user-agent""#);
+    }
+}
@@ -0,0 +1,707 @@
+use super::{column::ColumnExt, query::QueryExt, ConnectionPool, Schema};
+use crate::{
+    bail,
+    datetime::DateTime,
+    error::Error,
+    extension::JsonObjectExt,
+    model::{Column, DecodeRow, EncodeColumn, Index, Query},
+    JsonValue, Map,
+};
+use futures::TryStreamExt;
+use std::{
+    fs,
+    path::Path,
+    sync::atomic::{AtomicU32, Ordering::Relaxed},
+};
+
+/// Name of the table which tracks the applied migrations.
+static MIGRATION_TABLE_NAME: &str = "zino_migrations";
+
+/// Sequence number of the generated migrations.
+static MIGRATION_SEQUENCE: AtomicU32 = AtomicU32::new(0);
+
+/// A versioned migration with ordered up and down SQL statements.
+///
+/// ```rust,ignore
+/// use zino_core::orm::{Migration, Schema};
+///
+/// // Generates a migration by comparing the model columns with the table.
+/// let migration = Migration::diff::<User>(false).await?;
+/// migration.write_files("./migrations")?;
+///
+/// // Applies the pending migrations in the directory.
+/// let pool = User::acquire_writer().await?;
+/// let migrations = Migration::read_dir("./migrations")?;
+/// Migration::run_pending(pool, &migrations, false).await?;
+/// ```
+///
+/// A migration is identified by both the version and the name,
+/// so the migrations generated for different tables never collide.
+#[derive(Debug, Clone, Default)]
+pub struct Migration {
+    /// Version.
+    version: String,
+    /// Name.
+    name: String,
+    /// Statements for the upgrade.
+    up: Vec<String>,
+    /// Statements for the downgrade.
+    down: Vec<String>,
+}
+
+impl Migration {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(version: impl ToString, name: impl ToString) -> Self {
+        Self {
+            version: version.to_string(),
+            name: name.to_string(),
+            up: Vec::new(),
+            down: Vec::new(),
+        }
+    }
+
+    /// Adds a pair of up and down statements.
+    ///
+    /// The down statements are reverted in the reverse order.
+    #[inline]
+    pub fn add_statements(&mut self, up: impl Into<String>, down: impl Into<String>) {
+        self.up.push(up.into());
+        self.down.insert(0, down.into());
+    }
+
+    /// Returns the version.
+    #[inline]
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Returns the name.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the statements for the upgrade.
+    #[inline]
+    pub fn up_statements(&self) -> &[String] {
+        &self.up
+    }
+
+    /// Returns the statements for the downgrade.
+    #[inline]
+    pub fn down_statements(&self) -> &[String] {
+        &self.down
+    }
+
+    /// Returns `true` if there are no statements for the upgrade.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.up.is_empty()
+    }
+
+    /// Returns the identifier `{version}_{name}`, which is also the stem of the file names.
+    #[inline]
+    pub fn id(&self) -> String {
+        format!("{}_{}", self.version, self.name)
+    }
+
+    /// Generates a migration by comparing the columns of the model
+    /// with the table in the database. The version is the current time
+    /// followed by a sequence number, and the name is the table name.
+    ///
+    /// A column can be renamed with the `renamed_from` attribute.
+    /// Columns which exist only in the table are dropped if `drop_columns` is `true`,
+    /// otherwise they are reported as warnings.
+    pub async fn diff<M: Schema>(drop_columns: bool) -> Result<Self, Error> {
+        let connection_pool = M::acquire_writer().await?;
+        let table_name = M::table_name();
+        let primary_key_name = M::PRIMARY_KEY_NAME;
+        let sequence = MIGRATION_SEQUENCE.fetch_add(1, Relaxed) % 10000;
+        let version = format!("{}{sequence:04}", DateTime::now().format("%Y%m%d%H%M%S"));
+        let mut migration = Self::new(version, table_name);
+
+        let table_columns = fetch_table_columns(connection_pool, table_name).await?;
+        if table_columns.is_empty() {
+            let definitions = format_table_definitions::<M>();
+            migration.add_statements(
+                format!("CREATE TABLE IF NOT EXISTS {table_name} (\n  {definitions}\n);"),
+                format!("DROP TABLE IF EXISTS {table_name};"),
+            );
+            return Ok(migration);
+        }
+
+        let is_mysql = cfg!(any(
+            feature = "orm-mariadb",
+            feature = "orm-mysql",
+            feature = "orm-tidb"
+        ));
+        let is_postgres = cfg!(feature = "orm-postgres");
+        let mut matched_columns = Vec::new();
+        for col in M::columns() {
+            let column_name = col.name();
+            let mut table_column = table_columns.iter().find(|c| c.name == column_name);
+            if table_column.is_none()
+                && let Some(old_name) = col.extra().get_str("renamed_from")
+                && let Some(c) = table_columns.iter().find(|c| c.name == old_name)
+            {
+                migration.add_statements(
+                    format!("ALTER TABLE {table_name} RENAME COLUMN {old_name} TO {column_name};"),
+                    format!("ALTER TABLE {table_name} RENAME COLUMN {column_name} TO {old_name};"),
+                );
+                table_column = Some(c);
+            }
+
+            let Some(c) = table_column else {
+                let column_definition = col.field_definition(primary_key_name);
+                migration.add_statements(
+                    format!("ALTER TABLE {table_name} ADD COLUMN {column_definition};"),
+                    format!("ALTER TABLE {table_name} DROP COLUMN {column_name};"),
+                );
+                continue;
+            };
+            matched_columns.push(c.name.as_str());
+            if column_name == primary_key_name {
+                continue;
+            }
+
+            let type_changed = !col.is_compatible_type(&c.column_type);
+            let not_null_changed = col.is_not_null() != c.not_null;
+            if !type_changed && !not_null_changed {
+                continue;
+            }
+            if is_mysql {
+                let column_definition = col.field_definition(primary_key_name);
+                let old_column_type = &c.column_type;
+                let old_constraint = if c.not_null { " NOT NULL" } else { "" };
+                migration.add_statements(
+                    format!("ALTER TABLE {table_name} MODIFY COLUMN {column_definition};"),
+                    format!(
+                        "ALTER TABLE {table_name} MODIFY COLUMN \
+                            {column_name} {old_column_type}{old_constraint};"
+                    ),
+                );
+            } else if is_postgres {
+                if type_changed {
+                    let column_type = col.column_type();
+                    let old_column_type = &c.column_type;
+                    migration.add_statements(
+                        format!(
+                            "ALTER TABLE {table_name} ALTER COLUMN {column_name} \
+                                TYPE {column_type} USING {column_name}::{column_type};"
+                        ),
+                        format!(
+                            "ALTER TABLE {table_name} ALTER COLUMN {column_name} \
+                                TYPE {old_column_type} USING {column_name}::{old_column_type};"
+                        ),
+                    );
+                }
+                if not_null_changed {
+                    let (action, reverse_action) = if col.is_not_null() {
+                        ("SET", "DROP")
+                    } else {
+                        ("DROP", "SET")
+                    };
+                    migration.add_statements(
+                        format!(
+                            "ALTER TABLE {table_name} ALTER COLUMN {column_name} {action} NOT NULL;"
+                        ),
+                        format!(
+                            "ALTER TABLE {table_name} ALTER COLUMN {column_name} \
+                                {reverse_action} NOT NULL;"
+                        ),
+                    );
+                }
+            } else {
+                tracing::warn!(
+                    model_name = M::model_name(),
+                    table_name,
+                    column_name,
+                    type_changed,
+                    not_null_changed,
+                    "the column `{column_name}` can not be altered in SQLite; \
+                        please rebuild the table manually",
+                );
+            }
+        }
+        for c in table_columns.iter() {
+            let column_name = c.name.as_str();
+            if matched_columns.contains(&column_name) {
+                continue;
+            }
+            if drop_columns {
+                let column_type = &c.column_type;
+                migration.add_statements(
+                    format!("ALTER TABLE {table_name} DROP COLUMN {column_name};"),
+                    format!("ALTER TABLE {table_name} ADD COLUMN {column_name} {column_type};"),
+                );
+            } else {
+                tracing::warn!(
+                    model_name = M::model_name(),
+                    table_name,
+                    column_name,
+                    "the column `{column_name}` is not declared on the model",
+                );
+            }
+        }
+        Ok(migration)
+    }
+
+    /// Writes the up and down SQL files into the directory.
+    /// The file names are `{version}_{name}.up.sql` and `{version}_{name}.down.sql`.
+    pub fn write_files(&self, dir: impl AsRef<Path>) -> Result<(), Error> {
+        let dir = dir.as_ref();
+        let id = self.id();
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(format!("{id}.up.sql")), self.up.join("\n") + "\n")?;
+        fs::write(
+            dir.join(format!("{id}.down.sql")),
+            self.down.join("\n") + "\n",
+        )?;
+        Ok(())
+    }
+
+    /// Reads the migrations in the directory ordered by the versions.
+    pub fn read_dir(dir: impl AsRef<Path>) -> Result<Vec<Self>, Error> {
+        let dir = dir.as_ref();
+        let mut migrations = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let file_name = entry?.file_name();
+            let Some(file_stem) = file_name.to_str().and_then(|s| s.strip_suffix(".up.sql")) else {
+                continue;
+            };
+            let Some((version, name)) = file_stem.split_once('_') else {
+                bail!("invalid migration file name `{}`", file_stem);
+            };
+            let mut migration = Self::new(version, name);
+            migration.up = parse_statements(&fs::read_to_string(dir.join(&file_name))?);
+
+            let down_file = dir.join(format!("{file_stem}.down.sql"));
+            if down_file.exists() {
+                migration.down = parse_statements(&fs::read_to_string(down_file)?);
+            }
+            migrations.push(migration);
+        }
+        migrations.sort_by(|a, b| a.version.cmp(&b.version).then(a.name.cmp(&b.name)));
+        Ok(migrations)
+    }
+
+    /// Returns the identifiers `{version}_{name}` of the applied migrations.
+    pub async fn applied_migrations(
+        connection_pool: &ConnectionPool,
+    ) -> Result<Vec<String>, Error> {
+        let pool = connection_pool.pool();
+        let sql = format!(
+            "CREATE TABLE IF NOT EXISTS {MIGRATION_TABLE_NAME} (\n  \
+                version VARCHAR(255) NOT NULL,\n  \
+                name VARCHAR(255) NOT NULL,\n  \
+                applied_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,\n  \
+                PRIMARY KEY (version, name)\n\
+            );"
+        );
+        sqlx::query(&sql).execute(pool).await?;
+
+        let sql = format!("SELECT version, name FROM {MIGRATION_TABLE_NAME} ORDER BY version;");
+        let rows = sqlx::query_as::<_, (String, String)>(&sql)
+            .fetch_all(pool)
+            .await?;
+        let ids = rows
+            .into_iter()
+            .map(|(version, name)| format!("{version}_{name}"))
+            .collect();
+        Ok(ids)
+    }
+
+    /// Applies the migration and records the version.
+    /// Returns `false` if the migration has already been applied.
+    ///
+    /// In the dry-run mode, the statements are logged instead of being executed.
+    pub async fn apply(
+        &self,
+        connection_pool: &ConnectionPool,
+        dry_run: bool,
+    ) -> Result<bool, Error> {
+        let ids = Self::applied_migrations(connection_pool).await?;
+        if ids.contains(&self.id()) {
+            return Ok(false);
+        }
+
+        let version = self.version.as_str();
+        let name = self.name.as_str();
+        if dry_run {
+            for sql in self.up.iter() {
+                tracing::info!(version, name, dry_run, "{sql}");
+            }
+            return Ok(true);
+        }
+
+        let mut transaction = connection_pool.pool().begin().await?;
+        for sql in self.up.iter() {
+            sqlx::query(sql).execute(&mut *transaction).await?;
+        }
+
+        let sql = format!(
+            "INSERT INTO {MIGRATION_TABLE_NAME} (version, name) VALUES ({}, {});",
+            Query::placeholder(1),
+            Query::placeholder(2),
+        );
+        sqlx::query(&sql)
+            .bind(version)
+            .bind(name)
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await?;
+        tracing::warn!(version, name, "the migration has been applied");
+        Ok(true)
+    }
+
+    /// Reverts the migration and removes the version.
+    /// Returns `false` if the migration has not been applied.
+    ///
+    /// In the dry-run mode, the statements are logged instead of being executed.
+    pub async fn revert(
+        &self,
+        connection_pool: &ConnectionPool,
+        dry_run: bool,
+    ) -> Result<bool, Error> {
+        let ids = Self::applied_migrations(connection_pool).await?;
+        if !ids.contains(&self.id()) {
+            return Ok(false);
+        }
+
+        let version = self.version.as_str();
+        let name = self.name.as_str();
+        if dry_run {
+            for sql in self.down.iter() {
+                tracing::info!(version, name, dry_run, "{sql}");
+            }
+            return Ok(true);
+        }
+
+        let mut transaction = connection_pool.pool().begin().await?;
+        for sql in self.down.iter() {
+            sqlx::query(sql).execute(&mut *transaction).await?;
+        }
+
+        let sql = format!(
+            "DELETE FROM {MIGRATION_TABLE_NAME} WHERE version = {} AND name = {};",
+            Query::placeholder(1),
+            Query::placeholder(2),
+        );
+        sqlx::query(&sql)
+            .bind(version)
+            .bind(name)
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await?;
+        tracing::warn!(version, name, "the migration has been reverted");
+        Ok(true)
+    }
+
+    /// Applies the pending migrations in order, and returns the identifiers applied.
+    pub async fn run_pending(
+        connection_pool: &ConnectionPool,
+        migrations: &[Self],
+        dry_run: bool,
+    ) -> Result<Vec<String>, Error> {
+        let mut ids = Vec::new();
+        for migration in migrations {
+            if migration.apply(connection_pool, dry_run).await? {
+                ids.push(migration.id());
+            }
+        }
+        Ok(ids)
+    }
+}
+
+/// Column information in the database.
+#[derive(Debug, Default)]
+pub(super) struct TableColumn {
+    /// Column name.
+    pub(super) name: String,
+    /// Data type.
+    pub(super) data_type: String,
+    /// Column type with the dialect specific details.
+    pub(super) column_type: String,
+    /// Default value.
+    pub(super) default_value: Option<String>,
+    /// A flag for the `NOT NULL` constraint.
+    pub(super) not_null: bool,
+}
+
+/// Fetches the columns of a table in the database.
+pub(super) async fn fetch_table_columns(
+    connection_pool: &ConnectionPool,
+    table_name: &str,
+) -> Result<Vec<TableColumn>, Error> {
+    let sql = if cfg!(any(
+        feature = "orm-mariadb",
+        feature = "orm-mysql",
+        feature = "orm-tidb"
+    )) {
+        let table_schema = connection_pool.database();
+        format!(
+            "SELECT column_name, data_type, column_type, column_default, is_nullable \
+                FROM information_schema.columns \
+                    WHERE table_schema = '{table_schema}' AND table_name = '{table_name}';"
+        )
+    } else if cfg!(feature = "orm-postgres") {
+        format!(
+            "SELECT column_name, data_type, udt_name AS column_type, column_default, is_nullable \
+                FROM information_schema.columns \
+                    WHERE table_schema = 'public' AND table_name = '{table_name}';"
+        )
+    } else {
+        format!(
+            "SELECT p.name AS column_name, p.type AS data_type, p.type AS column_type, \
+                    p.dflt_value AS column_default, p.[notnull] AS is_not_null \
+                FROM sqlite_master m LEFT OUTER JOIN pragma_table_info((m.name)) p
+                    ON m.name <> p.name WHERE m.name = '{table_name}';"
+        )
+    };
+    let mut rows = sqlx::query(&sql).fetch(connection_pool.pool());
+    let mut columns = Vec::new();
+    while let Some(row) = rows.try_next().await? {
+        // MySQL returns the column names of `information_schema` in uppercase.
+        let data = Map::decode_row(&row)?
+            .into_iter()
+            .map(|(key, value)| (key.to_ascii_lowercase(), value))
+            .collect::<Map>();
+        let Some(name) = data.get_str("column_name") else {
+            continue;
+        };
+        let data_type = data.get_str("data_type").unwrap_or_default();
+        let not_null = match data.get("is_not_null") {
+            Some(JsonValue::Bool(b)) => *b,
+            Some(JsonValue::Number(n)) => n.as_i64() == Some(1),
+            Some(JsonValue::String(s)) => s == "1",
+            _ => data
+                .get_str("is_nullable")
+                .is_some_and(|s| s.eq_ignore_ascii_case("NO")),
+        };
+        columns.push(TableColumn {
+            name: name.to_owned(),
+            data_type: data_type.to_owned(),
+            column_type: data.get_str("column_type").unwrap_or(data_type).to_owned(),
+            default_value: data.get_str("column_default").map(|s| s.to_owned()),
+            not_null,
+        });
+    }
+    Ok(columns)
+}
+
+/// Fetches the index names of a table in the database.
+pub(super) async fn fetch_table_indexes(
+    connection_pool: &ConnectionPool,
+    table_name: &str,
+) -> Result<Vec<String>, Error> {
+    let sql = if cfg!(any(
+        feature = "orm-mariadb",
+        feature = "orm-mysql",
+        feature = "orm-tidb"
+    )) {
+        let table_schema = connection_pool.database();
+        format!(
+            "SELECT DISTINCT index_name FROM information_schema.statistics \
+                WHERE table_schema = '{table_schema}' AND table_name = '{table_name}';"
+        )
+    } else if cfg!(feature = "orm-postgres") {
+        format!(
+            "SELECT indexname AS index_name FROM pg_indexes \
+                WHERE schemaname = 'public' AND tablename = '{table_name}';"
+        )
+    } else {
+        format!(
+            "SELECT name AS index_name FROM sqlite_master \
+                WHERE type = 'index' AND tbl_name = '{table_name}';"
+        )
+    };
+    let mut rows = sqlx::query(&sql).fetch(connection_pool.pool());
+    let mut index_names = Vec::new();
+    while let Some(row) = rows.try_next().await? {
+        // MySQL returns the column names of `information_schema` in uppercase.
+        let data = Map::decode_row(&row)?;
+        if let Some(index_name) = data
+            .iter()
+            .find_map(|(key, value)| key.eq_ignore_ascii_case("index_name").then_some(value))
+            .and_then(|value| value.as_str())
+        {
+            index_names.push(index_name.to_owned());
+        }
+    }
+    Ok(index_names)
+}
+
+/// Formats the statement to create an index declared on the model.
+/// Returns `None` if the index is not supported by the database.
+pub(super) fn format_index_definition(table_name: &str, index: &Index<'_>) -> Option<String> {
+    let index_name = index.index_name(table_name);
+    let index_type = if index.is_unique() { "UNIQUE " } else { "" };
+    let fields = index.fields().join(", ");
+    if cfg!(any(
+        feature = "orm-mariadb",
+        feature = "orm-mysql",
+        feature = "orm-tidb"
+    )) {
+        if index.filter().is_some() {
+            tracing::warn!(
+                table_name,
+                index_name,
+                "the partial index `{index_name}` is not supported by MySQL",
+            );
+            return None;
+        }
+        Some(format!(
+            "CREATE {index_type}INDEX {index_name} ON {table_name} ({fields});"
+        ))
+    } else if let Some(filter) = index.filter() {
+        // Both PostgreQL and SQLite support partial indexes.
+        Some(format!(
+            "CREATE {index_type}INDEX IF NOT EXISTS {index_name} \
+                ON {table_name} ({fields}) WHERE {filter};"
+        ))
+    } else {
+        Some(format!(
+            "CREATE {index_type}INDEX IF NOT EXISTS {index_name} ON {table_name} ({fields});"
+        ))
+    }
+}
+
+/// Creates the missing indexes declared on the model `M`,
+/// and reports the indexes in the table which are not declared.
+pub(super) async fn synchronize_indexes<M: Schema>(
+    connection_pool: &ConnectionPool,
+) -> Result<(), Error> {
+    let table_name = M::table_name();
+    let index_names = fetch_table_indexes(connection_pool, table_name).await?;
+    for index in M::indexes() {
+        let index_name = index.index_name(table_name);
+        if !index_names.contains(&index_name)
+            && let Some(sql) = format_index_definition(table_name, index)
+        {
+            sqlx::query(&sql).execute(connection_pool.pool()).await?;
+            tracing::warn!(
+                model_name = M::model_name(),
+                table_name,
+                index_name,
+                "a new index `{index_name}` has been created",
+            );
+        }
+    }
+    for index_name in index_names {
+        let is_declared = M::indexes()
+            .iter()
+            .any(|index| index.index_name(table_name) == index_name);
+        if !is_declared && !is_derived_index(table_name, M::columns(), &index_name) {
+            tracing::warn!(
+                model_name = M::model_name(),
+                table_name,
+                index_name,
+                "the index `{index_name}` is not declared on the model",
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Returns `true` if the index is a primary key, an implicit index created by the database,
+/// or an index derived from the `index_type` of the columns.
+fn is_derived_index(table_name: &str, columns: &[Column<'_>], index_name: &str) -> bool {
+    if index_name == "PRIMARY"
+        || index_name == format!("{table_name}_pkey")
+        || index_name.starts_with("sqlite_autoindex_")
+    {
+        return true;
+    }
+    let Some(name) = index_name
+        .strip_prefix(table_name)
+        .and_then(|s| s.strip_prefix('_'))
+        .and_then(|s| s.strip_suffix("_index"))
+    else {
+        return false;
+    };
+    name.starts_with("text_search")
+        || columns
+            .iter()
+            .any(|col| col.name() == name && col.index_type().is_some())
+}
+
+/// Formats the column definitions and constraints of the model table.
+pub(super) fn format_table_definitions<M: Schema>() -> String {
+    let primary_key_name = M::PRIMARY_KEY_NAME;
+    let columns = M::columns();
+    let mut definitions = columns
+        .iter()
+        .map(|col| col.field_definition(primary_key_name))
+        .collect::<Vec<_>>();
+    for col in columns {
+        let mut constraints = col.constraints();
+        if !constraints.is_empty() {
+            definitions.append(&mut constraints);
+        }
+    }
+    definitions.join(",\n  ")
+}
+
+/// Parses the SQL statements separated by the lines ending with `;`.
+fn parse_statements(sql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut statement = String::new();
+    for line in sql.lines() {
+        let line = line.trim_end();
+        if line.is_empty() || line.trim_start().starts_with("--") {
+            continue;
+        }
+        if !statement.is_empty() {
+            statement.push('\n');
+        }
+        statement.push_str(line);
+        if line.ends_with(';') {
+            statements.push(std::mem::take(&mut statement));
+        }
+    }
+    if !statement.trim().is_empty() {
+        statements.push(statement);
+    }
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_derived_index, parse_statements, Migration};
+    use crate::model::Column;
+
+    #[test]
+    fn it_parses_migration_statements() {
+        let sql = "-- add a column\n\
+            ALTER TABLE user ADD COLUMN age INT;\n\n\
+            CREATE TABLE IF NOT EXISTS tag (\n  id UUID PRIMARY KEY\n);\n";
+        let statements = parse_statements(sql);
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0], "ALTER TABLE user ADD COLUMN age INT;");
+        assert_eq!(
+            statements[1],
+            "CREATE TABLE IF NOT EXISTS tag (\n  id UUID PRIMARY KEY\n);"
+        );
+
+        let mut migration = Migration::new("202401010000000001", "user_role");
+        migration.add_statements("ALTER TABLE a;", "ALTER TABLE -a;");
+        migration.add_statements("ALTER TABLE b;", "ALTER TABLE -b;");
+        assert_eq!(migration.down_statements()[0], "ALTER TABLE -b;");
+        assert_eq!(migration.id(), "202401010000000001_user_role");
+    }
+
+    #[test]
+    fn it_checks_derived_indexes() {
+        let mut column = Column::new("status", "String", true);
+        column.set_index_type("hash");
+        let columns = [Column::new("name", "String", true), column];
+        let is_derived = |index_name| is_derived_index("user", &columns, index_name);
+        assert!(is_derived("user_pkey"));
+        assert!(is_derived("sqlite_autoindex_user_1"));
+        assert!(is_derived("user_status_index"));
+        assert!(is_derived("user_text_search_english_index"));
+        assert!(!is_derived("user_name_index"));
+        assert!(!is_derived("user_namespace_status_index"));
+    }
+}
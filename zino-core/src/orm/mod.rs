@@ -12,6 +12,83 @@
 //! | `orm-sqlite`   | Enables the SQLite database driver.                  | No       |
 //! | `orm-tidb`     | Enables the TiDB database driver.                    | No       |
 //!
+//! # Read replicas
+//!
+//! A database service can have several entries with the same `name`, one of which
+//! is the primary and the others declare `role = "replica"`. The model writer always uses
+//! the primary, while the model reader balances the load across the available replicas
+//! with the `replica-strategy` of **`round-robin`** (default) or **`least-connections`**,
+//! and falls back to the primary if there are no healthy replicas.
+//! An unhealthy replica is checked by a ping again after the `replica-retry-interval`.
+//! Since the replicas may lag behind the primary, the reader sticks to the primary
+//! within the `read-your-writes` window after a write in the same request context,
+//! which is scoped by [`scope_writes`].
+//!
+//! ```toml
+//! [database]
+//! type = "postgres"
+//! replica-strategy = "least-connections"
+//! replica-retry-interval = "30s"
+//! read-your-writes = "3s"
+//!
+//! [[postgres]]
+//! host = "10.0.0.1"
+//! database = "data_cube"
+//!
+//! [[postgres]]
+//! host = "10.0.0.2"
+//! database = "data_cube"
+//! role = "replica"
+//! ```
+//!
+//! # Field-level encryption
+//!
+//! The string values of the columns declared with `#[schema(encrypted)]` are encrypted
+//! before being written, and decrypted after being decoded. Each ciphertext is prefixed with
+//! the `encryption-key-id`, and the key is derived from the checksum in the `encryption-keys`
+//! table or the application secret key. The blind indexes are always derived from
+//! the application secret key, so the encrypted columns can not be written or queried
+//! before the application has been initialized. Rotating the key is a matter of setting
+//! a new key ID: values encrypted by the previous keys remain readable, and they are
+//! re-encrypted with the current key when the models are updated.
+//!
+//! ```toml
+//! [database]
+//! encryption-key-id = "v2"
+//!
+//! [database.encryption-keys]
+//! v1 = "d9b3e6c2f0a14e8b9c7d5a3f1e2b4c6d"
+//! v2 = "5f8a2c4e6b1d3f7a9c0e2b4d6f8a1c3e"
+//! ```
+//!
+//! # Slow query log
+//!
+//! The execution time of the queries is recorded as the histogram
+//! `zino_model_scan_duration_seconds` labeled by the model and the action, including
+//! the failed queries which are also counted by `zino_model_scan_errors_total` and logged.
+//! A query taking longer than the `slow-query-threshold` is logged with its bound arguments,
+//! and the query plan obtained by `EXPLAIN` (or `EXPLAIN QUERY PLAN` for SQLite)
+//! is attached to the log if `explain-slow-query` is enabled.
+//!
+//! ```toml
+//! [database]
+//! slow-query-threshold = "500ms"
+//! explain-slow-query = true
+//! ```
+//!
+//! # Seeding
+//!
+//! The reference data in `config/seeds/{model_name}.toml` or `config/seeds/{model_name}.json`
+//! can be loaded by a [`Seeder`] registered with `Application::seed`. It runs before the servers
+//! start if `seed-on-boot` is enabled, or exits after seeding with the `--seed` argument.
+//! In the latter case, the process exits with a non-zero status if the seeding fails.
+//!
+//! ```toml
+//! [database]
+//! seed-on-boot = true
+//! seed-dir = "config/seeds"
+//! ```
+//!
 //! # Design references
 //!
 //! The design of our ORM is inspired by [`Mongoose`], [`Prisma`], [`TypeORM`] and [`PostgREST`].
@@ -56,6 +133,16 @@
 //!     "category": "Rustacean",
 //! });
 //! let records = User::query::<Record>(sql, params.as_object()).await?;
+//!
+//! // Runs the operations with `_with` variants inside a transaction.
+//! User::transaction_with(|conn| Box::pin(async move {
+//!     user.insert_with(conn).await?;
+//!
+//!     let query = Query::new(json!({ "id": group_id }));
+//!     let mut mutation = Mutation::new(json!({ "$inc": { "num_members": 1 } }));
+//!     Group::update_one_with(conn, &query, &mut mutation).await?;
+//!     Ok(())
+//! })).await?;
 //! ```
 //!
 //! # Query operators
@@ -84,12 +171,35 @@
 //! | `$all`     | N/A                 | `@>`             | N/A                   |
 //! | `$size`    | `json_length()`     | `array_length()` | `json_array_length()` |
 //!
+//! # JSON paths
+//!
+//! The nested values of a `Map` column can be filtered by a dotted path such as
+//! `"extra.plan.tier"`, which is extracted by `json_extract()` for MySQL and SQLite,
+//! and `->>` for PostgreSQL. The operators `$contains` and `$has_key` are also supported
+//! for the column or a nested path. The nested values can be updated by the mutation operators
+//! `$set`, `$unset` and `$push`, where the intermediate objects of the path should exist.
+//!
+//! ```rust,ignore
+//! let query = Query::new(json!({
+//!     "extra.plan.tier": "pro",
+//!     "extra.seats": { "$ge": 5 },
+//!     "extra.tags": { "$contains": "beta" },
+//!     "content": { "$has_key": "summary" },
+//! }));
+//! let mut mutation = Mutation::new(json!({
+//!     "$set": { "extra.plan.tier": "enterprise" },
+//!     "$unset": ["extra.plan.trial"],
+//!     "$push": { "extra.tags": "migrated" },
+//! }));
+//! User::update_many(&query, &mut mutation).await?;
+//! ```
+//!
 //! [`Mongoose`]: https://mongoosejs.com/
 //! [`Prisma`]: https://www.prisma.io/
 //! [`TypeORM`]: https://typeorm.io/
 //! [`PostgREST`]: https://postgrest.org/
 
-use crate::{extension::TomlTableExt, state::State};
+use crate::{datetime::DateTime, extension::TomlTableExt, state::State};
 use convert_case::{Case, Casing};
 use smallvec::SmallVec;
 use sqlx::{
@@ -98,29 +208,50 @@ use sqlx::{
 };
 use std::{
     sync::{
-        atomic::{AtomicBool, AtomicUsize, Ordering::Relaxed},
-        LazyLock,
+        atomic::{AtomicBool, AtomicI64, AtomicUsize, Ordering::Relaxed},
+        Arc, LazyLock,
     },
     time::Duration,
 };
 use toml::value::Table;
 
 mod accessor;
+mod aggregation;
+mod audit;
+mod cache;
 mod column;
+mod constraint;
 mod decode;
+mod encryption;
 mod helper;
+mod join;
+mod json_path;
+mod migration;
 mod mutation;
+mod profile;
 mod query;
 mod schema;
+mod scope;
+mod search;
+mod seed;
+
+pub(crate) use query::QueryExt;
 
 pub use accessor::ModelAccessor;
+pub use audit::AuditLog;
+pub use constraint::check_unique_violation;
 pub use decode::{decode, decode_array};
+pub use encryption::{blind_index, decrypt_value, encrypt_value};
 pub use helper::ModelHelper;
+pub use join::JoinOn;
+pub use migration::Migration;
 pub use schema::Schema;
+pub use scope::scope_writes;
+pub use seed::Seeder;
 
 cfg_if::cfg_if! {
     if #[cfg(any(feature = "orm-mariadb", feature = "orm-mysql", feature = "orm-tidb"))] {
-        use sqlx::mysql::{MySql, MySqlConnectOptions, MySqlRow};
+        use sqlx::mysql::{MySql, MySqlConnectOptions, MySqlConnection, MySqlRow};
 
         mod mysql;
 
@@ -139,6 +270,9 @@ cfg_if::cfg_if! {
         /// A single row from the MySQL database.
         pub type DatabaseRow = MySqlRow;
 
+        /// A single connection to the MySQL database.
+        pub type DatabaseConnection = MySqlConnection;
+
         /// Options and flags which can be used to configure a MySQL connection.
         fn new_connect_options(database: &'static str, config: &'static Table) -> MySqlConnectOptions {
             let username = config
@@ -160,7 +294,7 @@ cfg_if::cfg_if! {
             connect_options
         }
     } else if #[cfg(feature = "orm-postgres")] {
-        use sqlx::postgres::{PgConnectOptions, PgRow, Postgres};
+        use sqlx::postgres::{PgConnectOptions, PgConnection, PgRow, Postgres};
 
         mod postgres;
 
@@ -173,6 +307,9 @@ cfg_if::cfg_if! {
         /// A single row from the PostgreSQL database.
         pub type DatabaseRow = PgRow;
 
+        /// A single connection to the PostgreSQL database.
+        pub type DatabaseConnection = PgConnection;
+
         /// Options and flags which can be used to configure a PostgreSQL connection.
         fn new_connect_options(database: &'static str, config: &'static Table) -> PgConnectOptions {
             let username = config
@@ -194,7 +331,7 @@ cfg_if::cfg_if! {
             connect_options
         }
     } else {
-        use sqlx::sqlite::{Sqlite, SqliteConnectOptions, SqliteRow};
+        use sqlx::sqlite::{Sqlite, SqliteConnectOptions, SqliteConnection, SqliteRow};
 
         mod sqlite;
 
@@ -207,6 +344,9 @@ cfg_if::cfg_if! {
         /// A single row from the SQLite database.
         pub type DatabaseRow = SqliteRow;
 
+        /// A single connection to the SQLite database.
+        pub type DatabaseConnection = SqliteConnection;
+
         /// Options and flags which can be used to configure a SQLite connection.
         fn new_connect_options(database: &'static str, config: &'static Table) -> SqliteConnectOptions {
             let mut connect_options = SqliteConnectOptions::new().create_if_missing(true);
@@ -225,6 +365,13 @@ cfg_if::cfg_if! {
     }
 }
 
+/// Returns the max number of returning rows specified by the `database.max-rows` config.
+#[inline]
+pub fn max_rows() -> usize {
+    LazyLock::force(&NAMESPACE_PREFIX);
+    MAX_ROWS.load(Relaxed)
+}
+
 /// A database connection pool based on [`sqlx::Pool`](sqlx::pool::Pool).
 #[derive(Debug)]
 pub struct ConnectionPool {
@@ -232,10 +379,14 @@ pub struct ConnectionPool {
     name: &'static str,
     /// Database.
     database: &'static str,
+    /// Role: `primary` or `replica`.
+    role: &'static str,
     /// Pool.
     pool: Pool<DatabaseDriver>,
     /// Availability.
-    available: AtomicBool,
+    available: Arc<AtomicBool>,
+    /// Timestamp in milliseconds of the last health check for an unavailable pool.
+    checked_at: Arc<AtomicI64>,
 }
 
 impl ConnectionPool {
@@ -249,6 +400,33 @@ impl ConnectionPool {
     #[inline]
     pub fn store_availability(&self, available: bool) {
         self.available.store(available, Relaxed);
+        if !available {
+            self.checked_at
+                .store(DateTime::now().timestamp_millis(), Relaxed);
+        }
+    }
+
+    /// Checks the availability of the connection pool by a ping.
+    pub async fn check_availability(&self) -> bool {
+        let available = match self.pool.acquire().await {
+            Ok(mut conn) => conn.ping().await.is_ok(),
+            Err(_) => false,
+        };
+        self.store_availability(available);
+        available
+    }
+
+    /// Returns `true` if the unavailable connection pool should be checked again
+    /// after the retry interval. Only one caller is allowed for each interval.
+    fn should_retry(&self, retry_interval: Duration) -> bool {
+        let checked_at = self.checked_at.load(Relaxed);
+        let now = DateTime::now().timestamp_millis();
+        let retry_interval = i64::try_from(retry_interval.as_millis()).unwrap_or(i64::MAX);
+        now - checked_at >= retry_interval
+            && self
+                .checked_at
+                .compare_exchange(checked_at, now, Relaxed, Relaxed)
+                .is_ok()
     }
 
     /// Returns the name.
@@ -263,15 +441,41 @@ impl ConnectionPool {
         self.database
     }
 
+    /// Returns the role.
+    #[inline]
+    pub fn role(&self) -> &'static str {
+        self.role
+    }
+
+    /// Returns `true` if the connection pool is a read replica.
+    #[inline]
+    pub fn is_replica(&self) -> bool {
+        self.role == "replica"
+    }
+
     /// Returns a reference to the pool.
     #[inline]
     pub fn pool(&self) -> &Pool<DatabaseDriver> {
         &self.pool
     }
 
+    /// Returns the number of connections which are in use.
+    #[inline]
+    pub fn num_active_connections(&self) -> u32 {
+        let num_idle = u32::try_from(self.pool.num_idle()).unwrap_or(u32::MAX);
+        self.pool.size().saturating_sub(num_idle)
+    }
+
+    /// Records a write to the database in the current write scope.
+    #[inline]
+    pub fn record_write(&self) {
+        scope::record_write(self.name);
+    }
+
     /// Connects lazily to the database according to the config.
     pub fn connect_lazy(config: &'static Table) -> Self {
         let name = config.get_str("name").unwrap_or("main");
+        let role = config.get_str("role").unwrap_or("primary");
 
         // Connect options.
         let database = config
@@ -295,6 +499,10 @@ impl ConnectionPool {
             .get_duration("acquire-timeout")
             .unwrap_or_else(|| Duration::from_secs(30));
         let health_check_interval = config.get_u64("health-check-interval").unwrap_or(60);
+        let available = Arc::new(AtomicBool::new(true));
+        let availability = available.clone();
+        let checked_at = Arc::new(AtomicI64::new(0));
+        let last_checked_at = checked_at.clone();
         let pool = PoolOptions::<DatabaseDriver>::new()
             .max_connections(max_connections)
             .min_connections(min_connections)
@@ -303,15 +511,16 @@ impl ConnectionPool {
             .acquire_timeout(acquire_timeout)
             .test_before_acquire(false)
             .before_acquire(move |conn, meta| {
+                let availability = availability.clone();
+                let last_checked_at = last_checked_at.clone();
                 Box::pin(async move {
-                    if meta.idle_for.as_secs() > health_check_interval
-                        && let Some(cp) = SHARED_CONNECTION_POOLS.get_pool(name)
-                    {
+                    if meta.idle_for.as_secs() > health_check_interval {
                         if let Err(err) = conn.ping().await {
-                            cp.store_availability(false);
+                            availability.store(false, Relaxed);
+                            last_checked_at.store(DateTime::now().timestamp_millis(), Relaxed);
                             return Err(err);
                         } else {
-                            cp.store_availability(cp.pool().num_idle() > 0);
+                            availability.store(true, Relaxed);
                         }
                     }
                     Ok(true)
@@ -322,8 +531,10 @@ impl ConnectionPool {
         Self {
             name,
             database,
+            role,
             pool,
-            available: AtomicBool::new(true),
+            available,
+            checked_at,
         }
     }
 }
@@ -333,17 +544,65 @@ impl ConnectionPool {
 struct ConnectionPools(SmallVec<[ConnectionPool; 4]>);
 
 impl ConnectionPools {
-    /// Returns a connection pool with the specific name.
+    /// Returns a primary connection pool with the specific name.
+    /// The replicas are used only if there is no primary for the name.
     pub(crate) fn get_pool(&self, name: &str) -> Option<&ConnectionPool> {
         let mut pool = None;
-        for cp in self.0.iter().filter(|cp| cp.name() == name) {
+        for cp in self
+            .0
+            .iter()
+            .filter(|cp| cp.name() == name && !cp.is_replica())
+        {
             if cp.is_available() {
                 return Some(cp);
             } else {
                 pool = Some(cp);
             }
         }
-        pool
+        pool.or_else(|| self.0.iter().find(|cp| cp.name() == name))
+    }
+
+    /// Returns a connection pool with the specific name for reading.
+    /// An available replica is selected according to the replica strategy,
+    /// and the primary is used as a fallback or within the read-your-writes window.
+    /// The unavailable replicas are checked again after the retry interval.
+    pub(crate) async fn get_reader(&self, name: &str) -> Option<&ConnectionPool> {
+        let primary = self.get_pool(name);
+        if primary.is_some_and(|cp| !cp.is_replica())
+            && scope::has_recent_write(name, *READ_YOUR_WRITES)
+        {
+            return primary;
+        }
+
+        let retry_interval = *REPLICA_RETRY_INTERVAL;
+        for cp in self.0.iter().filter(|cp| {
+            cp.name() == name
+                && cp.is_replica()
+                && !cp.is_available()
+                && cp.should_retry(retry_interval)
+        }) {
+            if cp.check_availability().await {
+                tracing::warn!("the replica of the `{name}` service is available again");
+            }
+        }
+
+        let replicas = self
+            .0
+            .iter()
+            .filter(|cp| cp.name() == name && cp.is_replica() && cp.is_available())
+            .collect::<SmallVec<[_; 4]>>();
+        let replica = if *REPLICA_STRATEGY == "least-connections" {
+            replicas
+                .iter()
+                .min_by_key(|cp| cp.num_active_connections())
+                .copied()
+        } else if replicas.is_empty() {
+            None
+        } else {
+            let index = REPLICA_INDEX.fetch_add(1, Relaxed) % replicas.len();
+            Some(replicas[index])
+        };
+        replica.or(primary)
     }
 }
 
@@ -415,5 +674,32 @@ static NAMESPACE_PREFIX: LazyLock<&'static str> = LazyLock::new(|| {
         .unwrap_or_default()
 });
 
+/// Strategy for selecting a replica: `round-robin` or `least-connections`.
+static REPLICA_STRATEGY: LazyLock<&'static str> = LazyLock::new(|| {
+    State::shared()
+        .get_config("database")
+        .and_then(|config| config.get_str("replica-strategy"))
+        .unwrap_or("round-robin")
+});
+
+/// Interval for checking the availability of an unhealthy replica again.
+static REPLICA_RETRY_INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
+    State::shared()
+        .get_config("database")
+        .and_then(|config| config.get_duration("replica-retry-interval"))
+        .unwrap_or_else(|| Duration::from_secs(30))
+});
+
+/// Window after a write in which the reader sticks to the primary.
+static READ_YOUR_WRITES: LazyLock<Duration> = LazyLock::new(|| {
+    State::shared()
+        .get_config("database")
+        .and_then(|config| config.get_duration("read-your-writes"))
+        .unwrap_or_else(|| Duration::from_secs(1))
+});
+
+/// Index of the next replica in the round-robin strategy.
+static REPLICA_INDEX: AtomicUsize = AtomicUsize::new(0);
+
 /// Max number of returning rows.
 static MAX_ROWS: AtomicUsize = AtomicUsize::new(10000);
@@ -1,25 +1,74 @@
 /// Generates SQL `SET` expressions.
-use super::{query::QueryExt, DatabaseDriver, Schema};
-use crate::model::{EncodeColumn, Mutation, Query};
+use super::{encryption, json_path, query::QueryExt, DatabaseDriver, Schema};
+use crate::{
+    bail,
+    error::Error,
+    extension::JsonObjectExt,
+    model::{Column, EncodeColumn, Mutation, Query},
+    JsonValue, Map,
+};
 
 /// Extension trait for [`Mutation`](crate::model::Mutation).
 pub(super) trait MutationExt<DB> {
     /// Formats the updates to generate SQL `SET` expression.
-    fn format_updates<M: Schema>(&self) -> String;
+    fn format_updates<M: Schema>(&self) -> Result<String, Error>;
 }
 
 impl MutationExt<DatabaseDriver> for Mutation {
-    fn format_updates<M: Schema>(&self) -> String {
+    fn format_updates<M: Schema>(&self) -> Result<String, Error> {
         let updates = self.updates();
         if updates.is_empty() {
-            return String::new();
+            return Ok(String::new());
         }
 
         let fields = self.fields();
         let permissive = fields.is_empty();
         let mut mutations = Vec::new();
+        let mut json_updates: Vec<(&str, String)> = Vec::new();
+        let null = JsonValue::Null;
         for (key, value) in updates.iter() {
             match key.as_str() {
+                "$set" | "$unset" | "$push" => {
+                    let operator = key.as_str();
+                    let updates = if let Some(update) = value.as_object() {
+                        update
+                            .iter()
+                            .map(|(key, value)| (key.as_str(), value))
+                            .collect::<Vec<_>>()
+                    } else if operator == "$unset"
+                        && let Some(keys) = value.as_array()
+                    {
+                        keys.iter()
+                            .filter_map(|key| key.as_str())
+                            .map(|key| (key, &null))
+                            .collect()
+                    } else {
+                        Vec::new()
+                    };
+                    for (key, value) in updates {
+                        if let Some((column_key, path)) = json_path::parse_json_path::<M>(key) {
+                            if (permissive || fields.iter().any(|field| field == column_key))
+                                && M::get_column(column_key).is_some_and(|c| !c.is_read_only())
+                            {
+                                let index = json_updates
+                                    .iter()
+                                    .position(|(key, _)| *key == column_key)
+                                    .unwrap_or_else(|| {
+                                        let expr = json_path::format_json_column(column_key);
+                                        json_updates.push((column_key, expr));
+                                        json_updates.len() - 1
+                                    });
+                                let expr = &mut json_updates[index].1;
+                                *expr = json_path::format_json_update(expr, operator, &path, value);
+                            }
+                        } else if operator == "$set"
+                            && (permissive || fields.iter().any(|field| field == key))
+                            && let Some(col) = M::get_column(key).filter(|c| !c.is_read_only())
+                        {
+                            format_column_update::<M>(col, value, &mut mutations)?;
+                        }
+                    }
+                }
                 "$inc" => {
                     if let Some(update) = value.as_object() {
                         for (key, value) in update.iter() {
@@ -80,14 +129,53 @@ impl MutationExt<DatabaseDriver> for Mutation {
                     if (permissive || fields.contains(key))
                         && let Some(col) = M::get_column(key).filter(|c| !c.is_read_only())
                     {
-                        let key = Query::format_field(key);
-                        let value = col.encode_value(Some(value));
-                        let mutation = format!(r#"{key} = {value}"#);
-                        mutations.push(mutation);
+                        format_column_update::<M>(col, value, &mut mutations)?;
                     }
                 }
             }
         }
-        mutations.join(", ")
+        for (column_key, expr) in json_updates {
+            let key = Query::format_field(column_key);
+            mutations.push(format!(r#"{key} = {expr}"#));
+        }
+
+        // A column can not be assigned more than once, e.g. a `$set` on a JSON path
+        // combined with an update of the whole column.
+        let mut columns = Vec::with_capacity(mutations.len());
+        for mutation in mutations.iter() {
+            if let Some((column, _)) = mutation.split_once(" = ") {
+                if columns.contains(&column) {
+                    bail!("the column {} can not be updated more than once", column);
+                }
+                columns.push(column);
+            }
+        }
+        Ok(mutations.join(", "))
+    }
+}
+
+/// Formats the update of a column. The value of an `encrypted` column is encrypted,
+/// and the corresponding blind index is also updated.
+fn format_column_update<M: Schema>(
+    col: &Column<'_>,
+    value: &JsonValue,
+    mutations: &mut Vec<String>,
+) -> Result<(), Error> {
+    let key = col.name();
+    if encryption::is_encrypted(col) {
+        let mut model = Map::from_entry(key, value.clone());
+        encryption::encrypt_model::<M>(&mut model)?;
+        for (key, value) in model.iter() {
+            if let Some(col) = M::get_column(key) {
+                let key = Query::format_field(key);
+                let value = col.encode_value(Some(value));
+                mutations.push(format!(r#"{key} = {value}"#));
+            }
+        }
+    } else {
+        let key = Query::format_field(key);
+        let value = col.encode_value(Some(value));
+        mutations.push(format!(r#"{key} = {value}"#));
     }
+    Ok(())
 }
@@ -552,6 +552,26 @@ impl QueryExt<DatabaseDriver> for Query {
         self.limit()
     }
 
+    #[inline]
+    fn query_cursor(&self) -> Option<(&[JsonValue], bool)> {
+        self.cursor()
+    }
+
+    #[inline]
+    fn query_tenant_id(&self) -> Option<&JsonValue> {
+        self.tenant_id()
+    }
+
+    #[inline]
+    fn query_scopes_enabled(&self) -> bool {
+        self.scopes_enabled()
+    }
+
+    #[inline]
+    fn query_show_deleted(&self) -> bool {
+        self.show_deleted()
+    }
+
     #[inline]
     fn placeholder(_n: usize) -> SharedString {
         "?".into()
@@ -613,6 +633,19 @@ impl QueryExt<DatabaseDriver> for Query {
         format!(r#"`{table_name}` `{model_name}`"#)
     }
 
+    fn format_date_bucket(field: &str, unit: &str) -> String {
+        match unit {
+            "year" => format!("date_format({field}, '%Y-01-01')"),
+            "month" => format!("date_format({field}, '%Y-%m-01')"),
+            "week" => {
+                format!("date_format(date_sub({field}, INTERVAL weekday({field}) DAY), '%Y-%m-%d')")
+            }
+            "hour" => format!("date_format({field}, '%Y-%m-%d %H:00:00')"),
+            "minute" => format!("date_format({field}, '%Y-%m-%d %H:%i:00')"),
+            _ => format!("date_format({field}, '%Y-%m-%d')"),
+        }
+    }
+
     fn parse_text_search(filter: &Map) -> Option<String> {
         let fields = filter.parse_str_array("$fields")?;
         filter.parse_string("$search").map(|search| {
@@ -560,6 +560,26 @@ impl QueryExt<DatabaseDriver> for Query {
         self.limit()
     }
 
+    #[inline]
+    fn query_cursor(&self) -> Option<(&[JsonValue], bool)> {
+        self.cursor()
+    }
+
+    #[inline]
+    fn query_tenant_id(&self) -> Option<&JsonValue> {
+        self.tenant_id()
+    }
+
+    #[inline]
+    fn query_scopes_enabled(&self) -> bool {
+        self.scopes_enabled()
+    }
+
+    #[inline]
+    fn query_show_deleted(&self) -> bool {
+        self.show_deleted()
+    }
+
     #[inline]
     fn placeholder(n: usize) -> SharedString {
         if n == 1 {
@@ -625,6 +645,10 @@ impl QueryExt<DatabaseDriver> for Query {
         format!(r#""{table_name}" "{model_name}""#)
     }
 
+    fn format_date_bucket(field: &str, unit: &str) -> String {
+        format!("date_trunc('{unit}', {field})")
+    }
+
     fn parse_text_search(filter: &Map) -> Option<String> {
         let fields = filter.parse_str_array("$fields")?;
         filter.parse_string("$search").map(|search| {
@@ -636,3 +660,46 @@ impl QueryExt<DatabaseDriver> for Query {
         })
     }
 }
+
+/// Encodes the value as a field of `COPY FROM STDIN` in the text format.
+pub(super) fn encode_copy_value(value: Option<&JsonValue>) -> Cow<'_, str> {
+    match value {
+        None | Some(JsonValue::Null) => r"\N".into(),
+        Some(JsonValue::Bool(value)) => {
+            let value = if *value { "t" } else { "f" };
+            value.into()
+        }
+        Some(JsonValue::Number(value)) => value.to_string().into(),
+        Some(JsonValue::String(value)) => escape_copy_text(value),
+        Some(JsonValue::Array(values)) => {
+            let elements = values
+                .iter()
+                .map(|value| match value {
+                    JsonValue::Null => "NULL".to_owned(),
+                    JsonValue::String(value) => {
+                        let value = value.replace('\\', r"\\").replace('"', r#"\""#);
+                        format!(r#""{value}""#)
+                    }
+                    _ => value.to_string(),
+                })
+                .collect::<Vec<_>>();
+            let value = format!("{{{}}}", elements.join(","));
+            escape_copy_text(&value).into_owned().into()
+        }
+        Some(value) => escape_copy_text(&value.to_string()).into_owned().into(),
+    }
+}
+
+/// Escapes the special characters for the text format of `COPY FROM STDIN`.
+fn escape_copy_text(value: &str) -> Cow<'_, str> {
+    if value.contains(['\\', '\t', '\n', '\r']) {
+        value
+            .replace('\\', r"\\")
+            .replace('\t', r"\t")
+            .replace('\n', r"\n")
+            .replace('\r', r"\r")
+            .into()
+    } else {
+        value.into()
+    }
+}
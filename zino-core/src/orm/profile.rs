@@ -0,0 +1,145 @@
+use super::Schema;
+use crate::{
+    error::Error,
+    extension::TomlTableExt,
+    model::{DecodeRow, QueryContext},
+    state::State,
+    JsonValue, Map,
+};
+use futures::TryStreamExt;
+use std::sync::LazyLock;
+
+/// A flag to indicate whether the query plan of a slow query should be explained.
+static EXPLAIN_SLOW_QUERY: LazyLock<bool> = LazyLock::new(|| {
+    State::shared()
+        .get_config("database")
+        .and_then(|config| config.get_bool("explain-slow-query"))
+        .unwrap_or_default()
+});
+
+/// Profiles the query execution of the model `M` for the action.
+///
+/// The execution time is recorded as a histogram for the model and action. If the query
+/// exceeds the slow query threshold, it is logged with the query plan when `explain-slow-query`
+/// has been enabled in the database configuration.
+pub(super) async fn profile_query<M: Schema>(ctx: &QueryContext, action: &'static str) {
+    metrics::histogram!(
+        "zino_model_scan_duration_seconds",
+        ctx.start_time().elapsed().as_secs_f64(),
+        "model" => M::model_name(),
+        "action" => action,
+    );
+    if !ctx.is_slow_query() {
+        return;
+    }
+    if *EXPLAIN_SLOW_QUERY && is_explainable(ctx.query()) {
+        match explain_query::<M>(ctx).await {
+            Ok(plan) => ctx.record_slow_query(Some(&plan)),
+            Err(err) => {
+                ctx.record_slow_query(None);
+                ctx.record_error(format!("fail to explain the slow query: {err}"));
+            }
+        }
+    } else {
+        ctx.record_slow_query(None);
+    }
+}
+
+/// Profiles the failed query execution of the model `M` for the action,
+/// and returns the error.
+///
+/// The execution time is recorded in the same way as [`profile_query`],
+/// and the error is logged with the query and the bound arguments.
+pub(super) fn profile_error<M: Schema>(
+    ctx: &QueryContext,
+    query: &str,
+    action: &'static str,
+    err: impl Into<Error>,
+) -> Error {
+    let err = err.into();
+    let execution_time = ctx.start_time().elapsed();
+    metrics::histogram!(
+        "zino_model_scan_duration_seconds",
+        execution_time.as_secs_f64(),
+        "model" => M::model_name(),
+        "action" => action,
+    );
+    metrics::increment_counter!(
+        "zino_model_scan_errors_total",
+        "model" => M::model_name(),
+        "action" => action,
+    );
+
+    let query_id = ctx.query_id().to_string();
+    let arguments = ctx.format_arguments();
+    let execution_time_millis = execution_time.as_millis();
+    tracing::error!(
+        query_id,
+        query,
+        arguments,
+        execution_time_millis,
+        "fail to execute the query: {err}"
+    );
+    err
+}
+
+/// Returns `true` if the query plan of the statement can be explained.
+fn is_explainable(query: &str) -> bool {
+    ["SELECT", "INSERT", "UPDATE", "DELETE"]
+        .iter()
+        .any(|keyword| starts_with_keyword(query, keyword))
+}
+
+/// Returns `true` if the statement starts with the keyword case-insensitively.
+fn starts_with_keyword(query: &str, keyword: &str) -> bool {
+    query
+        .trim_start()
+        .get(..keyword.len())
+        .is_some_and(|s| s.eq_ignore_ascii_case(keyword))
+}
+
+/// Explains the query plan on the same connection pool as the query execution.
+/// The arguments are bound as strings in the same way as the query execution.
+async fn explain_query<M: Schema>(ctx: &QueryContext) -> Result<String, Error> {
+    let query = ctx.query();
+    let connection_pool = if starts_with_keyword(query, "SELECT") {
+        M::init_reader().await?
+    } else {
+        M::init_writer()?
+    };
+    let sql = if cfg!(any(
+        feature = "orm-mariadb",
+        feature = "orm-mysql",
+        feature = "orm-tidb",
+        feature = "orm-postgres"
+    )) {
+        format!("EXPLAIN {query}")
+    } else {
+        format!("EXPLAIN QUERY PLAN {query}")
+    };
+    let mut explain = sqlx::query(&sql);
+    for argument in ctx.arguments() {
+        explain = explain.bind(argument.as_str());
+    }
+
+    let mut rows = explain.fetch(connection_pool.pool());
+    let mut plan = Vec::new();
+    while let Some(row) = rows.try_next().await? {
+        plan.push(Map::decode_row(&row)?);
+    }
+    Ok(JsonValue::from(plan).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_explainable;
+
+    #[test]
+    fn it_checks_explainable_queries() {
+        assert!(is_explainable("SELECT * FROM user;"));
+        assert!(is_explainable("  update user SET status = 'Active';"));
+        assert!(!is_explainable("COPY user (id, name) FROM STDIN;"));
+        assert!(!is_explainable("ALTER TABLE user ADD COLUMN age INT;"));
+        assert!(!is_explainable("DEL"));
+    }
+}
@@ -1,13 +1,13 @@
-use super::Schema;
+use super::{encryption, json_path, JoinOn, Schema};
 use crate::{
     extension::{JsonObjectExt, JsonValueExt},
-    model::EncodeColumn,
+    model::{Column, EncodeColumn},
     JsonValue, Map, SharedString,
 };
 use std::{borrow::Cow, fmt::Display};
 
 /// Extension trait for [`Query`](crate::model::Query).
-pub(super) trait QueryExt<DB> {
+pub(crate) trait QueryExt<DB> {
     /// Query result type.
     type QueryResult;
 
@@ -29,6 +29,19 @@ pub(super) trait QueryExt<DB> {
     /// Returns the query limit.
     fn query_limit(&self) -> usize;
 
+    /// Returns the cursor values and a flag indicating whether
+    /// the pagination is backward or not.
+    fn query_cursor(&self) -> Option<(&[JsonValue], bool)>;
+
+    /// Returns the tenant ID for the tenant scope.
+    fn query_tenant_id(&self) -> Option<&JsonValue>;
+
+    /// Returns `true` if the default scopes are enabled.
+    fn query_scopes_enabled(&self) -> bool;
+
+    /// Returns `true` if the soft-deleted rows should be included.
+    fn query_show_deleted(&self) -> bool;
+
     /// Returns a placeholder for the n-th parameter.
     fn placeholder(n: usize) -> SharedString;
 
@@ -47,6 +60,9 @@ pub(super) trait QueryExt<DB> {
     /// Formats the table name.
     fn format_table_name<M: Schema>(&self) -> String;
 
+    /// Formats a date bucket for the field.
+    fn format_date_bucket(field: &str, unit: &str) -> String;
+
     /// Parses text search filter.
     fn parse_text_search(filter: &Map) -> Option<String>;
 
@@ -78,32 +94,112 @@ pub(super) trait QueryExt<DB> {
         }
     }
 
+    /// Formats the projection fields for a join query.
+    /// The fields without a table alias are qualified by the model name of `M`.
+    fn format_joined_fields<M: Schema>(&self) -> String {
+        let model_name = M::model_name();
+        let fields = self.query_fields();
+        if fields.is_empty() {
+            let model_name = Self::format_field(model_name);
+            return format!("{model_name}.*");
+        }
+        fields
+            .iter()
+            .map(|field| {
+                if let Some((alias, expr)) = field.split_once(':') {
+                    let alias = Self::format_field(alias.trim());
+                    let expr = expr.trim();
+                    let is_column = expr.split('.').all(|s| {
+                        s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                            && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                    });
+                    if is_column {
+                        let expr = Self::format_field(expr);
+                        format!("{expr} AS {alias}")
+                    } else {
+                        format!("{expr} AS {alias}")
+                    }
+                } else if field.contains('.') {
+                    Self::format_field(field).into_owned()
+                } else {
+                    let field = [model_name, field].join(".");
+                    Self::format_field(&field).into_owned()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Formats the default scopes of the model to generate SQL conditions.
+    /// The tenant scope fails closed, i.e. no rows are selected if the tenant ID
+    /// has not been specified.
+    fn format_scopes<M: Schema>(&self) -> Vec<String> {
+        let mut conditions = Vec::new();
+        if !self.query_scopes_enabled() {
+            return conditions;
+        }
+
+        let model_name = M::model_name();
+        if M::SOFT_DELETE
+            && !self.query_show_deleted()
+            && let Some(col) = M::get_column("status")
+        {
+            let field = [model_name, "status"].join(".");
+            let value = JsonValue::from(Map::from_entry("$ne", "Deleted"));
+            conditions.push(col.format_filter(&field, &value));
+        }
+        if let Some(tenant_scope) = M::TENANT_SCOPE {
+            if let Some(col) = M::get_column(tenant_scope)
+                && let Some(tenant_id) = self.query_tenant_id()
+                && !tenant_id.is_null()
+            {
+                let field = [model_name, tenant_scope].join(".");
+                conditions.push(col.format_filter(&field, tenant_id));
+            } else {
+                conditions.push("FALSE".to_owned());
+            }
+        }
+        conditions.retain(|condition| !condition.is_empty());
+        conditions
+    }
+
     /// Formats the query filters to generate SQL `WHERE` expression.
+    #[inline]
     fn format_filters<M: Schema>(&self) -> String {
+        self.format_joined_filters::<M>(&[])
+    }
+
+    /// Formats the query filters to generate SQL `WHERE` expression,
+    /// where the columns of the joined models are also resolved.
+    fn format_joined_filters<M: Schema>(&self, joins: &[JoinOn]) -> String {
         let filters = self.query_filters();
-        if filters.is_empty() {
+        let keyset_condition = self.format_keyset_filter::<M>();
+        let mut conditions = self.format_scopes::<M>();
+        if filters.is_empty() && keyset_condition.is_none() && conditions.is_empty() {
             return String::new();
         }
 
         let mut expression = String::new();
-        let mut conditions = Vec::with_capacity(filters.len());
+        if let Some(condition) = keyset_condition {
+            conditions.push(condition);
+        }
         for (key, value) in filters {
             match key.as_str() {
                 "$and" => {
                     if let Some(filters) = value.as_array() {
-                        let condition = Self::format_logical_filters::<M>(filters, " AND ");
+                        let condition = Self::format_logical_filters::<M>(filters, " AND ", joins);
                         conditions.push(condition);
                     }
                 }
                 "$not" => {
                     if let Some(filters) = value.as_array() {
-                        let condition = Self::format_logical_filters::<M>(filters, " AND ");
+                        let condition = Self::format_logical_filters::<M>(filters, " AND ", joins);
                         conditions.push(format!("(NOT {condition})"));
                     }
                 }
                 "$or" => {
                     if let Some(filters) = value.as_array() {
-                        let condition = Self::format_logical_filters::<M>(filters, " OR ");
+                        let condition = Self::format_logical_filters::<M>(filters, " OR ", joins);
                         conditions.push(condition);
                     }
                 }
@@ -132,8 +228,26 @@ pub(super) trait QueryExt<DB> {
                     }
                 }
                 _ => {
-                    if let Some(col) = M::get_column(key) {
-                        let condition = col.format_filter(key, value);
+                    if let Some(col) = M::get_column(key)
+                        .or_else(|| joins.iter().find_map(|join| join.get_column(key)))
+                    {
+                        if encryption::is_encrypted(col) {
+                            let condition = Self::format_encrypted_filter::<M>(col, value);
+                            conditions.push(condition);
+                            continue;
+                        }
+
+                        let condition =
+                            if col.type_name() == "Map" && json_path::has_json_operators(value) {
+                                json_path::format_json_filter(key, &[], value)
+                            } else {
+                                col.format_filter(key, value)
+                            };
+                        if !condition.is_empty() {
+                            conditions.push(condition);
+                        }
+                    } else if let Some((column_key, path)) = json_path::parse_json_path::<M>(key) {
+                        let condition = json_path::format_json_filter(column_key, &path, value);
                         if !condition.is_empty() {
                             conditions.push(condition);
                         }
@@ -157,7 +271,7 @@ pub(super) trait QueryExt<DB> {
                 .join(", ");
             expression += &format!(" GROUP BY {groups}");
             if let Some(filters) = filters.get_array("$having") {
-                let condition = Self::format_logical_filters::<M>(filters, " AND ");
+                let condition = Self::format_logical_filters::<M>(filters, " AND ", joins);
                 expression += &format!(" HAVING {condition}");
             }
         }
@@ -165,7 +279,11 @@ pub(super) trait QueryExt<DB> {
     }
 
     // Formats the filters with a logic operator.
-    fn format_logical_filters<M: Schema>(filters: &[JsonValue], operator: &str) -> String {
+    fn format_logical_filters<M: Schema>(
+        filters: &[JsonValue],
+        operator: &str,
+        joins: &[JoinOn],
+    ) -> String {
         let mut conditions = Vec::with_capacity(filters.len());
         for filter in filters {
             if let JsonValue::Object(filter) = filter {
@@ -173,31 +291,57 @@ pub(super) trait QueryExt<DB> {
                     match key.as_str() {
                         "$and" => {
                             if let Some(filters) = value.as_array() {
-                                let condition = Self::format_logical_filters::<M>(filters, " AND ");
+                                let condition =
+                                    Self::format_logical_filters::<M>(filters, " AND ", joins);
                                 conditions.push(condition);
                             }
                         }
                         "$not" => {
                             if let Some(filters) = value.as_array() {
-                                let condition = Self::format_logical_filters::<M>(filters, " AND ");
+                                let condition =
+                                    Self::format_logical_filters::<M>(filters, " AND ", joins);
                                 conditions.push(format!("(NOT {condition})"));
                             }
                         }
                         "$nor" => {
                             if let Some(filters) = value.as_array() {
-                                let condition = Self::format_logical_filters::<M>(filters, " OR ");
+                                let condition =
+                                    Self::format_logical_filters::<M>(filters, " OR ", joins);
                                 conditions.push(format!("(NOT {condition})"));
                             }
                         }
                         "$or" => {
                             if let Some(filters) = value.as_array() {
-                                let condition = Self::format_logical_filters::<M>(filters, " OR ");
+                                let condition =
+                                    Self::format_logical_filters::<M>(filters, " OR ", joins);
                                 conditions.push(condition);
                             }
                         }
                         _ => {
-                            if let Some(col) = M::get_column(key) {
-                                let condition = col.format_filter(key, value);
+                            if let Some(col) = M::get_column(key)
+                                .or_else(|| joins.iter().find_map(|join| join.get_column(key)))
+                            {
+                                if encryption::is_encrypted(col) {
+                                    let condition = Self::format_encrypted_filter::<M>(col, value);
+                                    conditions.push(condition);
+                                    continue;
+                                }
+
+                                let condition = if col.type_name() == "Map"
+                                    && json_path::has_json_operators(value)
+                                {
+                                    json_path::format_json_filter(key, &[], value)
+                                } else {
+                                    col.format_filter(key, value)
+                                };
+                                if !condition.is_empty() {
+                                    conditions.push(condition);
+                                }
+                            } else if let Some((column_key, path)) =
+                                json_path::parse_json_path::<M>(key)
+                            {
+                                let condition =
+                                    json_path::format_json_filter(column_key, &path, value);
                                 if !condition.is_empty() {
                                     conditions.push(condition);
                                 }
@@ -219,6 +363,28 @@ pub(super) trait QueryExt<DB> {
         }
     }
 
+    /// Formats a filter on the column with the `encrypted` attribute.
+    /// The filter is evaluated on the blind index if possible;
+    /// otherwise it is rejected and no rows will be matched.
+    fn format_encrypted_filter<M: Schema>(col: &Column<'_>, value: &JsonValue) -> String {
+        let column_name = col.name();
+        if let Some(blind_index_field) = col.extra().get_str("blind_index")
+            && let Some(blind_index_col) = M::get_column(blind_index_field)
+            && let Some(value) = encryption::encode_blind_index(value)
+        {
+            let condition = blind_index_col.format_filter(blind_index_field, &value);
+            if !condition.is_empty() {
+                return condition;
+            }
+        }
+        tracing::warn!(
+            model_name = M::model_name(),
+            column_name,
+            "the filter on the encrypted column `{column_name}` is rejected",
+        );
+        "FALSE".to_owned()
+    }
+
     /// Formats a query filter.
     fn format_filter(key: &str, value: &JsonValue) -> String {
         if let Some(filter) = value.as_object() {
@@ -254,8 +420,75 @@ pub(super) trait QueryExt<DB> {
         }
     }
 
+    /// Returns the sort order used for the keyset pagination.
+    /// The primary key is appended as a tie-breaker if it is absent.
+    fn keyset_order<M: Schema>(&self) -> Vec<(&str, bool)> {
+        let primary_key_name = M::PRIMARY_KEY_NAME;
+        let mut sort_order = self
+            .query_order()
+            .iter()
+            .map(|(field, descending)| (field.as_ref() as &str, *descending))
+            .collect::<Vec<_>>();
+        if !sort_order
+            .iter()
+            .any(|&(field, _)| field == primary_key_name)
+        {
+            let descending = sort_order.last().is_some_and(|&(_, descending)| descending);
+            sort_order.push((primary_key_name, descending));
+        }
+        sort_order
+    }
+
+    /// Formats the keyset condition for the rows after or before the cursor.
+    /// A cursor which does not match the keyset order matches no rows.
+    fn format_keyset_filter<M: Schema>(&self) -> Option<String> {
+        let (values, backward) = self.query_cursor()?;
+        let sort_order = self.keyset_order::<M>();
+        if values.len() != sort_order.len() {
+            return Some("FALSE".to_owned());
+        }
+
+        let mut conditions = Vec::with_capacity(values.len());
+        let mut equalities = Vec::with_capacity(values.len());
+        for (&(field, descending), value) in sort_order.iter().zip(values) {
+            let value = if let Some(col) = M::get_column(field) {
+                col.encode_value(Some(value)).into_owned()
+            } else if let Some(value) = value.parse_string() {
+                Self::escape_string(value)
+            } else {
+                "NULL".to_owned()
+            };
+            let field = Self::format_field(field);
+            let operator = if descending != backward { "<" } else { ">" };
+            let condition = format!("{field} {operator} {value}");
+            if equalities.is_empty() {
+                conditions.push(condition);
+            } else {
+                conditions.push(format!("({} AND {condition})", equalities.join(" AND ")));
+            }
+            equalities.push(format!("{field} = {value}"));
+        }
+        Some(format!("({})", conditions.join(" OR ")))
+    }
+
     /// Formats the query sort to generate SQL `ORDER BY` expression.
-    fn format_sort(&self) -> String {
+    /// In the keyset pagination, the sort order is reversed for a backward cursor.
+    fn format_sort<M: Schema>(&self) -> String {
+        if let Some((_, backward)) = self.query_cursor() {
+            let sort_order = self
+                .keyset_order::<M>()
+                .into_iter()
+                .map(|(sort, descending)| {
+                    if descending != backward {
+                        format!("{sort} DESC")
+                    } else {
+                        format!("{sort} ASC")
+                    }
+                })
+                .collect::<Vec<_>>();
+            return format!("ORDER BY {}", sort_order.join(", "));
+        }
+
         let sort_order = self.query_order();
         if sort_order.is_empty() {
             String::new()
@@ -281,7 +514,152 @@ pub(super) trait QueryExt<DB> {
             return String::new();
         }
 
+        if self.query_cursor().is_some() {
+            return format!("LIMIT {limit}");
+        }
+
         let offset = self.query_offset();
         format!("LIMIT {limit} OFFSET {offset}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::QueryExt;
+    use crate::{
+        bail,
+        error::Error,
+        model::{Column, Model, ModelHooks, Query},
+        orm::{schema::format_count_statement, ConnectionPool, Schema},
+        Map, Uuid,
+    };
+    use serde::{Deserialize, Serialize};
+    use std::sync::LazyLock;
+
+    #[derive(Debug, Default, Serialize, Deserialize)]
+    struct Account {
+        id: Uuid,
+        email: String,
+        email_hash: String,
+        status: String,
+        tenant_id: Uuid,
+    }
+
+    impl Model for Account {}
+
+    impl ModelHooks for Account {}
+
+    impl Schema for Account {
+        const MODEL_NAME: &'static str = "account";
+        const TABLE_NAME: Option<&'static str> = Some("dc_account");
+        const SOFT_DELETE: bool = true;
+        const TENANT_SCOPE: Option<&'static str> = Some("tenant_id");
+
+        fn primary_key(&self) -> &Uuid {
+            &self.id
+        }
+
+        fn schema() -> &'static apache_avro::Schema {
+            static SCHEMA: LazyLock<apache_avro::Schema> =
+                LazyLock::new(|| apache_avro::Schema::Null);
+            &SCHEMA
+        }
+
+        fn columns() -> &'static [Column<'static>] {
+            static COLUMNS: LazyLock<Vec<Column<'static>>> = LazyLock::new(|| {
+                let mut email = Column::new("email", "String", false);
+                email.set_extra_attribute("encrypted", true);
+                email.set_extra_attribute("blind_index", "email_hash");
+                vec![
+                    Column::new("id", "Uuid", true),
+                    email,
+                    Column::new("email_hash", "String", false),
+                    Column::new("status", "String", false),
+                    Column::new("tenant_id", "Uuid", false),
+                ]
+            });
+            &COLUMNS
+        }
+
+        fn fields() -> &'static [&'static str] {
+            &["id", "email", "email_hash", "status", "tenant_id"]
+        }
+
+        fn read_only_fields() -> &'static [&'static str] {
+            &["id"]
+        }
+
+        fn write_only_fields() -> &'static [&'static str] {
+            &["email_hash"]
+        }
+
+        async fn acquire_reader() -> Result<&'static ConnectionPool, Error> {
+            bail!("the connection pool is not available in tests");
+        }
+
+        async fn acquire_writer() -> Result<&'static ConnectionPool, Error> {
+            bail!("the connection pool is not available in tests");
+        }
+    }
+
+    #[test]
+    fn it_formats_encrypted_filters() {
+        let _ = crate::application::SECRET_KEY.set([0; 64]);
+
+        let mut query = Query::default();
+        query.set_tenant_id(Uuid::nil().to_string());
+        query.add_filter("email", "alice@example.com");
+        let filters = query.format_filters::<Account>();
+        assert!(filters.contains("email_hash"));
+        assert!(!filters.contains("alice@example.com"));
+
+        let mut query = Query::default();
+        query.set_tenant_id(Uuid::nil().to_string());
+        query.add_filter("$or", vec![Map::from_entry("email", "alice@example.com")]);
+        let filters = query.format_filters::<Account>();
+        assert!(filters.contains("email_hash"));
+        assert!(!filters.contains("alice@example.com"));
+
+        let mut query = Query::default();
+        query.set_tenant_id(Uuid::nil().to_string());
+        query.add_filter("email", Map::from_entry("$regex", "^alice"));
+        assert!(query.format_filters::<Account>().contains("FALSE"));
+    }
+
+    #[test]
+    fn it_formats_default_scopes() {
+        let mut query = Query::default();
+        assert!(query.format_filters::<Account>().contains("FALSE"));
+
+        let tenant_id = Uuid::now_v7().to_string();
+        query.set_tenant_id(tenant_id.as_str());
+        let filters = query.format_filters::<Account>();
+        assert!(filters.contains(&tenant_id));
+        assert!(filters.contains("Deleted"));
+        assert!(!filters.contains("FALSE"));
+
+        let mut query = Query::default();
+        let _ = query.read_map(&Map::from_entry("show_deleted", true));
+        query.set_tenant_id(tenant_id.as_str());
+        assert!(query.format_filters::<Account>().contains("Deleted"));
+
+        query.allow_show_deleted();
+        assert!(!query.format_filters::<Account>().contains("Deleted"));
+
+        let mut query = Query::default();
+        query.disable_scopes();
+        assert!(query.format_filters::<Account>().is_empty());
+    }
+    #[test]
+    fn it_formats_scoped_counts() {
+        let mut query = Query::default();
+        query.set_tenant_id(Uuid::nil().to_string());
+        let table_name = query.format_table_name::<Account>();
+        assert!(table_name.contains("dc_account"));
+
+        let sql = format_count_statement::<Account>(&query);
+        assert!(sql.starts_with(&format!("SELECT count(*) FROM {table_name} ")));
+        assert!(sql.contains(Query::format_field("account.status").as_ref()));
+        assert!(sql.contains(Query::format_field("account.tenant_id").as_ref()));
+    }
+}
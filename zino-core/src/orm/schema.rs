@@ -1,18 +1,27 @@
 use super::{
-    column::ColumnExt, mutation::MutationExt, query::QueryExt, ConnectionPool, DatabaseDriver,
-    DatabaseRow, ModelHelper,
+    aggregation::AggregationExt, cache, column::ColumnExt, constraint, encryption, migration,
+    mutation::MutationExt, profile, query::QueryExt, search, ConnectionPool, DatabaseConnection,
+    DatabaseDriver, DatabaseRow, JoinOn, ModelHelper,
 };
 use crate::{
     bail,
     error::Error,
     extension::{JsonObjectExt, JsonValueExt},
-    model::{Column, DecodeRow, EncodeColumn, ModelHooks, Mutation, Query, QueryContext},
+    model::{
+        Aggregation, Column, DecodeRow, EncodeColumn, Index, ModelHooks, Mutation, Query,
+        QueryContext,
+    },
     warn, BoxFuture, JsonValue, Map, Uuid,
 };
-use futures::TryStreamExt;
+use futures::{
+    channel::mpsc,
+    future::{self, FutureExt},
+    stream::{self, Stream, StreamExt, TryStreamExt},
+    Future, SinkExt,
+};
 use serde::de::DeserializeOwned;
 use sqlx::{Decode, Row, Transaction, Type};
-use std::{fmt::Display, sync::atomic::Ordering::Relaxed};
+use std::{fmt::Display, sync::atomic::Ordering::Relaxed, time::Duration};
 
 /// Database schema.
 ///
@@ -31,6 +40,14 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
     const WRITER_NAME: &'static str = "main";
     /// Optional custom table name.
     const TABLE_NAME: Option<&'static str> = None;
+    /// A flag to exclude the rows with the status `Deleted` from queries by default.
+    const SOFT_DELETE: bool = false;
+    /// Optional column constrained by the tenant ID of queries.
+    const TENANT_SCOPE: Option<&'static str> = None;
+    /// A flag to record the changes of the model in the audit logs.
+    const AUDIT: bool = false;
+    /// A flag to enforce the access policies in the default controller.
+    const ACCESS_CONTROL: bool = false;
 
     /// Returns the primary key.
     fn primary_key(&self) -> &Self::PrimaryKey;
@@ -137,6 +154,20 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         Self::columns().iter().any(|col| col.name() == key)
     }
 
+    /// Returns a reference to the indexes declared on the model,
+    /// which are not derived from the `index_type` of the columns.
+    #[inline]
+    fn indexes() -> &'static [Index<'static>] {
+        &[]
+    }
+
+    /// Returns the time-to-live of the cached query results,
+    /// or `None` if the query cache is not enabled for the model.
+    #[inline]
+    fn cache_ttl() -> Option<Duration> {
+        None
+    }
+
     /// Constructs a default `Query` for the model.
     #[inline]
     fn default_query() -> Query {
@@ -155,11 +186,12 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         mutation
     }
 
-    /// Initializes the model reader.
+    /// Initializes the model reader, which may be a read replica.
     #[inline]
-    fn init_reader() -> Result<&'static ConnectionPool, Error> {
+    async fn init_reader() -> Result<&'static ConnectionPool, Error> {
         super::SHARED_CONNECTION_POOLS
-            .get_pool(Self::READER_NAME)
+            .get_reader(Self::READER_NAME)
+            .await
             .ok_or_else(|| warn!("connection to the database is unavailable"))
     }
 
@@ -176,21 +208,8 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let pool = Self::init_writer()?.pool();
         Self::before_create_table().await?;
 
-        let primary_key_name = Self::PRIMARY_KEY_NAME;
         let table_name = Self::table_name();
-        let columns = Self::columns();
-        let mut definitions = columns
-            .iter()
-            .map(|col| col.field_definition(primary_key_name))
-            .collect::<Vec<_>>();
-        for col in columns {
-            let mut constraints = col.constraints();
-            if !constraints.is_empty() {
-                definitions.append(&mut constraints);
-            }
-        }
-
-        let definitions = definitions.join(",\n  ");
+        let definitions = migration::format_table_definitions::<Self>();
         let sql = format!("CREATE TABLE IF NOT EXISTS {table_name} (\n  {definitions}\n);");
         sqlx::query(&sql).execute(pool).await?;
         Self::after_create_table().await?;
@@ -198,72 +217,29 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
     }
 
     /// Synchronizes the table schema for the model.
+    ///
+    /// It only adds the missing columns and the missing indexes declared on the model,
+    /// and reports the indexes which are not declared. See [`Migration`](super::Migration)
+    /// for the other changes of the table schema.
     async fn synchronize_schema() -> Result<(), Error> {
         let connection_pool = Self::init_writer()?;
         let pool = connection_pool.pool();
 
         let table_name = Self::table_name();
-        let sql = if cfg!(any(
-            feature = "orm-mariadb",
-            feature = "orm-mysql",
-            feature = "orm-tidb"
-        )) {
-            let table_schema = connection_pool.database();
-            format!(
-                "SELECT column_name, data_type, column_default, is_nullable \
-                    FROM information_schema.columns \
-                        WHERE table_schema = '{table_schema}' AND table_name = '{table_name}';"
-            )
-        } else if cfg!(feature = "orm-postgres") {
-            format!(
-                "SELECT column_name, data_type, column_default, is_nullable \
-                    FROM information_schema.columns \
-                        WHERE table_schema = 'public' AND table_name = '{table_name}';"
-            )
-        } else {
-            format!(
-                "SELECT p.name AS column_name, p.type AS data_type, \
-                        p.dflt_value AS column_default, p.[notnull] AS is_not_null \
-                    FROM sqlite_master m LEFT OUTER JOIN pragma_table_info((m.name)) p
-                        ON m.name <> p.name WHERE m.name = '{table_name}';"
-            )
-        };
-        let mut rows = sqlx::query(&sql).fetch(pool);
-        let mut data = Vec::new();
-        while let Some(row) = rows.try_next().await? {
-            data.push(Map::decode_row(&row)?);
-        }
+        let data = migration::fetch_table_columns(connection_pool, table_name).await?;
 
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         for col in Self::columns() {
             let column_name = col.name();
-            let column_opt = data.iter().find(|d| {
-                d.get_str("column_name")
-                    .or_else(|| d.get_str("COLUMN_NAME"))
-                    == Some(column_name)
-            });
-            if let Some(d) = column_opt
-                && let Some(data_type) = d.get_str("data_type").or_else(|| d.get_str("DATA_TYPE"))
-            {
-                let column_default = d
-                    .get_str("column_default")
-                    .or_else(|| d.get_str("COLUMN_DEFAULT"));
-                let is_not_null = if cfg!(any(feature = "orm-mysql", feature = "orm-postgres")) {
-                    d.get_str("is_nullable")
-                        .or_else(|| d.get_str("IS_NULLABLE"))
-                        .unwrap_or("YES")
-                        .eq_ignore_ascii_case("NO")
-                } else {
-                    d.get_str("is_not_null") == Some("1")
-                };
-                if col.is_not_null() != is_not_null && column_name != primary_key_name {
+            if let Some(d) = data.iter().find(|d| d.name == column_name) {
+                if col.is_not_null() != d.not_null && column_name != primary_key_name {
                     tracing::warn!(
                         model_name = Self::model_name(),
                         table_name,
                         column_name,
-                        data_type,
-                        column_default,
-                        is_not_null,
+                        data_type = d.data_type.as_str(),
+                        column_default = d.default_value.as_deref(),
+                        is_not_null = d.not_null,
                         "the `NOT NULL` constraint of the column `{column_name}` should be updated",
                     );
                 }
@@ -279,34 +255,34 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
                 );
             }
         }
-        Ok(())
+        migration::synchronize_indexes::<Self>(connection_pool).await
     }
 
     /// Creates indexes for the model.
+    ///
+    /// The indexes are derived from the `index_type` of the columns,
+    /// together with the composite or partial indexes declared on the model.
     async fn create_indexes() -> Result<u64, Error> {
-        let pool = Self::init_writer()?.pool();
+        let connection_pool = Self::init_writer()?;
+        let pool = connection_pool.pool();
 
         let table_name = Self::table_name();
         let columns = Self::columns();
+        let index_names = migration::fetch_table_indexes(connection_pool, table_name).await?;
         let mut rows = 0;
         if cfg!(any(
             feature = "orm-mariadb",
             feature = "orm-mysql",
             feature = "orm-tidb"
         )) {
-            let sql = format!("SHOW INDEXES FROM {table_name}");
-            let indexes = sqlx::query(&sql).fetch_all(pool).await?;
-            if indexes.len() > 1 {
-                return Ok(0);
-            }
-
-            let mut text_search_columns = Vec::new();
+            // MySQL does not support the `IF NOT EXISTS` clause for indexes.
             for col in columns {
                 if let Some(index_type) = col.index_type() {
                     let column_name = col.name();
-                    if matches!(index_type, "fulltext" | "text") {
-                        text_search_columns.push(column_name);
-                    } else if matches!(index_type, "unique" | "spatial") {
+                    if index_names.contains(&format!("{table_name}_{column_name}_index")) {
+                        continue;
+                    }
+                    if matches!(index_type, "unique" | "spatial") {
                         let index_type = index_type.to_uppercase();
                         let sql = format!(
                             "CREATE {index_type} INDEX {table_name}_{column_name}_index \
@@ -331,11 +307,14 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
                     }
                 }
             }
-            if !text_search_columns.is_empty() {
-                let text_search_columns = text_search_columns.join(", ");
+            // The text search columns are shared with the `MATCH` expression of the search.
+            let text_search_fields = search::format_text_search_fields::<Self>();
+            if !text_search_fields.is_empty()
+                && !index_names.contains(&format!("{table_name}_text_search_index"))
+            {
                 let sql = format!(
                     "CREATE FULLTEXT INDEX {table_name}_text_search_index \
-                        ON {table_name} ({text_search_columns});"
+                        ON {table_name} ({text_search_fields});"
                 );
                 rows = sqlx::query(&sql)
                     .execute(pool)
@@ -411,16 +390,48 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
                         .max(rows);
                 }
             }
+
+            let text_search_columns = search::text_search_columns::<Self>()
+                .iter()
+                .map(|col| col.name())
+                .collect::<Vec<_>>();
+            if !text_search_columns.is_empty() {
+                rows = search::synchronize_fts_table(
+                    connection_pool,
+                    table_name,
+                    &text_search_columns,
+                )
+                .await?
+                .max(rows);
+            }
+        }
+        for index in Self::indexes() {
+            if !index_names.contains(&index.index_name(table_name))
+                && let Some(sql) = migration::format_index_definition(table_name, index)
+            {
+                rows = sqlx::query(&sql)
+                    .execute(pool)
+                    .await?
+                    .rows_affected()
+                    .max(rows);
+            }
         }
         Ok(rows)
     }
 
     /// Inserts the model into the table.
-    async fn insert(mut self) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+    async fn insert(self) -> Result<QueryContext, Error> {
+        let mut conn = Self::acquire_writer().await?.pool().acquire().await?;
+        self.insert_with(&mut conn).await
+    }
+
+    /// Inserts the model into the table,
+    /// using the specific database connection.
+    async fn insert_with(mut self, conn: &mut DatabaseConnection) -> Result<QueryContext, Error> {
         let model_data = self.before_insert().await?;
 
-        let map = self.into_map();
+        let mut map = self.into_map();
+        encryption::encrypt_model::<Self>(&mut map)?;
         let table_name = Self::table_name();
         let columns = Self::columns();
 
@@ -442,8 +453,14 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let sql = format!("INSERT INTO {table_name} ({fields}) VALUES ({values});");
 
         let mut ctx = Self::before_scan(&sql).await?;
-        let query_result = sqlx::query(&sql).execute(pool).await?;
+        super::scope::record_write(Self::WRITER_NAME);
+        let query_result = sqlx::query(&sql)
+            .execute(&mut *conn)
+            .await
+            .map_err(constraint::map_database_error::<Self>)
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "insert", err))?;
         let (last_insert_id, rows_affected) = Query::parse_query_result(query_result);
+        cache::invalidate::<Self>();
         let success = rows_affected == 1;
         if let Some(last_insert_id) = last_insert_id {
             ctx.set_last_insert_id(last_insert_id);
@@ -451,6 +468,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "insert").await;
         Self::after_insert(&ctx, model_data).await?;
         if success {
             Ok(ctx)
@@ -463,14 +481,17 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
     }
 
     /// Inserts many models into the table.
+    /// The `after_insert` hook is called for each model after all of them have been inserted.
     async fn insert_many(models: Vec<Self>) -> Result<QueryContext, Error> {
         let pool = Self::acquire_writer().await?.pool();
         let columns = Self::columns();
         let mut values = Vec::with_capacity(models.len());
+        let mut models_data = Vec::with_capacity(models.len());
         for mut model in models.into_iter() {
-            let _model_data = model.before_insert().await?;
+            models_data.push(model.before_insert().await?);
 
-            let map = model.into_map();
+            let mut map = model.into_map();
+            encryption::encrypt_model::<Self>(&mut map)?;
             let entries = columns
                 .iter()
                 .map(|col| col.encode_value(map.get(col.name())))
@@ -484,23 +505,140 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let sql = format!("INSERT INTO {table_name} ({fields}) VALUES {values};");
 
         let mut ctx = Self::before_scan(&sql).await?;
-        let query_result = sqlx::query(&sql).execute(pool).await?;
+        super::scope::record_write(Self::WRITER_NAME);
+        let query_result = sqlx::query(&sql)
+            .execute(pool)
+            .await
+            .map_err(constraint::map_database_error::<Self>)
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "insert_many", err))?;
         let rows_affected = query_result.rows_affected();
+        cache::invalidate::<Self>();
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "insert_many").await;
+        for model_data in models_data {
+            Self::after_insert(&ctx, model_data).await?;
+        }
         Ok(ctx)
     }
 
+    /// Loads many models into the table in bulk, which is intended for very large imports.
+    ///
+    /// For PostgreSQL, the rows are sent by `COPY FROM STDIN` in the text format,
+    /// so the default values of the columns will not be applied to the `null` values.
+    /// For other database drivers, the models are inserted with multi-row `INSERT` statements
+    /// in chunks, which are executed in a transaction. The `after_insert` hook is called
+    /// for each model after all of them have been loaded.
+    async fn bulk_load(models: Vec<Self>) -> Result<QueryContext, Error> {
+        let table_name = Self::table_name();
+        let mut models_data = Vec::with_capacity(models.len());
+        let columns = Self::columns()
+            .iter()
+            .filter(|col| !col.auto_increment())
+            .collect::<Vec<_>>();
+        let fields = columns
+            .iter()
+            .map(|col| col.name())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        #[cfg(all(
+            feature = "orm-postgres",
+            not(any(feature = "orm-mariadb", feature = "orm-mysql", feature = "orm-tidb"))
+        ))]
+        {
+            use sqlx::postgres::PgPoolCopyExt;
+
+            let mut data = Vec::new();
+            for mut model in models.into_iter() {
+                models_data.push(model.before_insert().await?);
+
+                let mut map = model.into_map();
+                encryption::encrypt_model::<Self>(&mut map)?;
+                let entries = columns
+                    .iter()
+                    .map(|col| super::postgres::encode_copy_value(map.get(col.name())))
+                    .collect::<Vec<_>>();
+                data.extend_from_slice(entries.join("\t").as_bytes());
+                data.push(b'\n');
+            }
+
+            let pool = Self::acquire_writer().await?.pool();
+            let sql = format!("COPY {table_name} ({fields}) FROM STDIN;");
+            let mut ctx = Self::before_scan(&sql).await?;
+            super::scope::record_write(Self::WRITER_NAME);
+            let mut copy_in = pool
+                .copy_in_raw(&sql)
+                .await
+                .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "bulk_load", err))?;
+            for chunk in data.chunks(COPY_CHUNK_SIZE) {
+                copy_in
+                    .send(chunk)
+                    .await
+                    .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "bulk_load", err))?;
+            }
+            let rows_affected = copy_in
+                .finish()
+                .await
+                .map_err(constraint::map_database_error::<Self>)
+                .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "bulk_load", err))?;
+            cache::invalidate::<Self>();
+            ctx.set_query(sql);
+            ctx.set_query_result(Some(rows_affected), true);
+            Self::after_scan(&ctx).await?;
+            profile::profile_query::<Self>(&ctx, "bulk_load").await;
+            for model_data in models_data {
+                Self::after_insert(&ctx, model_data).await?;
+            }
+            Ok(ctx)
+        }
+        #[cfg(not(all(
+            feature = "orm-postgres",
+            not(any(feature = "orm-mariadb", feature = "orm-mysql", feature = "orm-tidb"))
+        )))]
+        {
+            let mut values = Vec::with_capacity(models.len());
+            for mut model in models.into_iter() {
+                models_data.push(model.before_insert().await?);
+
+                let mut map = model.into_map();
+                encryption::encrypt_model::<Self>(&mut map)?;
+                let entries = columns
+                    .iter()
+                    .map(|col| col.encode_value(map.get(col.name())))
+                    .collect::<Vec<_>>();
+                values.push(format!("({})", entries.join(", ")));
+            }
+
+            let statements = format_insert_statements(table_name, &fields, values, "");
+            let ctx = execute_statements::<Self>(statements).await?;
+            for model_data in models_data {
+                Self::after_insert(&ctx, model_data).await?;
+            }
+            Ok(ctx)
+        }
+    }
+
     /// Updates the model in the table.
-    async fn update(mut self) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+    async fn update(self) -> Result<QueryContext, Error> {
+        let mut conn = Self::acquire_writer().await?.pool().acquire().await?;
+        self.update_with(&mut conn).await
+    }
+
+    /// Updates the model in the table,
+    /// using the specific database connection.
+    ///
+    /// The row is also matched by the tenant scope of the model if there is one.
+    async fn update_with(mut self, conn: &mut DatabaseConnection) -> Result<QueryContext, Error> {
         let model_data = self.before_update().await?;
 
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         let table_name = Self::table_name();
         let primary_key = Query::escape_string(self.primary_key());
-        let map = self.into_map();
+        let mut map = self.into_map();
+        let tenant_condition = format_tenant_condition::<Self>(&map);
+        encryption::encrypt_model::<Self>(&mut map)?;
         let read_only_fields = Self::read_only_fields();
         let num_writable_fields = Self::fields().len() - read_only_fields.len();
         let mut mutations = Vec::with_capacity(num_writable_fields);
@@ -515,16 +653,24 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
         let mutations = mutations.join(", ");
         let sql = format!(
-            "UPDATE {table_name} SET {mutations} WHERE {primary_key_name} = {primary_key};"
+            "UPDATE {table_name} SET {mutations} \
+                WHERE {primary_key_name} = {primary_key}{tenant_condition};"
         );
 
         let mut ctx = Self::before_scan(&sql).await?;
-        let query_result = sqlx::query(&sql).execute(pool).await?;
+        super::scope::record_write(Self::WRITER_NAME);
+        let query_result = sqlx::query(&sql)
+            .execute(&mut *conn)
+            .await
+            .map_err(constraint::map_database_error::<Self>)
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "update", err))?;
         let rows_affected = query_result.rows_affected();
+        cache::invalidate::<Self>();
         let success = rows_affected == 1;
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "update").await;
         Self::after_update(&ctx, model_data).await?;
         if success {
             Ok(ctx)
@@ -538,13 +684,23 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
     /// Updates at most one model selected by the query in the table.
     async fn update_one(query: &Query, mutation: &mut Mutation) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+        let mut conn = Self::acquire_writer().await?.pool().acquire().await?;
+        Self::update_one_with(&mut conn, query, mutation).await
+    }
+
+    /// Updates at most one model selected by the query in the table,
+    /// using the specific database connection.
+    async fn update_one_with(
+        conn: &mut DatabaseConnection,
+        query: &Query,
+        mutation: &mut Mutation,
+    ) -> Result<QueryContext, Error> {
         Self::before_mutation(query, mutation).await?;
 
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         let table_name = query.format_table_name::<Self>();
         let filters = query.format_filters::<Self>();
-        let updates = mutation.format_updates::<Self>();
+        let updates = mutation.format_updates::<Self>()?;
         let sql = if cfg!(any(
             feature = "orm-mariadb",
             feature = "orm-mysql",
@@ -558,7 +714,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
             )
         } else {
             // Both PostgreQL and SQLite support a `LIMIT` in subquery
-            let sort = query.format_sort();
+            let sort = query.format_sort::<Self>();
             format!(
                 "UPDATE {table_name} SET {updates} WHERE {primary_key_name} IN \
                     (SELECT {primary_key_name} FROM {table_name} {filters} {sort} LIMIT 1);"
@@ -566,12 +722,19 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         };
 
         let mut ctx = Self::before_scan(&sql).await?;
-        let query_result = sqlx::query(&sql).execute(pool).await?;
+        super::scope::record_write(Self::WRITER_NAME);
+        let query_result = sqlx::query(&sql)
+            .execute(&mut *conn)
+            .await
+            .map_err(constraint::map_database_error::<Self>)
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "update_one", err))?;
         let rows_affected = query_result.rows_affected();
+        cache::invalidate::<Self>();
         let success = rows_affected <= 1;
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "update_one").await;
         Self::after_mutation(&ctx).await?;
         if success {
             Ok(ctx)
@@ -585,30 +748,54 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
     /// Updates many models selected by the query in the table.
     async fn update_many(query: &Query, mutation: &mut Mutation) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+        let mut conn = Self::acquire_writer().await?.pool().acquire().await?;
+        Self::update_many_with(&mut conn, query, mutation).await
+    }
+
+    /// Updates many models selected by the query in the table,
+    /// using the specific database connection.
+    async fn update_many_with(
+        conn: &mut DatabaseConnection,
+        query: &Query,
+        mutation: &mut Mutation,
+    ) -> Result<QueryContext, Error> {
         Self::before_mutation(query, mutation).await?;
 
         let table_name = query.format_table_name::<Self>();
         let filters = query.format_filters::<Self>();
-        let updates = mutation.format_updates::<Self>();
+        let updates = mutation.format_updates::<Self>()?;
         let sql = format!("UPDATE {table_name} SET {updates} {filters};");
 
         let mut ctx = Self::before_scan(&sql).await?;
-        let query_result = sqlx::query(&sql).execute(pool).await?;
+        super::scope::record_write(Self::WRITER_NAME);
+        let query_result = sqlx::query(&sql)
+            .execute(&mut *conn)
+            .await
+            .map_err(constraint::map_database_error::<Self>)
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "update_many", err))?;
         let rows_affected = query_result.rows_affected();
+        cache::invalidate::<Self>();
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "update_many").await;
         Self::after_mutation(&ctx).await?;
         Ok(ctx)
     }
 
     /// Updates or inserts the model into the table.
-    async fn upsert(mut self) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+    async fn upsert(self) -> Result<QueryContext, Error> {
+        let mut conn = Self::acquire_writer().await?.pool().acquire().await?;
+        self.upsert_with(&mut conn).await
+    }
+
+    /// Updates or inserts the model into the table,
+    /// using the specific database connection.
+    async fn upsert_with(mut self, conn: &mut DatabaseConnection) -> Result<QueryContext, Error> {
         let model_data = self.before_upsert().await?;
 
-        let map = self.into_map();
+        let mut map = self.into_map();
+        encryption::encrypt_model::<Self>(&mut map)?;
         let table_name = Self::table_name();
         let fields = Self::fields();
         let num_fields = fields.len();
@@ -649,8 +836,14 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         };
 
         let mut ctx = Self::before_scan(&sql).await?;
-        let query_result = sqlx::query(&sql).execute(pool).await?;
+        super::scope::record_write(Self::WRITER_NAME);
+        let query_result = sqlx::query(&sql)
+            .execute(&mut *conn)
+            .await
+            .map_err(constraint::map_database_error::<Self>)
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "upsert", err))?;
         let (last_insert_id, rows_affected) = Query::parse_query_result(query_result);
+        cache::invalidate::<Self>();
         let success = rows_affected == 1;
         if let Some(last_insert_id) = last_insert_id {
             ctx.set_last_insert_id(last_insert_id);
@@ -658,6 +851,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "upsert").await;
         Self::after_upsert(&ctx, model_data).await?;
         if success {
             Ok(ctx)
@@ -669,34 +863,130 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         }
     }
 
+    /// Updates or inserts many models into the table.
+    ///
+    /// The conflict target is the primary key if `conflict_target` is `None`.
+    /// For MySQL, it is ignored since `ON DUPLICATE KEY UPDATE` checks all the unique indexes.
+    /// The primary key is never updated, so an existing row keeps its primary key
+    /// when it conflicts on the other unique columns.
+    ///
+    /// The models are split into chunks to limit the size of a statement,
+    /// and the statements are executed in a transaction. The `after_upsert` hook
+    /// is called for each model after all of them have been upserted.
+    async fn upsert_many(
+        models: Vec<Self>,
+        conflict_target: Option<&[&str]>,
+    ) -> Result<QueryContext, Error> {
+        let columns = Self::columns();
+        let mut values = Vec::with_capacity(models.len());
+        let mut models_data = Vec::with_capacity(models.len());
+        for mut model in models.into_iter() {
+            models_data.push(model.before_upsert().await?);
+
+            let mut map = model.into_map();
+            encryption::encrypt_model::<Self>(&mut map)?;
+            let entries = columns
+                .iter()
+                .map(|col| col.encode_value(map.get(col.name())))
+                .collect::<Vec<_>>();
+            values.push(format!("({})", entries.join(", ")));
+        }
+
+        let is_mysql = cfg!(any(
+            feature = "orm-mariadb",
+            feature = "orm-mysql",
+            feature = "orm-tidb"
+        ));
+        let table_name = Self::table_name();
+        let primary_key_name = Self::PRIMARY_KEY_NAME;
+        let fields = Self::fields();
+        let read_only_fields = Self::read_only_fields();
+        let mutations = fields
+            .iter()
+            .filter(|&&field| field != primary_key_name && !read_only_fields.contains(&field))
+            .map(|field| {
+                let field = Query::format_field(field);
+                if is_mysql {
+                    format!("{field} = VALUES({field})")
+                } else {
+                    format!("{field} = excluded.{field}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let conflict_clause = if is_mysql {
+            format!("ON DUPLICATE KEY UPDATE {mutations}")
+        } else {
+            let conflict_target = conflict_target
+                .map(|fields| fields.join(", "))
+                .unwrap_or_else(|| primary_key_name.to_owned());
+
+            // Both PostgreQL and SQLite (3.24+) support this syntax.
+            format!("ON CONFLICT ({conflict_target}) DO UPDATE SET {mutations}")
+        };
+
+        let fields = fields.join(", ");
+        let statements = format_insert_statements(table_name, &fields, values, &conflict_clause);
+        let ctx = execute_statements::<Self>(statements).await?;
+        for model_data in models_data {
+            Self::after_upsert(&ctx, model_data).await?;
+        }
+        Ok(ctx)
+    }
+
     /// Deletes the model in the table.
-    async fn delete(mut self) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+    async fn delete(self) -> Result<QueryContext, Error> {
+        let mut conn = Self::acquire_writer().await?.pool().acquire().await?;
+        self.delete_with(&mut conn).await
+    }
+
+    /// Deletes the model in the table,
+    /// using the specific database connection.
+    ///
+    /// The row is also matched by the tenant scope of the model if there is one.
+    async fn delete_with(mut self, conn: &mut DatabaseConnection) -> Result<QueryContext, Error> {
         let model_data = self.before_delete().await?;
 
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         let table_name = Self::table_name();
         let primary_key = self.primary_key();
         let placeholder = Query::placeholder(1);
+        let tenant_condition = if Self::TENANT_SCOPE.is_some() {
+            let map = serde_json::to_value(&self)?
+                .into_map_opt()
+                .unwrap_or_default();
+            format_tenant_condition::<Self>(&map)
+        } else {
+            String::new()
+        };
         let sql = if cfg!(feature = "orm-postgres") {
             let type_annotation = Self::primary_key_column().type_annotation();
             format!(
                 "DELETE FROM {table_name} \
-                    WHERE {primary_key_name} = ({placeholder}){type_annotation};"
+                    WHERE {primary_key_name} = ({placeholder}){type_annotation}{tenant_condition};"
             )
         } else {
-            format!("DELETE FROM {table_name} WHERE {primary_key_name} = {placeholder};")
+            format!(
+                "DELETE FROM {table_name} \
+                    WHERE {primary_key_name} = {placeholder}{tenant_condition};"
+            )
         };
 
         let mut ctx = Self::before_scan(&sql).await?;
+        super::scope::record_write(Self::WRITER_NAME);
         let query = sqlx::query(&sql).bind(primary_key.to_string());
-        let query_result = query.execute(pool).await?;
+        let query_result = query
+            .execute(&mut *conn)
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "delete", err))?;
         let rows_affected = query_result.rows_affected();
+        cache::invalidate::<Self>();
         let success = rows_affected == 1;
         ctx.set_query(sql);
         ctx.add_argument(primary_key);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "delete").await;
         self.after_delete(&ctx, model_data).await?;
         if success {
             Ok(ctx)
@@ -710,25 +1000,40 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
     /// Deletes at most one model selected by the query in the table.
     async fn delete_one(query: &Query) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+        let mut conn = Self::acquire_writer().await?.pool().acquire().await?;
+        Self::delete_one_with(&mut conn, query).await
+    }
+
+    /// Deletes at most one model selected by the query in the table,
+    /// using the specific database connection.
+    async fn delete_one_with(
+        conn: &mut DatabaseConnection,
+        query: &Query,
+    ) -> Result<QueryContext, Error> {
         Self::before_query(query).await?;
 
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         let table_name = query.format_table_name::<Self>();
         let filters = query.format_filters::<Self>();
-        let sort = query.format_sort();
+        let sort = query.format_sort::<Self>();
         let sql = format!(
             "DELETE FROM {table_name} WHERE {primary_key_name} IN \
                 (SELECT {primary_key_name} FROM {table_name} {filters} {sort} LIMIT 1);"
         );
 
         let mut ctx = Self::before_scan(&sql).await?;
-        let query_result = sqlx::query(&sql).execute(pool).await?;
+        super::scope::record_write(Self::WRITER_NAME);
+        let query_result = sqlx::query(&sql)
+            .execute(&mut *conn)
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "delete_one", err))?;
         let rows_affected = query_result.rows_affected();
+        cache::invalidate::<Self>();
         let success = rows_affected <= 1;
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "delete_one").await;
         Self::after_query(&ctx).await?;
         if success {
             Ok(ctx)
@@ -742,7 +1047,16 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
     /// Deletes many models selected by the query in the table.
     async fn delete_many(query: &Query) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+        let mut conn = Self::acquire_writer().await?.pool().acquire().await?;
+        Self::delete_many_with(&mut conn, query).await
+    }
+
+    /// Deletes many models selected by the query in the table,
+    /// using the specific database connection.
+    async fn delete_many_with(
+        conn: &mut DatabaseConnection,
+        query: &Query,
+    ) -> Result<QueryContext, Error> {
         Self::before_query(query).await?;
 
         let table_name = query.format_table_name::<Self>();
@@ -750,11 +1064,17 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let sql = format!("DELETE FROM {table_name} {filters};");
 
         let mut ctx = Self::before_scan(&sql).await?;
-        let query_result = sqlx::query(&sql).execute(pool).await?;
+        super::scope::record_write(Self::WRITER_NAME);
+        let query_result = sqlx::query(&sql)
+            .execute(&mut *conn)
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "delete_many", err))?;
         let rows_affected = query_result.rows_affected();
+        cache::invalidate::<Self>();
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "delete_many").await;
         Self::after_query(&ctx).await?;
         Ok(ctx)
     }
@@ -764,61 +1084,224 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
     async fn find<T: DecodeRow<DatabaseRow, Error = Error>>(
         query: &Query,
     ) -> Result<Vec<T>, Error> {
-        let pool = Self::acquire_reader().await?.pool();
+        let mut conn = Self::acquire_reader().await?.pool().acquire().await?;
+        Self::find_with(&mut conn, query).await
+    }
+
+    /// Finds a list of models selected by the query in the table,
+    /// and decodes it as `Vec<T>`,
+    /// using the specific database connection.
+    async fn find_with<T: DecodeRow<DatabaseRow, Error = Error>>(
+        conn: &mut DatabaseConnection,
+        query: &Query,
+    ) -> Result<Vec<T>, Error> {
         Self::before_query(query).await?;
 
         let table_name = query.format_table_name::<Self>();
         let projection = query.format_table_fields::<Self>();
         let filters = query.format_filters::<Self>();
-        let sort = query.format_sort();
+        let sort = query.format_sort::<Self>();
         let pagination = query.format_pagination();
         let sql = format!("SELECT {projection} FROM {table_name} {filters} {sort} {pagination};");
 
         let mut ctx = Self::before_scan(&sql).await?;
-        let mut rows = sqlx::query(&sql).fetch(pool);
+        let mut rows = sqlx::query(&sql).fetch(&mut *conn);
         let mut data = Vec::new();
         let mut max_rows = super::MAX_ROWS.load(Relaxed);
-        while let Some(row) = rows.try_next().await?
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "find", err))?
             && max_rows > 0
         {
             data.push(T::decode_row(&row)?);
             max_rows -= 1;
         }
+        if query.cursor().is_some_and(|(_, backward)| backward) {
+            // The rows are selected in the reversed order for a backward cursor.
+            data.reverse();
+        }
         ctx.set_query(&sql);
         ctx.set_query_result(Some(u64::try_from(data.len())?), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "find").await;
         Self::after_query(&ctx).await?;
         Ok(data)
     }
 
     /// Finds a list of models selected by the query in the table,
     /// and parses it as `Vec<T>`.
+    ///
+    /// If the query cache is enabled by `#[schema(cache)]`, the decoded rows are cached
+    /// until they expire or the table is modified by the model writer.
     async fn find_as<T: DeserializeOwned>(query: &Query) -> Result<Vec<T>, Error> {
-        let mut data = Self::find::<Map>(query).await?;
+        let cache_key = cache::format_query_key::<Self>(query, &query.format_pagination());
+        let mut data = if let Some(ref key) = cache_key
+            && let Some(rows) = cache::get_rows::<Self>(key)
+        {
+            cache::run_query_hooks::<Self>(query, key, rows.len()).await?;
+            rows
+        } else {
+            let generation = cache::generation::<Self>();
+            let data = Self::find::<Map>(query).await?;
+            if let Some(key) = cache_key {
+                cache::put_rows::<Self>(key, data.clone(), generation);
+            }
+            data
+        };
         let translate_enabled = query.translate_enabled();
         for model in data.iter_mut() {
+            Self::decrypt_model(model)?;
             Self::after_decode(model).await?;
             translate_enabled.then(|| Self::translate_model(model));
         }
         serde_json::from_value(data.into()).map_err(Error::from)
     }
 
+    /// Searches the models selected by the query for the text in the columns
+    /// declared with `index_type = "text"`. Each model is returned with a relevance `_score`
+    /// and the `_highlights` of the matched columns, and the models are sorted by the score
+    /// before the sort order of the query.
+    ///
+    /// The language of PostgreSQL defaults to the one declared by `index_type = "text:{language}"`.
+    /// For SQLite, the search is performed on the FTS5 virtual table `{table_name}_fts`
+    /// which is maintained by [`create_indexes`](Self::create_indexes).
+    async fn search(
+        query: &Query,
+        search: &str,
+        language: Option<&str>,
+    ) -> Result<Vec<Map>, Error> {
+        let terms = search::parse_search_terms(search);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let pool = Self::acquire_reader().await?.pool();
+        Self::before_query(query).await?;
+
+        let Some(sql) = search::format_search_sql::<Self>(query, search, language) else {
+            bail!(
+                "404 Not Found: there are no text search columns for the model `{}`",
+                Self::model_name()
+            );
+        };
+        let mut ctx = Self::before_scan(&sql).await?;
+        let mut rows = sqlx::query(&sql).fetch(pool);
+        let mut data = Vec::new();
+        let mut max_rows = super::MAX_ROWS.load(Relaxed);
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "search", err))?
+            && max_rows > 0
+        {
+            data.push(Map::decode_row(&row)?);
+            max_rows -= 1;
+        }
+        ctx.set_query(&sql);
+        ctx.set_query_result(Some(u64::try_from(data.len())?), true);
+        Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "search").await;
+        Self::after_query(&ctx).await?;
+
+        let translate_enabled = query.translate_enabled();
+        for model in data.iter_mut() {
+            search::collect_highlights::<Self>(model, &terms);
+            Self::decrypt_model(model)?;
+            Self::after_decode(model).await?;
+            translate_enabled.then(|| Self::translate_model(model));
+        }
+        Ok(data)
+    }
+
+    /// Finds the models selected by the query in the table,
+    /// and parses them as a stream of `T` which yields the rows lazily.
+    ///
+    /// Unlike [`find_as`](Self::find_as), the rows are not limited by the `max-rows` setting,
+    /// and the hooks `after_decode` and `translate_model` are applied to each row
+    /// when it is yielded. The stream should be pinned before polling.
+    /// For a backward cursor, the rows are yielded in the selection order without reversing.
+    fn find_stream<'a, T: DeserializeOwned + 'a>(
+        query: &'a Query,
+    ) -> impl Stream<Item = Result<T, Error>> + 'a {
+        let (mut tx, rx) = mpsc::channel(STREAM_BUFFER_SIZE);
+        let producer = async move {
+            let result = async {
+                let pool = Self::acquire_reader().await?.pool();
+                Self::before_query(query).await?;
+
+                let table_name = query.format_table_name::<Self>();
+                let projection = query.format_table_fields::<Self>();
+                let filters = query.format_filters::<Self>();
+                let sort = query.format_sort::<Self>();
+                let pagination = query.format_pagination();
+                let sql =
+                    format!("SELECT {projection} FROM {table_name} {filters} {sort} {pagination};");
+
+                let mut ctx = Self::before_scan(&sql).await?;
+                let mut rows = sqlx::query(&sql).fetch(pool);
+                let mut num_rows = 0;
+                let translate_enabled = query.translate_enabled();
+                while let Some(row) = rows
+                    .try_next()
+                    .await
+                    .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "find", err))?
+                {
+                    let mut model = Map::decode_row(&row)?;
+                    Self::decrypt_model(&mut model)?;
+                    Self::after_decode(&mut model).await?;
+                    translate_enabled.then(|| Self::translate_model(&mut model));
+                    let data = serde_json::from_value(model.into())?;
+                    if tx.send(Ok(data)).await.is_err() {
+                        break;
+                    }
+                    num_rows += 1;
+                }
+                ctx.set_query(&sql);
+                ctx.set_query_result(Some(num_rows), true);
+                Self::after_scan(&ctx).await?;
+                profile::profile_query::<Self>(&ctx, "find").await;
+                Self::after_query(&ctx).await?;
+                Ok::<_, Error>(())
+            }
+            .await;
+            if let Err(err) = result {
+                tx.send(Err(err)).await.ok();
+            }
+        };
+        merge_producer(rx, producer)
+    }
+
     /// Finds one model selected by the query in the table,
     /// and decodes it as an instance of type `T`.
     async fn find_one<T: DecodeRow<DatabaseRow, Error = Error>>(
         query: &Query,
     ) -> Result<Option<T>, Error> {
-        let pool = Self::acquire_reader().await?.pool();
+        let mut conn = Self::acquire_reader().await?.pool().acquire().await?;
+        Self::find_one_with(&mut conn, query).await
+    }
+
+    /// Finds one model selected by the query in the table,
+    /// and decodes it as an instance of type `T`,
+    /// using the specific database connection.
+    async fn find_one_with<T: DecodeRow<DatabaseRow, Error = Error>>(
+        conn: &mut DatabaseConnection,
+        query: &Query,
+    ) -> Result<Option<T>, Error> {
         Self::before_query(query).await?;
 
         let table_name = query.format_table_name::<Self>();
         let projection = query.format_table_fields::<Self>();
         let filters = query.format_filters::<Self>();
-        let sort = query.format_sort();
+        let sort = query.format_sort::<Self>();
         let sql = format!("SELECT {projection} FROM {table_name} {filters} {sort} LIMIT 1;");
 
         let mut ctx = Self::before_scan(&sql).await?;
-        let (num_rows, data) = if let Some(row) = sqlx::query(&sql).fetch_optional(pool).await? {
+        let (num_rows, data) = if let Some(row) = sqlx::query(&sql)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "find_one", err))?
+        {
             (1, Some(T::decode_row(&row)?))
         } else {
             (0, None)
@@ -826,15 +1309,32 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query(sql);
         ctx.set_query_result(Some(num_rows), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "find_one").await;
         Self::after_query(&ctx).await?;
         Ok(data)
     }
 
     /// Finds one model selected by the query in the table,
     /// and parses it as an instance of type `T`.
+    /// The decoded row is cached in the same way as [`find_as`](Self::find_as).
     async fn find_one_as<T: DeserializeOwned>(query: &Query) -> Result<Option<T>, Error> {
-        match Self::find_one::<Map>(query).await? {
+        let cache_key = cache::format_query_key::<Self>(query, "LIMIT 1");
+        let data = if let Some(ref key) = cache_key
+            && let Some(mut rows) = cache::get_rows::<Self>(key)
+        {
+            cache::run_query_hooks::<Self>(query, key, rows.len()).await?;
+            rows.pop()
+        } else {
+            let generation = cache::generation::<Self>();
+            let data = Self::find_one::<Map>(query).await?;
+            if let Some(key) = cache_key {
+                cache::put_rows::<Self>(key, data.iter().cloned().collect(), generation);
+            }
+            data
+        };
+        match data {
             Some(mut data) => {
+                Self::decrypt_model(&mut data)?;
                 Self::after_decode(&mut data).await?;
                 query
                     .translate_enabled()
@@ -854,17 +1354,21 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let pool = Self::acquire_reader().await?.pool();
         Self::before_query(query).await?;
 
-        let table_name = Self::table_name();
+        let table_name = query.format_table_name::<Self>();
         let projection = query.format_projection();
         let filters = query.format_filters::<Self>();
-        let sort = query.format_sort();
+        let sort = query.format_sort::<Self>();
         let sql = format!("SELECT {projection} FROM {table_name} {filters} {sort} LIMIT 1;");
 
         let mut ctx = Self::before_scan(&sql).await?;
-        let scalar = sqlx::query_scalar(&sql).fetch_one(pool).await?;
+        let scalar = sqlx::query_scalar(&sql)
+            .fetch_one(pool)
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "find_scalar", err))?;
         ctx.set_query(sql);
         ctx.set_query_result(Some(1), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "find_scalar").await;
         Self::after_query(&ctx).await?;
         Ok(scalar)
     }
@@ -881,7 +1385,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let table_name = query.format_table_name::<Self>();
         let projection = query.format_table_fields::<Self>();
         let filters = query.format_filters::<Self>();
-        let sort = query.format_sort();
+        let sort = query.format_sort::<Self>();
         let pagination = query.format_pagination();
         let sql = format!("SELECT {projection} FROM {table_name} {filters} {sort} {pagination};");
 
@@ -889,7 +1393,10 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let mut rows = sqlx::query(&sql).fetch(pool);
         let mut data = Vec::new();
         let mut max_rows = super::MAX_ROWS.load(Relaxed);
-        while let Some(row) = rows.try_next().await?
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "find_scalars", err))?
             && max_rows > 0
         {
             data.push(row.try_get_unchecked(0)?);
@@ -898,6 +1405,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query(&sql);
         ctx.set_query_result(Some(u64::try_from(data.len())?), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "find_scalars").await;
         Self::after_query(&ctx).await?;
         Ok(data)
     }
@@ -943,9 +1451,14 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let mut rows = sqlx::query(&sql).fetch(pool);
         let mut associations = Vec::with_capacity(num_values);
         let translate_enabled = query.translate_enabled();
-        while let Some(row) = rows.try_next().await? {
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "populate", err))?
+        {
             let mut map = Map::decode_row(&row)?;
             let primary_key = map.get(primary_key_name).cloned();
+            Self::decrypt_model(&mut map)?;
             Self::after_decode(&mut map).await?;
             translate_enabled.then(|| Self::translate_model(&mut map));
             if let Some(key) = primary_key {
@@ -957,6 +1470,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query(&sql);
         ctx.set_query_result(Some(associations_len), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "populate").await;
         Self::after_query(&ctx).await?;
 
         for row in data {
@@ -1023,7 +1537,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
             return Ok(());
         }
 
-        let table_name = Self::table_name();
+        let table_name = query.format_table_name::<Self>();
         let projection = query.format_projection();
         let filters = query.format_filters::<Self>();
         let sql = format!("SELECT {projection} FROM {table_name} {filters};");
@@ -1032,9 +1546,14 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let mut rows = sqlx::query(&sql).fetch(pool);
         let mut associations = Vec::with_capacity(num_values);
         let translate_enabled = query.translate_enabled();
-        while let Some(row) = rows.try_next().await? {
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "populate_one", err))?
+        {
             let mut map = Map::decode_row(&row)?;
             let primary_key = map.get(primary_key_name).cloned();
+            Self::decrypt_model(&mut map)?;
             Self::after_decode(&mut map).await?;
             translate_enabled.then(|| Self::translate_model(&mut map));
             if let Some(key) = primary_key {
@@ -1044,6 +1563,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query(&sql);
         ctx.set_query_result(Some(u64::try_from(associations.len())?), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "populate_one").await;
         Self::after_query(&ctx).await?;
 
         for col in columns {
@@ -1078,6 +1598,78 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         Ok(())
     }
 
+    /// Populates the models which refer to the data by the `column` as arrays
+    /// in the `populated_field` for `Vec<Map>`, using a merged select on the column.
+    async fn populate_reverse(
+        query: &mut Query,
+        data: &mut Vec<Map>,
+        column: &str,
+        populated_field: &str,
+    ) -> Result<u64, Error> {
+        let pool = Self::acquire_reader().await?.pool();
+        Self::before_query(query).await?;
+
+        let Some(reference) = Self::get_column(column).and_then(|col| col.reference()) else {
+            bail!("the column `{}` should have a model reference", column);
+        };
+        let referenced_key = reference.column_name();
+        let values = data
+            .iter()
+            .filter_map(|row| row.get(referenced_key).cloned())
+            .collect::<Vec<_>>();
+        let num_values = values.len();
+        if num_values > 0 {
+            query.add_field(column);
+            query.add_filter(column, Map::from_entry("$in", values));
+        } else {
+            return Ok(0);
+        }
+
+        let table_name = query.format_table_name::<Self>();
+        let projection = query.format_table_fields::<Self>();
+        let filters = query.format_filters::<Self>();
+        let sort = query.format_sort::<Self>();
+        let sql = format!("SELECT {projection} FROM {table_name} {filters} {sort};");
+
+        let mut ctx = Self::before_scan(&sql).await?;
+        let mut rows = sqlx::query(&sql).fetch(pool);
+        let mut associations = Vec::new();
+        let translate_enabled = query.translate_enabled();
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "populate_reverse", err))?
+        {
+            let mut map = Map::decode_row(&row)?;
+            let key = map.get(column).cloned();
+            Self::decrypt_model(&mut map)?;
+            Self::after_decode(&mut map).await?;
+            translate_enabled.then(|| Self::translate_model(&mut map));
+            if let Some(key) = key {
+                associations.push((key, map));
+            }
+        }
+
+        let associations_len = u64::try_from(associations.len())?;
+        ctx.set_query(&sql);
+        ctx.set_query_result(Some(associations_len), true);
+        Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "populate_reverse").await;
+        Self::after_query(&ctx).await?;
+
+        for row in data.iter_mut() {
+            if let Some(key) = row.get(referenced_key) {
+                let populated_values = associations
+                    .iter()
+                    .filter(|(k, _)| k == key)
+                    .map(|(_, v)| JsonValue::from(v.clone()))
+                    .collect::<Vec<_>>();
+                row.upsert(populated_field, populated_values);
+            }
+        }
+        Ok(associations_len)
+    }
+
     /// Performs a left outer join to another table to filter rows in the joined table,
     /// and decodes it as `Vec<T>`.
     async fn lookup<M: Schema, T: DecodeRow<DatabaseRow, Error = Error>>(
@@ -1094,7 +1686,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let other_table_name = query.format_table_name::<M>();
         let projection = query.format_table_fields::<Self>();
         let filters = query.format_filters::<Self>();
-        let sort = query.format_sort();
+        let sort = query.format_sort::<Self>();
         let pagination = query.format_pagination();
         let on_expressions = left_columns
             .iter()
@@ -1118,7 +1710,10 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let mut rows = sqlx::query(&sql).fetch(pool);
         let mut data = Vec::new();
         let mut max_rows = super::MAX_ROWS.load(Relaxed);
-        while let Some(row) = rows.try_next().await?
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "lookup", err))?
             && max_rows > 0
         {
             data.push(T::decode_row(&row)?);
@@ -1127,6 +1722,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query(&sql);
         ctx.set_query_result(Some(u64::try_from(data.len())?), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "lookup").await;
         Self::after_query(&ctx).await?;
         Ok(data)
     }
@@ -1141,6 +1737,68 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let mut data = Self::lookup::<M, Map>(query, left_columns, right_columns).await?;
         let translate_enabled = query.translate_enabled();
         for model in data.iter_mut() {
+            Self::decrypt_model(model)?;
+            Self::after_decode(model).await?;
+            translate_enabled.then(|| Self::translate_model(model));
+        }
+        serde_json::from_value(data.into()).map_err(Error::from)
+    }
+
+    /// Performs the joins to other tables and decodes it as `Vec<T>`.
+    /// The filters, projection fields and sort order can refer to the columns
+    /// of the joined tables with the table aliases as prefixes.
+    async fn join<T: DecodeRow<DatabaseRow, Error = Error>>(
+        query: &Query,
+        joins: &[JoinOn],
+    ) -> Result<Vec<T>, Error> {
+        let pool = Self::acquire_reader().await?.pool();
+        Self::before_query(query).await?;
+
+        let table_name = query.format_table_name::<Self>();
+        let projection = query.format_joined_fields::<Self>();
+        let filters = query.format_joined_filters::<Self>(joins);
+        let sort = query.format_sort::<Self>();
+        let pagination = query.format_pagination();
+        let join_expressions = joins
+            .iter()
+            .map(|join| join.format_join())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let sql = format!(
+            "SELECT {projection} FROM {table_name} {join_expressions} \
+                {filters} {sort} {pagination};"
+        );
+
+        let mut ctx = Self::before_scan(&sql).await?;
+        let mut rows = sqlx::query(&sql).fetch(pool);
+        let mut data = Vec::new();
+        let mut max_rows = super::MAX_ROWS.load(Relaxed);
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "join", err))?
+            && max_rows > 0
+        {
+            data.push(T::decode_row(&row)?);
+            max_rows -= 1;
+        }
+        ctx.set_query(&sql);
+        ctx.set_query_result(Some(u64::try_from(data.len())?), true);
+        Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "join").await;
+        Self::after_query(&ctx).await?;
+        Ok(data)
+    }
+
+    /// Performs the joins to other tables and parses it as `Vec<T>`.
+    async fn join_as<T: DeserializeOwned>(
+        query: &Query,
+        joins: &[JoinOn],
+    ) -> Result<Vec<T>, Error> {
+        let mut data = Self::join::<Map>(query, joins).await?;
+        let translate_enabled = query.translate_enabled();
+        for model in data.iter_mut() {
+            Self::decrypt_model(model)?;
             Self::after_decode(model).await?;
             translate_enabled.then(|| Self::translate_model(model));
         }
@@ -1152,15 +1810,17 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let pool = Self::acquire_writer().await?.pool();
         Self::before_count(query).await?;
 
-        let table_name = Self::table_name();
-        let filters = query.format_filters::<Self>();
-        let sql = format!("SELECT count(*) FROM {table_name} {filters};");
+        let sql = format_count_statement::<Self>(query);
 
         let mut ctx = Self::before_scan(&sql).await?;
-        let count: i64 = sqlx::query_scalar(&sql).fetch_one(pool).await?;
+        let count: i64 = sqlx::query_scalar(&sql)
+            .fetch_one(pool)
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "count", err))?;
         ctx.set_query(sql);
         ctx.set_query_result(Some(1), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "count").await;
         Self::after_count(&ctx).await?;
         u64::try_from(count).map_err(Error::from)
     }
@@ -1195,10 +1855,14 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let sql = format!("SELECT {projection} FROM {table_name} {filters};");
 
         let mut ctx = Self::before_scan(&sql).await?;
-        let row = sqlx::query(&sql).fetch_one(pool).await?;
+        let row = sqlx::query(&sql)
+            .fetch_one(pool)
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "count_many", err))?;
         ctx.set_query(sql);
         ctx.set_query_result(Some(1), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "count_many").await;
         Self::after_count(&ctx).await?;
         T::decode_row(&row).map_err(Error::from)
     }
@@ -1213,9 +1877,60 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         serde_json::from_value(map.into()).map_err(Error::from)
     }
 
+    /// Aggregates the rows selected by the query in the table,
+    /// and decodes it as `Vec<T>`.
+    async fn aggregate<T: DecodeRow<DatabaseRow, Error = Error>>(
+        query: &Query,
+        aggregation: &Aggregation,
+    ) -> Result<Vec<T>, Error> {
+        let pool = Self::acquire_reader().await?.pool();
+        Self::before_query(query).await?;
+
+        let table_name = query.format_table_name::<Self>();
+        let projection = aggregation.format_projection::<Self>();
+        if projection.is_empty() {
+            bail!("there are no valid groups or accumulators in the aggregation");
+        }
+
+        let filters = query.format_filters::<Self>();
+        let groups = aggregation.format_groups::<Self>();
+        let sort = aggregation.format_sort::<Self>();
+        let sql = format!("SELECT {projection} FROM {table_name} {filters} {groups} {sort};");
+
+        let mut ctx = Self::before_scan(&sql).await?;
+        let mut rows = sqlx::query(&sql).fetch(pool);
+        let mut data = Vec::new();
+        let mut max_rows = super::MAX_ROWS.load(Relaxed);
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "aggregate", err))?
+            && max_rows > 0
+        {
+            data.push(T::decode_row(&row)?);
+            max_rows -= 1;
+        }
+        ctx.set_query(&sql);
+        ctx.set_query_result(Some(u64::try_from(data.len())?), true);
+        Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "aggregate").await;
+        Self::after_query(&ctx).await?;
+        Ok(data)
+    }
+
+    /// Aggregates the rows selected by the query in the table,
+    /// and parses it as `Vec<T>`.
+    async fn aggregate_as<T: DeserializeOwned>(
+        query: &Query,
+        aggregation: &Aggregation,
+    ) -> Result<Vec<T>, Error> {
+        let data = Self::aggregate::<Map>(query, aggregation).await?;
+        serde_json::from_value(data.into()).map_err(Error::from)
+    }
+
     /// Executes the query in the table, and returns the total number of rows affected.
     async fn execute(query: &str, params: Option<&Map>) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_reader().await?.pool();
+        let pool = Self::acquire_writer().await?.pool();
         let (sql, values) = Query::prepare_query(query, params);
         let mut query = sqlx::query(&sql);
         let mut arguments = Vec::with_capacity(values.len());
@@ -1225,12 +1940,18 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         }
 
         let mut ctx = Self::before_scan(&sql).await?;
-        let query_result = query.execute(pool).await?;
+        super::scope::record_write(Self::WRITER_NAME);
+        let query_result = query
+            .execute(pool)
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "execute", err))?;
         let rows_affected = query_result.rows_affected();
+        cache::invalidate::<Self>();
         ctx.set_query(sql);
         ctx.append_arguments(&mut arguments);
         ctx.set_query_result(Some(rows_affected), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "execute").await;
         Ok(ctx)
     }
 
@@ -1252,7 +1973,10 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let mut rows = query.fetch(pool);
         let mut data = Vec::new();
         let mut max_rows = super::MAX_ROWS.load(Relaxed);
-        while let Some(row) = rows.try_next().await?
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "query", err))?
             && max_rows > 0
         {
             data.push(T::decode_row(&row)?);
@@ -1262,6 +1986,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.append_arguments(&mut arguments);
         ctx.set_query_result(Some(u64::try_from(data.len())?), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "query").await;
         Ok(data)
     }
 
@@ -1272,11 +1997,63 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
     ) -> Result<Vec<T>, Error> {
         let mut data = Self::query::<Map>(query, params).await?;
         for model in data.iter_mut() {
+            Self::decrypt_model(model)?;
             Self::after_decode(model).await?;
         }
         serde_json::from_value(data.into()).map_err(Error::from)
     }
 
+    /// Executes the query in the table, and parses the rows as a stream of `T`
+    /// which yields them lazily. The rows are not limited by the `max-rows` setting,
+    /// and the hook `after_decode` is applied to each row when it is yielded.
+    fn query_stream<'a, T: DeserializeOwned + 'a>(
+        query: &'a str,
+        params: Option<&'a Map>,
+    ) -> impl Stream<Item = Result<T, Error>> + 'a {
+        let (mut tx, rx) = mpsc::channel(STREAM_BUFFER_SIZE);
+        let producer = async move {
+            let result = async {
+                let pool = Self::acquire_reader().await?.pool();
+                let (sql, values) = Query::prepare_query(query, params);
+                let mut query = sqlx::query(&sql);
+                let mut arguments = Vec::with_capacity(values.len());
+                for value in values {
+                    query = query.bind(value.to_string_unquoted());
+                    arguments.push(value.to_string_unquoted());
+                }
+
+                let mut ctx = Self::before_scan(&sql).await?;
+                let mut rows = query.fetch(pool);
+                let mut num_rows = 0;
+                while let Some(row) = rows
+                    .try_next()
+                    .await
+                    .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "query", err))?
+                {
+                    let mut model = Map::decode_row(&row)?;
+                    Self::decrypt_model(&mut model)?;
+                    Self::after_decode(&mut model).await?;
+                    let data = serde_json::from_value(model.into())?;
+                    if tx.send(Ok(data)).await.is_err() {
+                        break;
+                    }
+                    num_rows += 1;
+                }
+                ctx.set_query(sql.as_ref());
+                ctx.append_arguments(&mut arguments);
+                ctx.set_query_result(Some(num_rows), true);
+                Self::after_scan(&ctx).await?;
+                profile::profile_query::<Self>(&ctx, "query").await;
+                Ok::<_, Error>(())
+            }
+            .await;
+            if let Err(err) = result {
+                tx.send(Err(err)).await.ok();
+            }
+        };
+        merge_producer(rx, producer)
+    }
+
     /// Executes the query in the table, and decodes it as an instance of type `T`.
     async fn query_one<T: DecodeRow<DatabaseRow, Error = Error>>(
         query: &str,
@@ -1292,7 +2069,11 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         }
 
         let mut ctx = Self::before_scan(&sql).await?;
-        let (num_rows, data) = if let Some(row) = query.fetch_optional(pool).await? {
+        let (num_rows, data) = if let Some(row) = query
+            .fetch_optional(pool)
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "query_one", err))?
+        {
             (1, Some(T::decode_row(&row)?))
         } else {
             (0, None)
@@ -1301,6 +2082,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.append_arguments(&mut arguments);
         ctx.set_query_result(Some(num_rows), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "query_one").await;
         Ok(data)
     }
 
@@ -1311,6 +2093,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
     ) -> Result<Option<T>, Error> {
         match Self::query_one::<Map>(query, params).await? {
             Some(mut data) => {
+                Self::decrypt_model(&mut data)?;
                 Self::after_decode(&mut data).await?;
                 serde_json::from_value(data.into()).map_err(Error::from)
             }
@@ -1333,11 +2116,15 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         }
 
         let mut ctx = Self::before_scan(&sql).await?;
-        let scalar = query.fetch_one(pool).await?;
+        let scalar = query
+            .fetch_one(pool)
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "query_scalar", err))?;
         ctx.set_query(sql);
         ctx.append_arguments(&mut arguments);
         ctx.set_query_result(Some(1), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "query_scalar").await;
         Ok(scalar)
     }
 
@@ -1359,7 +2146,10 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let mut rows = query.fetch(pool);
         let mut data = Vec::new();
         let mut max_rows = super::MAX_ROWS.load(Relaxed);
-        while let Some(row) = rows.try_next().await?
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "query_scalars", err))?
             && max_rows > 0
         {
             data.push(row.try_get_unchecked(0)?);
@@ -1369,27 +2159,73 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.append_arguments(&mut arguments);
         ctx.set_query_result(Some(u64::try_from(data.len())?), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "query_scalars").await;
         Ok(data)
     }
 
     /// Executes the specific operations inside a transaction.
     /// If the operations return an error, the transaction will be rolled back;
     /// if not, the transaction will be committed.
+    ///
+    /// The query cache of the model is invalidated after the commit. For the other models
+    /// modified in the transaction, [`invalidate_cache`](Self::invalidate_cache)
+    /// should be called after the commit.
     async fn transaction<F, T>(tx: F) -> Result<T, Error>
     where
         F: for<'a> FnOnce(&'a Transaction<DatabaseDriver>) -> BoxFuture<'a, Result<T, Error>>,
     {
         let pool = Self::acquire_writer().await?.pool();
         let transaction = pool.begin().await?;
+        super::scope::record_write(Self::WRITER_NAME);
         let data = tx(&transaction).await?;
         transaction.commit().await?;
+        cache::invalidate::<Self>();
+        Ok(data)
+    }
+
+    /// Executes the specific operations with a database connection inside a transaction,
+    /// which can be used to run the `_with` variants of the model operations.
+    /// If the operations return an error, the transaction will be rolled back;
+    /// if not, the transaction will be committed.
+    ///
+    /// The query cache of the model is invalidated after the commit as well.
+    async fn transaction_with<F, T>(tx: F) -> Result<T, Error>
+    where
+        F: for<'a> FnOnce(&'a mut DatabaseConnection) -> BoxFuture<'a, Result<T, Error>>,
+    {
+        let pool = Self::acquire_writer().await?.pool();
+        let mut transaction = pool.begin().await?;
+        super::scope::record_write(Self::WRITER_NAME);
+        let data = tx(&mut transaction).await?;
+        transaction.commit().await?;
+        cache::invalidate::<Self>();
         Ok(data)
     }
 
+    /// Invalidates the query cache of the model.
+    ///
+    /// The cache is also invalidated by the writes with a specific database connection,
+    /// but it can be refilled with the stale rows before the transaction is committed.
+    /// So this method should be called after committing a transaction in which
+    /// the model has been modified.
+    #[inline]
+    fn invalidate_cache() {
+        cache::invalidate::<Self>();
+    }
+
     /// Deletes a model selected by the primary key in the table.
+    /// The default scopes are not applied, so the caller should check the tenant.
     async fn delete_by_id(primary_key: &Self::PrimaryKey) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+        let mut conn = Self::acquire_writer().await?.pool().acquire().await?;
+        Self::delete_by_id_with(&mut conn, primary_key).await
+    }
 
+    /// Deletes a model selected by the primary key in the table,
+    /// using the specific database connection.
+    async fn delete_by_id_with(
+        conn: &mut DatabaseConnection,
+        primary_key: &Self::PrimaryKey,
+    ) -> Result<QueryContext, Error> {
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         let table_name = Self::table_name();
         let placeholder = Query::placeholder(1);
@@ -1404,14 +2240,20 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         };
 
         let mut ctx = Self::before_scan(&sql).await?;
+        super::scope::record_write(Self::WRITER_NAME);
         let query = sqlx::query(&sql).bind(primary_key.to_string());
-        let query_result = query.execute(pool).await?;
+        let query_result = query
+            .execute(&mut *conn)
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "delete_by_id", err))?;
         let rows_affected = query_result.rows_affected();
+        cache::invalidate::<Self>();
         let success = rows_affected == 1;
         ctx.set_query(sql);
         ctx.add_argument(primary_key);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "delete_by_id").await;
         if success {
             Ok(ctx)
         } else {
@@ -1424,6 +2266,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
     /// Finds a model selected by the primary key in the table,
     /// and decodes it as an instance of type `T`.
+    /// The default scopes are not applied, so the caller should check the tenant.
     async fn find_by_id<T: DecodeRow<DatabaseRow, Error = Error>>(
         primary_key: &Self::PrimaryKey,
     ) -> Result<Option<T>, Error> {
@@ -1448,7 +2291,11 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
         let mut ctx = Self::before_scan(&sql).await?;
         let query = sqlx::query(&sql).bind(primary_key.to_string());
-        let (num_rows, data) = if let Some(row) = query.fetch_optional(pool).await? {
+        let (num_rows, data) = if let Some(row) = query
+            .fetch_optional(pool)
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "find_by_id", err))?
+        {
             (1, Some(T::decode_row(&row)?))
         } else {
             (0, None)
@@ -1457,14 +2304,23 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.add_argument(primary_key);
         ctx.set_query_result(Some(num_rows), true);
         Self::after_scan(&ctx).await?;
+        profile::profile_query::<Self>(&ctx, "find_by_id").await;
         Self::after_query(&ctx).await?;
         Ok(data)
     }
 
     /// Finds a model selected by the primary key in the table, and parses it as `Self`.
     async fn try_get_model(primary_key: &Self::PrimaryKey) -> Result<Self, Error> {
-        let pool = Self::acquire_reader().await?.pool();
+        let mut conn = Self::acquire_reader().await?.pool().acquire().await?;
+        Self::try_get_model_with(&mut conn, primary_key).await
+    }
 
+    /// Finds a model selected by the primary key in the table, and parses it as `Self`,
+    /// using the specific database connection.
+    async fn try_get_model_with(
+        conn: &mut DatabaseConnection,
+        primary_key: &Self::PrimaryKey,
+    ) -> Result<Self, Error> {
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         let table_name = Self::table_name();
         let query = Self::default_query();
@@ -1485,19 +2341,26 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let mut ctx = Self::before_scan(&sql).await?;
         let query = sqlx::query(&sql).bind(primary_key.to_string());
         ctx.add_argument(primary_key);
-        if let Some(row) = query.fetch_optional(pool).await? {
+        if let Some(row) = query
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|err| profile::profile_error::<Self>(&ctx, &sql, "try_get_model", err))?
+        {
             ctx.set_query(sql);
             ctx.set_query_result(Some(1), true);
             Self::after_scan(&ctx).await?;
+            profile::profile_query::<Self>(&ctx, "try_get_model").await;
             Self::after_query(&ctx).await?;
 
             let mut map = Map::decode_row(&row)?;
+            Self::decrypt_model(&mut map)?;
             Self::after_decode(&mut map).await?;
             Self::try_from_map(map).map_err(Error::from)
         } else {
             ctx.set_query(sql);
             ctx.set_query_result(Some(0), true);
             Self::after_scan(&ctx).await?;
+            profile::profile_query::<Self>(&ctx, "try_get_model").await;
             Self::after_query(&ctx).await?;
             bail!(
                 "404 Not Found: no rows for the model `{}` with the key `{}`",
@@ -1557,3 +2420,135 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         }
     }
 }
+
+/// Formats the tenant scope of the model data as an SQL condition prefixed with ` AND `,
+/// so that a model can not be updated or deleted across the tenants by the primary key.
+fn format_tenant_condition<M: Schema>(model: &Map) -> String {
+    if let Some(tenant_scope) = M::TENANT_SCOPE
+        && let Some(col) = M::get_column(tenant_scope)
+    {
+        let value = model.get(tenant_scope).unwrap_or(&JsonValue::Null);
+        format!(" AND {}", col.format_filter(tenant_scope, value))
+    } else {
+        String::new()
+    }
+}
+
+/// Formats the `SELECT count(*)` statement for the rows selected by the query.
+/// The table is aliased by the model name, which qualifies the default scopes.
+pub(super) fn format_count_statement<M: Schema>(query: &Query) -> String {
+    let table_name = query.format_table_name::<M>();
+    let filters = query.format_filters::<M>();
+    format!("SELECT count(*) FROM {table_name} {filters};")
+}
+
+/// Buffer size of the channel used by the row streams.
+const STREAM_BUFFER_SIZE: usize = 64;
+
+/// Merges the producer which sends the decoded rows with the receiver of the channel,
+/// so that the producer is driven by polling the returned stream.
+fn merge_producer<'a, T: 'a>(
+    rx: mpsc::Receiver<Result<T, Error>>,
+    producer: impl Future<Output = ()> + 'a,
+) -> impl Stream<Item = Result<T, Error>> + 'a {
+    let producer = producer.into_stream().filter_map(|_| future::ready(None));
+    stream::select(rx, producer)
+}
+
+/// Max size in bytes of a multi-row statement. Since the values are inlined,
+/// it is the statement size that matters, which is far below the default limits
+/// such as the `max_allowed_packet` of MySQL and the `SQLITE_MAX_SQL_LENGTH` of SQLite.
+const MAX_STATEMENT_SIZE: usize = 1 << 20;
+
+/// Formats the multi-row `INSERT` statements with an optional conflict clause.
+/// The rows are packed into a statement until it exceeds the max statement size.
+fn format_insert_statements(
+    table_name: &str,
+    fields: &str,
+    values: Vec<String>,
+    conflict_clause: &str,
+) -> Vec<String> {
+    let prefix = format!("INSERT INTO {table_name} ({fields}) VALUES ");
+    let suffix = if conflict_clause.is_empty() {
+        ";".to_owned()
+    } else {
+        format!(" {conflict_clause};")
+    };
+    let mut statements = Vec::new();
+    let mut statement = String::new();
+    for value in values {
+        if !statement.is_empty() && statement.len() + value.len() + 2 > MAX_STATEMENT_SIZE {
+            statement.push_str(&suffix);
+            statements.push(std::mem::take(&mut statement));
+        }
+        if statement.is_empty() {
+            statement.push_str(&prefix);
+        } else {
+            statement.push_str(", ");
+        }
+        statement.push_str(&value);
+    }
+    if !statement.is_empty() {
+        statement.push_str(&suffix);
+        statements.push(statement);
+    }
+    statements
+}
+
+/// Size of the data chunks sent by `COPY FROM STDIN`.
+#[cfg(all(
+    feature = "orm-postgres",
+    not(any(feature = "orm-mariadb", feature = "orm-mysql", feature = "orm-tidb"))
+))]
+const COPY_CHUNK_SIZE: usize = 1 << 20;
+
+/// Executes the statements for the model `M` in a transaction.
+/// The context of the last statement is returned with the total number of rows affected.
+async fn execute_statements<M: Schema>(statements: Vec<String>) -> Result<QueryContext, Error> {
+    let pool = M::acquire_writer().await?.pool();
+    let mut transaction = pool.begin().await?;
+    super::scope::record_write(M::WRITER_NAME);
+    let mut ctx = QueryContext::new();
+    let mut total_rows = 0;
+    for sql in statements {
+        ctx = M::before_scan(&sql).await?;
+        let query_result = sqlx::query(&sql)
+            .execute(&mut *transaction)
+            .await
+            .map_err(constraint::map_database_error::<M>)
+            .map_err(|err| profile::profile_error::<M>(&ctx, &sql, "execute", err))?;
+        let rows_affected = query_result.rows_affected();
+        ctx.set_query(sql);
+        ctx.set_query_result(Some(rows_affected), true);
+        M::after_scan(&ctx).await?;
+        profile::profile_query::<M>(&ctx, "execute").await;
+        total_rows += rows_affected;
+    }
+    transaction.commit().await?;
+    cache::invalidate::<M>();
+    ctx.set_query_result(Some(total_rows), true);
+    Ok(ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_insert_statements, MAX_STATEMENT_SIZE};
+
+    #[test]
+    fn it_formats_insert_statements() {
+        let values = vec!["(1, 'a')".to_owned(), "(2, 'b')".to_owned()];
+        let statements = format_insert_statements("tag", "id, name", values, "");
+        assert_eq!(
+            statements,
+            ["INSERT INTO tag (id, name) VALUES (1, 'a'), (2, 'b');"]
+        );
+
+        let value = format!("('{}')", "x".repeat(MAX_STATEMENT_SIZE / 2));
+        let values = vec![value.clone(), value.clone(), value];
+        let conflict_clause = "ON CONFLICT (name) DO NOTHING";
+        let statements = format_insert_statements("tag", "name", values, conflict_clause);
+        assert_eq!(statements.len(), 3);
+        assert!(statements[0].ends_with(" ON CONFLICT (name) DO NOTHING;"));
+        assert!(format_insert_statements("tag", "name", Vec::new(), "").is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+use crate::datetime::DateTime;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+thread_local! {
+    /// Write scope of the future being polled on the current thread.
+    static CURRENT_SCOPE: RefCell<Option<Arc<WriteScope>>> = const { RefCell::new(None) };
+}
+
+/// Timestamps in milliseconds of the last writes to the database services.
+#[derive(Debug, Default)]
+struct WriteScope(Mutex<HashMap<&'static str, i64>>);
+
+/// A future running within a write scope.
+struct WriteScoped<F> {
+    /// Inner future.
+    future: Pin<Box<F>>,
+    /// Write scope.
+    scope: Arc<WriteScope>,
+}
+
+impl<F: Future> Future for WriteScoped<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let _guard = ScopeGuard(CURRENT_SCOPE.replace(Some(this.scope.clone())));
+        this.future.as_mut().poll(cx)
+    }
+}
+
+/// A guard to restore the previous write scope, even if the future panics.
+struct ScopeGuard(Option<Arc<WriteScope>>);
+
+impl Drop for ScopeGuard {
+    #[inline]
+    fn drop(&mut self) {
+        CURRENT_SCOPE.set(self.0.take());
+    }
+}
+
+/// Runs the future within a new write scope such as a request context.
+///
+/// The writes to the database services are recorded in the scope, and the model readers
+/// stick to the primary within the `read-your-writes` window after a write in the same scope.
+/// Outside of any scope, the writes are not recorded and the readers are never sticky.
+pub fn scope_writes<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    WriteScoped {
+        future: Box::pin(future),
+        scope: Arc::default(),
+    }
+}
+
+/// Records a write to the database service in the current write scope.
+pub(super) fn record_write(name: &'static str) {
+    CURRENT_SCOPE.with_borrow(|scope| {
+        if let Some(scope) = scope
+            && let Ok(mut writes) = scope.0.lock()
+        {
+            writes.insert(name, DateTime::now().timestamp_millis());
+        }
+    });
+}
+
+/// Returns `true` if there is a write to the database service within the window
+/// in the current write scope.
+pub(super) fn has_recent_write(name: &str, window: Duration) -> bool {
+    CURRENT_SCOPE.with_borrow(|scope| {
+        scope
+            .as_ref()
+            .and_then(|scope| scope.0.lock().ok()?.get(name).copied())
+            .is_some_and(|last_write| {
+                let window = i64::try_from(window.as_millis()).unwrap_or(i64::MAX);
+                DateTime::now().timestamp_millis() - last_write < window
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{has_recent_write, record_write, scope_writes};
+    use std::time::Duration;
+
+    #[test]
+    fn it_scopes_writes() {
+        let window = Duration::from_secs(60);
+        record_write("main");
+        assert!(!has_recent_write("main", window));
+
+        futures::executor::block_on(scope_writes(async {
+            assert!(!has_recent_write("main", window));
+            record_write("main");
+            assert!(has_recent_write("main", window));
+            assert!(!has_recent_write("replica", window));
+
+            scope_writes(async {
+                assert!(!has_recent_write("main", window));
+            })
+            .await;
+            assert!(has_recent_write("main", window));
+        }));
+        assert!(!has_recent_write("main", window));
+    }
+}
@@ -0,0 +1,415 @@
+use super::{encryption, query::QueryExt, ConnectionPool, Schema};
+use crate::{
+    error::Error,
+    extension::JsonObjectExt,
+    model::{Column, Query},
+    JsonValue, Map,
+};
+
+/// Prefix of the fields which hold the highlighted snippets in the selected rows.
+const HIGHLIGHT_PREFIX: &str = "_highlight_";
+
+/// Start and stop selections of the highlighted terms.
+const HIGHLIGHT_TAGS: (&str, &str) = ("<mark>", "</mark>");
+
+/// Maximum number of bytes kept around the first match in a highlighted snippet.
+const SNIPPET_RADIUS: usize = 64;
+
+/// Returns the columns declared with `index_type = "text"` for the model `M`.
+/// The encrypted columns are excluded since their ciphertexts can not be searched.
+pub(super) fn text_search_columns<M: Schema>() -> Vec<&'static Column<'static>> {
+    M::columns()
+        .iter()
+        .filter(|col| {
+            col.index_type().is_some_and(|index_type| {
+                index_type.starts_with("text") || index_type == "fulltext"
+            }) && !encryption::is_encrypted(col)
+        })
+        .collect()
+}
+
+/// Formats the names of the text search columns for the model `M` as a list.
+/// In MySQL, it is shared by the `FULLTEXT` index and the `MATCH` expression,
+/// since the column list of the expression should be the same as the one of the index.
+pub(super) fn format_text_search_fields<M: Schema>() -> String {
+    text_search_columns::<M>()
+        .iter()
+        .map(|col| col.name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Parses the search text into the lowercase terms to be highlighted.
+/// The excluded terms such as `-word` and the operator `or` are skipped.
+pub(super) fn parse_search_terms(search: &str) -> Vec<String> {
+    search
+        .split_whitespace()
+        .filter(|s| !s.starts_with('-') && !s.eq_ignore_ascii_case("or"))
+        .map(|s| {
+            s.trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase()
+        })
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Formats the SQL statement to search the models with the relevance score and highlights.
+/// Returns `None` if the model `M` has no text search columns.
+pub(super) fn format_search_sql<M: Schema>(
+    query: &Query,
+    search: &str,
+    language: Option<&str>,
+) -> Option<String> {
+    let columns = text_search_columns::<M>();
+    if columns.is_empty() {
+        return None;
+    }
+
+    let mut table_name = query.format_table_name::<M>();
+    let projection = query.format_table_fields::<M>();
+    let mut projection = if projection == "*" {
+        format!("{}.*", Query::format_field(M::model_name()))
+    } else {
+        projection.into_owned()
+    };
+    let mut condition = None;
+    if cfg!(any(
+        feature = "orm-mariadb",
+        feature = "orm-mysql",
+        feature = "orm-tidb"
+    )) {
+        let fields = format_text_search_fields::<M>();
+        let search = Query::escape_string(search);
+        let rank = format!("match({fields}) against({search} in natural language mode)");
+        projection = format!("{projection}, {rank} AS _score");
+        condition = Some(rank);
+    } else if cfg!(feature = "orm-postgres") {
+        let language = language
+            .or_else(|| columns.iter().find_map(|col| column_language(col)))
+            .unwrap_or("english");
+        let document_columns = if columns
+            .iter()
+            .any(|col| column_language(col).unwrap_or("english") == language)
+        {
+            columns
+                .iter()
+                .filter(|col| column_language(col).unwrap_or("english") == language)
+                .copied()
+                .collect()
+        } else {
+            columns.clone()
+        };
+
+        // The document is the same as the expression of the `gin` index for the language.
+        let text = document_columns
+            .iter()
+            .map(|col| format!("coalesce({}, '')", col.name()))
+            .collect::<Vec<_>>()
+            .join(" || ' ' || ");
+        let language = Query::escape_string(language);
+        let search = Query::escape_string(search);
+        let document = format!("to_tsvector({language}, {text})");
+        let tsquery = format!("websearch_to_tsquery({language}, {search})");
+        let (start_sel, stop_sel) = HIGHLIGHT_TAGS;
+        let highlights = columns
+            .iter()
+            .map(|col| {
+                let col_name = col.name();
+                format!(
+                    "ts_headline({language}, coalesce({col_name}, ''), {tsquery}, \
+                        'StartSel={start_sel}, StopSel={stop_sel}, MaxFragments=2') \
+                        AS \"{HIGHLIGHT_PREFIX}{col_name}\""
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        projection =
+            format!("{projection}, ts_rank({document}, {tsquery})::float8 AS _score, {highlights}");
+        condition = Some(format!("{document} @@ {tsquery}"));
+    } else {
+        let model_name = M::model_name();
+        let fts_table_name = format!("{}_fts", M::table_name());
+        let search = Query::escape_string(format_fts_query(search));
+        let (start_sel, stop_sel) = HIGHLIGHT_TAGS;
+        let highlights = columns
+            .iter()
+            .enumerate()
+            .map(|(index, col)| {
+                format!(
+                    "snippet({fts_table_name}, {index}, '{start_sel}', '{stop_sel}', '…', 16) \
+                        AS `{HIGHLIGHT_PREFIX}{}`",
+                    col.name()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let highlight_fields = columns
+            .iter()
+            .map(|col| format!("_fts.`{HIGHLIGHT_PREFIX}{}`", col.name()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        projection = format!("{projection}, _fts._score, {highlight_fields}");
+        table_name = format!(
+            "{table_name} JOIN (\
+                SELECT rowid, -bm25({fts_table_name}) AS _score, {highlights} \
+                    FROM {fts_table_name} WHERE {fts_table_name} MATCH {search}\
+            ) _fts ON `{model_name}`.rowid = _fts.rowid"
+        );
+    }
+
+    let mut filters = query.format_filters::<M>();
+    if let Some(condition) = condition {
+        if filters.is_empty() {
+            filters = format!("WHERE {condition}");
+        } else {
+            filters = format!("{filters} AND {condition}");
+        }
+    }
+
+    // The rows are sorted by the score before the sort order of the query.
+    let sort = query.format_sort::<M>();
+    let sort = if let Some(sort_order) = sort.strip_prefix("ORDER BY ") {
+        format!("ORDER BY _score DESC, {sort_order}")
+    } else {
+        "ORDER BY _score DESC".to_owned()
+    };
+    let pagination = query.format_pagination();
+    let sql = format!("SELECT {projection} FROM {table_name} {filters} {sort} {pagination};");
+    Some(sql)
+}
+
+/// Moves the highlighted snippets of the text search columns into the `_highlights` field.
+/// If the database does not provide the snippets, they are generated from the column values.
+pub(super) fn collect_highlights<M: Schema>(model: &mut Map, terms: &[String]) {
+    let mut highlights = Map::new();
+    for col in text_search_columns::<M>() {
+        let col_name = col.name();
+        let snippet = match model.remove(&format!("{HIGHLIGHT_PREFIX}{col_name}")) {
+            Some(JsonValue::String(snippet)) => Some(snippet),
+            _ => model
+                .get_str(col_name)
+                .and_then(|text| highlight_text(text, terms)),
+        };
+        if let Some(snippet) = snippet
+            && snippet.contains(HIGHLIGHT_TAGS.0)
+        {
+            highlights.upsert(col_name, snippet);
+        }
+    }
+    model.upsert("_highlights", highlights);
+}
+
+/// Creates or rebuilds the SQLite FTS5 virtual table `{table_name}_fts` for the columns.
+/// The virtual table is kept in sync with the content table by triggers,
+/// and it is recreated if the text search columns have been changed.
+pub(super) async fn synchronize_fts_table(
+    connection_pool: &ConnectionPool,
+    table_name: &str,
+    columns: &[&str],
+) -> Result<u64, Error> {
+    let pool = connection_pool.pool();
+    let fts_table_name = format!("{table_name}_fts");
+    let fts_columns = columns.join(", ");
+    let fts_arguments = format!("{fts_columns}, content='{table_name}', content_rowid='rowid'");
+    let sql = format!(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = '{fts_table_name}';"
+    );
+    let definition = sqlx::query_scalar::<_, String>(&sql)
+        .fetch_optional(pool)
+        .await?;
+    if definition
+        .as_ref()
+        .is_some_and(|sql| sql.contains(&fts_arguments))
+    {
+        return Ok(0);
+    }
+
+    let mut statements = Vec::new();
+    if definition.is_some() {
+        for trigger in ["insert", "delete", "update"] {
+            statements.push(format!(
+                "DROP TRIGGER IF EXISTS {fts_table_name}_{trigger};"
+            ));
+        }
+        statements.push(format!("DROP TABLE {fts_table_name};"));
+    }
+
+    let new_values = columns
+        .iter()
+        .map(|col| format!("new.{col}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let old_values = columns
+        .iter()
+        .map(|col| format!("old.{col}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let insert_entry = format!(
+        "INSERT INTO {fts_table_name} (rowid, {fts_columns}) VALUES (new.rowid, {new_values});"
+    );
+    let delete_entry = format!(
+        "INSERT INTO {fts_table_name} ({fts_table_name}, rowid, {fts_columns}) \
+            VALUES ('delete', old.rowid, {old_values});"
+    );
+    statements.push(format!(
+        "CREATE VIRTUAL TABLE {fts_table_name} USING fts5({fts_arguments});"
+    ));
+    statements.push(format!(
+        "CREATE TRIGGER {fts_table_name}_insert AFTER INSERT ON {table_name} \
+            BEGIN {insert_entry} END;"
+    ));
+    statements.push(format!(
+        "CREATE TRIGGER {fts_table_name}_delete AFTER DELETE ON {table_name} \
+            BEGIN {delete_entry} END;"
+    ));
+    statements.push(format!(
+        "CREATE TRIGGER {fts_table_name}_update AFTER UPDATE ON {table_name} \
+            BEGIN {delete_entry} {insert_entry} END;"
+    ));
+    statements.push(format!(
+        "INSERT INTO {fts_table_name} ({fts_table_name}) VALUES ('rebuild');"
+    ));
+
+    let mut transaction = pool.begin().await?;
+    let mut rows = 0;
+    for sql in statements {
+        rows = sqlx::query(&sql)
+            .execute(&mut *transaction)
+            .await?
+            .rows_affected()
+            .max(rows);
+    }
+    transaction.commit().await?;
+    Ok(rows)
+}
+
+/// Returns the language declared by `index_type = "text:{language}"`.
+#[inline]
+fn column_language<'a>(col: &Column<'a>) -> Option<&'a str> {
+    col.index_type()?.strip_prefix("text:")
+}
+
+/// Formats the search text as an FTS5 query where each term is quoted as a string,
+/// so that the characters of the query syntax in the terms are matched literally.
+fn format_fts_query(search: &str) -> String {
+    search
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Highlights the terms in the text and crops it around the first match.
+/// Returns `None` if there are no matches.
+fn highlight_text(text: &str, terms: &[String]) -> Option<String> {
+    // The text is lowercased char by char, and the offsets of the chars are recorded
+    // as pairs of the lowercase offset and the original offset, since the lowercasing
+    // can change the byte lengths of the chars.
+    let mut haystack = String::with_capacity(text.len());
+    let mut char_offsets = Vec::with_capacity(text.len() + 1);
+    for (index, ch) in text.char_indices() {
+        char_offsets.push((haystack.len(), index));
+        haystack.extend(ch.to_lowercase());
+    }
+    char_offsets.push((haystack.len(), text.len()));
+
+    // Maps the offset in the lowercase text to the boundary of the char in the original text.
+    let original_offset = |offset: usize, round_up: bool| match char_offsets
+        .binary_search_by_key(&offset, |&(lowercase_offset, _)| lowercase_offset)
+    {
+        Ok(index) => char_offsets[index].1,
+        Err(index) if round_up => char_offsets[index].1,
+        Err(index) => char_offsets[index - 1].1,
+    };
+
+    let mut matches = Vec::new();
+    let mut offset = 0;
+    while offset < haystack.len() {
+        let next_match = terms
+            .iter()
+            .filter(|term| !term.is_empty())
+            .filter_map(|term| {
+                haystack[offset..]
+                    .find(term.as_str())
+                    .map(|index| (offset + index, offset + index + term.len()))
+            })
+            .min_by_key(|&(start, end)| (start, usize::MAX - end));
+        let Some((start, end)) = next_match else {
+            break;
+        };
+        let last_end = matches.last().map_or(0, |&(_, end)| end);
+        let match_start = original_offset(start, false).max(last_end);
+        let match_end = original_offset(end, true);
+        if match_start < match_end {
+            matches.push((match_start, match_end));
+        }
+        offset = end;
+    }
+
+    let &(first_start, first_end) = matches.first()?;
+    let mut snippet_start = first_start.saturating_sub(SNIPPET_RADIUS);
+    while !text.is_char_boundary(snippet_start) {
+        snippet_start -= 1;
+    }
+    let mut snippet_end = (first_end + SNIPPET_RADIUS).min(text.len());
+    while !text.is_char_boundary(snippet_end) {
+        snippet_end += 1;
+    }
+
+    let (start_sel, stop_sel) = HIGHLIGHT_TAGS;
+    let mut snippet = String::new();
+    if snippet_start > 0 {
+        snippet.push('…');
+    }
+    let mut cursor = snippet_start;
+    for (start, end) in matches {
+        if end > snippet_end {
+            break;
+        }
+        snippet.push_str(&text[cursor..start]);
+        snippet.push_str(start_sel);
+        snippet.push_str(&text[start..end]);
+        snippet.push_str(stop_sel);
+        cursor = end;
+    }
+    snippet.push_str(&text[cursor..snippet_end]);
+    if snippet_end < text.len() {
+        snippet.push('…');
+    }
+    Some(snippet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_fts_query, highlight_text, parse_search_terms};
+
+    #[test]
+    fn it_highlights_text() {
+        let terms = parse_search_terms(r#""Rust" -java web OR framework"#);
+        assert_eq!(terms, ["rust", "web", "framework"]);
+
+        let text = "Zino is a next-generation framework for composable applications in Rust.";
+        assert_eq!(
+            highlight_text(text, &terms).unwrap(),
+            "Zino is a next-generation <mark>framework</mark> for composable applications in <mark>Rust</mark>."
+        );
+        assert!(highlight_text(text, &["java".to_owned()]).is_none());
+
+        let text = format!("{} rust {}", "a".repeat(100), "b".repeat(100));
+        let snippet = highlight_text(&text, &terms).unwrap();
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains("<mark>rust</mark>"));
+
+        // The lowercasing changes the byte lengths of `ẞ` and `İ`.
+        let text = "ẞİ Rust ẞ";
+        assert_eq!(
+            highlight_text(text, &terms).unwrap(),
+            "ẞİ <mark>Rust</mark> ẞ"
+        );
+
+        assert_eq!(
+            format_fts_query(r#"rust "web OR"#),
+            r#""rust" """web" "OR""#
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use super::Schema;
+use crate::{
+    application::PROJECT_DIR,
+    bail,
+    error::Error,
+    extension::{JsonObjectExt, JsonValueExt, TomlTableExt, TomlValueExt},
+    model::Query,
+    state::State,
+    JsonValue, Map,
+};
+use convert_case::{Case, Casing};
+use futures::future::LocalBoxFuture;
+use std::{env, fs, path::Path, sync::OnceLock};
+use toml::value::Table;
+
+/// A loader of the seed data for a model.
+type SeedLoader = for<'a> fn(&'a Path) -> LocalBoxFuture<'a, Result<u64, Error>>;
+
+/// The seeder registered to run at boot.
+static SHARED_SEEDER: OnceLock<Seeder> = OnceLock::new();
+
+/// A seeder which loads the reference data from the files `{model_name}.toml`
+/// or `{model_name}.json` in the seeds directory.
+///
+/// The entries are validated by `Model::read_map` and upserted by the primary key.
+/// If an entry has no primary key, the existing row is matched by the unique fields,
+/// so that the same files can be seeded repeatedly. For a model without any unique fields,
+/// each entry should have the primary key. The models are seeded in the order
+/// of their references regardless of the order in which they are added.
+///
+/// ```toml
+/// # config/seeds/tag.toml
+/// [[data]]
+/// name = "rust"
+/// category = "language"
+/// ```
+///
+/// ```rust,ignore
+/// use zino_core::orm::Seeder;
+///
+/// let seeder = Seeder::new().add::<User>().add::<Tag>();
+/// let num_rows = seeder.run("./config/seeds").await?;
+/// ```
+#[derive(Default)]
+pub struct Seeder {
+    /// Model names, the referenced model names and the loaders.
+    models: Vec<(&'static str, Vec<String>, SeedLoader)>,
+}
+
+impl Seeder {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a model to be seeded.
+    pub fn add<M: Schema>(mut self) -> Self {
+        let model_name = M::model_name();
+        let references = M::columns()
+            .iter()
+            .filter_map(|col| col.reference())
+            .map(|reference| reference.name().to_case(Case::Snake))
+            .filter(|name| name != model_name)
+            .collect();
+        self.models
+            .push((model_name, references, load_seeds::<M> as SeedLoader));
+        self
+    }
+
+    /// Loads the seed data in the directory for the models,
+    /// and returns the total number of rows affected.
+    pub async fn run(&self, dir: impl AsRef<Path>) -> Result<u64, Error> {
+        let dir = dir.as_ref();
+        let dependencies = self
+            .models
+            .iter()
+            .map(|(model_name, references, _)| (*model_name, references.as_slice()))
+            .collect::<Vec<_>>();
+        let mut total_rows = 0;
+        for index in sort_dependencies(&dependencies)? {
+            let (model_name, _, load) = &self.models[index];
+            let num_rows = load(dir).await?;
+            tracing::info!(model_name, num_rows, "the seed data has been loaded");
+            total_rows += num_rows;
+        }
+        Ok(total_rows)
+    }
+
+    /// Registers the seeder to run at boot.
+    pub fn register(self) {
+        if SHARED_SEEDER.set(self).is_err() {
+            tracing::warn!("the seeder has already been registered");
+        }
+    }
+
+    /// Runs the registered seeder at boot if the `--seed` argument is provided
+    /// or `seed-on-boot` is enabled in the database configuration.
+    /// The seed data is loaded from the `seed-dir` (default: `config/seeds`)
+    /// in the project directory.
+    ///
+    /// Returns `true` if the application should exit after seeding,
+    /// which is the case for the `--seed` argument. An error is returned
+    /// only for the `--seed` argument, otherwise it is logged and the application continues.
+    pub async fn seed_on_boot() -> Result<bool, Error> {
+        let Some(seeder) = SHARED_SEEDER.get() else {
+            return Ok(false);
+        };
+
+        let config = State::shared().get_config("database");
+        let seed_only = env::args().any(|arg| arg == "--seed");
+        let seed_on_boot = config
+            .and_then(|config| config.get_bool("seed-on-boot"))
+            .unwrap_or_default();
+        if !seed_only && !seed_on_boot {
+            return Ok(false);
+        }
+
+        let seed_dir = config
+            .and_then(|config| config.get_str("seed-dir"))
+            .unwrap_or("config/seeds");
+        match seeder.run(PROJECT_DIR.join(seed_dir)).await {
+            Ok(num_rows) => tracing::warn!(num_rows, seed_dir, "the seeder has finished"),
+            Err(err) if seed_only => return Err(err),
+            Err(err) => tracing::error!(seed_dir, "fail to run the seeder: {err}"),
+        }
+        Ok(seed_only)
+    }
+}
+
+/// Loads the seed data for the model `M`.
+fn load_seeds<M: Schema>(dir: &Path) -> LocalBoxFuture<'_, Result<u64, Error>> {
+    Box::pin(seed_model::<M>(dir))
+}
+
+/// Reads the seed entries in the files, and upserts them into the table of the model `M`.
+async fn seed_model<M: Schema>(dir: &Path) -> Result<u64, Error> {
+    let model_name = M::model_name();
+    let mut entries = Vec::new();
+    let toml_file = dir.join(format!("{model_name}.toml"));
+    if toml_file.exists() {
+        let table = fs::read_to_string(toml_file)?.parse::<Table>()?;
+        if let Some(data) = table.get_array("data") {
+            entries.extend(data.iter().filter_map(|v| v.to_json_value().into_map_opt()));
+        }
+    }
+
+    let json_file = dir.join(format!("{model_name}.json"));
+    if json_file.exists() {
+        let data = match serde_json::from_str(&fs::read_to_string(json_file)?)? {
+            JsonValue::Object(mut map) => map.remove("data").unwrap_or_default(),
+            value => value,
+        };
+        if let JsonValue::Array(data) = data {
+            entries.extend(data.into_iter().filter_map(|v| v.into_map_opt()));
+        }
+    }
+    if entries.is_empty() {
+        return Ok(0);
+    }
+
+    let primary_key_name = M::PRIMARY_KEY_NAME;
+    let unique_keys = unique_keys::<M>();
+    let mut models = Vec::with_capacity(entries.len());
+    for (index, mut entry) in entries.into_iter().enumerate() {
+        if !entry.contains_key(primary_key_name) {
+            if unique_keys.is_empty() {
+                bail!(
+                    "the seed data for the model `{}` at index {} should have the primary key \
+                        since there are no unique fields",
+                    model_name,
+                    index
+                );
+            }
+            if let Some(primary_key) = find_primary_key::<M>(&entry, &unique_keys).await? {
+                entry.upsert(primary_key_name, primary_key);
+            }
+        }
+
+        let mut model = M::new();
+        let validation = model.read_map(&entry);
+        if !validation.is_success() {
+            let errors = JsonValue::from(validation.into_map());
+            bail!(
+                "invalid seed data for the model `{}` at index {}: {}",
+                model_name,
+                index,
+                errors
+            );
+        }
+        models.push(model);
+    }
+
+    let ctx = M::upsert_many(models, None).await?;
+    Ok(ctx.rows_affected().unwrap_or_default())
+}
+
+/// Returns the fields of the unique columns and the unique indexes for the model `M`.
+fn unique_keys<M: Schema>() -> Vec<Vec<&'static str>> {
+    let mut unique_keys = M::columns()
+        .iter()
+        .filter(|col| col.index_type() == Some("unique"))
+        .map(|col| vec![col.name()])
+        .collect::<Vec<_>>();
+    for index in M::indexes() {
+        if index.is_unique() && index.filter().is_none() {
+            unique_keys.push(index.fields().to_vec());
+        }
+    }
+    unique_keys
+}
+
+/// Finds the primary key of the existing row matched by the unique fields of the entry.
+async fn find_primary_key<M: Schema>(
+    entry: &Map,
+    unique_keys: &[Vec<&'static str>],
+) -> Result<Option<JsonValue>, Error> {
+    let primary_key_name = M::PRIMARY_KEY_NAME;
+    for fields in unique_keys {
+        let mut filters = Map::new();
+        for &field in fields {
+            if let Some(value) = entry.get(field).filter(|v| !v.is_null()) {
+                filters.upsert(field, value.clone());
+            }
+        }
+        if filters.len() == fields.len() {
+            let mut query = Query::new(filters);
+            query.allow_fields(&[primary_key_name]);
+            query.disable_scopes();
+            if let Some(mut model) = M::find_one::<Map>(&query).await? {
+                return Ok(model.remove(primary_key_name));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Sorts the models such that each model comes after the models it references.
+/// The references to the models which are not in the list are ignored.
+fn sort_dependencies(dependencies: &[(&str, &[String])]) -> Result<Vec<usize>, Error> {
+    let mut sorted = Vec::with_capacity(dependencies.len());
+    while sorted.len() < dependencies.len() {
+        let ready = (0..dependencies.len())
+            .filter(|index| !sorted.contains(index))
+            .filter(|&index| {
+                dependencies[index].1.iter().all(|reference| {
+                    dependencies
+                        .iter()
+                        .enumerate()
+                        .all(|(i, (name, _))| *name != reference || sorted.contains(&i))
+                })
+            })
+            .collect::<Vec<_>>();
+        if ready.is_empty() {
+            let model_names = (0..dependencies.len())
+                .filter(|index| !sorted.contains(index))
+                .map(|index| format!("`{}`", dependencies[index].0))
+                .collect::<Vec<_>>()
+                .join(", ");
+            bail!("circular references between the models {}", model_names);
+        }
+        sorted.extend(ready);
+    }
+    Ok(sorted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sort_dependencies;
+
+    #[test]
+    fn it_sorts_dependencies() {
+        let project_references = vec!["user".to_owned(), "tag".to_owned()];
+        let user_references = vec!["tag".to_owned()];
+        let mut tag_references = Vec::new();
+        let dependencies = [
+            ("project", project_references.as_slice()),
+            ("user", user_references.as_slice()),
+            ("tag", tag_references.as_slice()),
+        ];
+        assert_eq!(sort_dependencies(&dependencies).unwrap(), [2, 1, 0]);
+
+        tag_references.push("project".to_owned());
+        let dependencies = [
+            ("project", project_references.as_slice()),
+            ("user", user_references.as_slice()),
+            ("tag", tag_references.as_slice()),
+        ];
+        assert!(sort_dependencies(&dependencies).is_err());
+    }
+}
@@ -499,6 +499,26 @@ impl QueryExt<DatabaseDriver> for Query {
         self.limit()
     }
 
+    #[inline]
+    fn query_cursor(&self) -> Option<(&[JsonValue], bool)> {
+        self.cursor()
+    }
+
+    #[inline]
+    fn query_tenant_id(&self) -> Option<&JsonValue> {
+        self.tenant_id()
+    }
+
+    #[inline]
+    fn query_scopes_enabled(&self) -> bool {
+        self.scopes_enabled()
+    }
+
+    #[inline]
+    fn query_show_deleted(&self) -> bool {
+        self.show_deleted()
+    }
+
     #[inline]
     fn placeholder(_n: usize) -> SharedString {
         "?".into()
@@ -585,6 +605,17 @@ impl QueryExt<DatabaseDriver> for Query {
         }
     }
 
+    fn format_date_bucket(field: &str, unit: &str) -> String {
+        match unit {
+            "year" => format!("strftime('%Y-01-01', {field})"),
+            "month" => format!("strftime('%Y-%m-01', {field})"),
+            "week" => format!("date({field}, '-6 days', 'weekday 1')"),
+            "hour" => format!("strftime('%Y-%m-%d %H:00:00', {field})"),
+            "minute" => format!("strftime('%Y-%m-%d %H:%M:00', {field})"),
+            _ => format!("strftime('%Y-%m-%d', {field})"),
+        }
+    }
+
     fn parse_text_search(filter: &Map) -> Option<String> {
         let fields = filter.parse_str_array("$fields")?;
         filter.parse_string("$search").map(|search| {
@@ -22,7 +22,7 @@ use bytes::Bytes;
 use cookie::{Cookie, SameSite};
 use fluent::FluentArgs;
 use http::Uri;
-use jwt_simple::algorithms::MACLike;
+use jwt_simple::{algorithms::MACLike, common::VerificationOptions};
 use multer::Multipart;
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
@@ -173,7 +173,7 @@ pub trait RequestContext {
     /// Gets a cookie with the given name.
     fn get_cookie(&self, name: &str) -> Option<Cookie<'_>> {
         self.get_header("cookie")?.split(';').find_map(|cookie| {
-            if let Some((key, value)) = cookie.split_once('=')
+            if let Some((key, value)) = cookie.trim().split_once('=')
                 && key == name
             {
                 Some(Cookie::new(key, value))
@@ -548,33 +548,16 @@ pub trait RequestContext {
     /// Attempts to construct an instance of `JwtClaims` from an HTTP request.
     /// The value is extracted from the query parameter `access_token` or
     /// the `authorization` header.
+    ///
+    /// The token is only verified by the given HMAC key. Use
+    /// [`verify_jwt_claims()`](Self::verify_jwt_claims) for the tokens signed
+    /// by the asymmetric keys in the `[[jwt.keys]]` configuration.
     fn parse_jwt_claims<T, K>(&self, key: &K) -> Result<JwtClaims<T>, Rejection>
     where
         T: Default + Serialize + DeserializeOwned,
         K: MACLike,
     {
-        let (param, mut token) = match self.get_query("access_token") {
-            Some(access_token) => ("access_token", access_token),
-            None => ("authorization", ""),
-        };
-        if let Some(authorization) = self.get_header("authorization") {
-            token = authorization
-                .strip_prefix("Bearer ")
-                .unwrap_or(authorization);
-        }
-        if token.is_empty() {
-            let mut validation = Validation::new();
-            validation.record(param, "the JWT token is absent");
-            return Err(Rejection::bad_request(validation).context(self));
-        }
-
-        let mut options = auth::default_verification_options();
-        options.reject_before = self
-            .get_query("timestamp")
-            .and_then(|s| s.parse().ok())
-            .map(|i| Duration::from_secs(i).into());
-        options.required_nonce = self.get_query("nonce").map(|s| s.to_owned());
-
+        let (token, options) = extract_jwt_token(self)?;
         match key.verify_token(token, Some(options)) {
             Ok(claims) => Ok(JwtClaims(claims)),
             Err(err) => {
@@ -584,6 +567,20 @@ pub trait RequestContext {
         }
     }
 
+    /// Attempts to construct an instance of `JwtClaims` from an HTTP request,
+    /// where the token is verified by the shared key with the `kid` in the header
+    /// so that the keys can be rotated. See [`JwtClaims::verify`] for the details.
+    fn verify_jwt_claims<T>(&self) -> Result<JwtClaims<T>, Rejection>
+    where
+        T: Default + Serialize + DeserializeOwned,
+    {
+        let (token, options) = extract_jwt_token(self)?;
+        JwtClaims::verify_with_options(token, options).map_err(|err| {
+            let message = format!("401 Unauthorized: {}", err.message());
+            Rejection::with_message(message).context(self)
+        })
+    }
+
     /// Returns a `Response` or `Rejection` from a model query validation.
     /// The data is extracted from [`parse_query()`](RequestContext::parse_query).
     fn query_validation<S>(&self, query: &mut Query) -> Result<Response<S>, Rejection>
@@ -772,3 +769,33 @@ pub trait RequestContext {
         event
     }
 }
+
+/// Extracts the JWT token from the query parameter `access_token` or the `authorization` header,
+/// and the verification options from the query parameters `timestamp` and `nonce`.
+fn extract_jwt_token<T>(ctx: &T) -> Result<(&str, VerificationOptions), Rejection>
+where
+    T: RequestContext + ?Sized,
+{
+    let (param, mut token) = match ctx.get_query("access_token") {
+        Some(access_token) => ("access_token", access_token),
+        None => ("authorization", ""),
+    };
+    if let Some(authorization) = ctx.get_header("authorization") {
+        token = authorization
+            .strip_prefix("Bearer ")
+            .unwrap_or(authorization);
+    }
+    if token.is_empty() {
+        let mut validation = Validation::new();
+        validation.record(param, "the JWT token is absent");
+        return Err(Rejection::bad_request(validation).context(ctx));
+    }
+
+    let mut options = auth::default_verification_options();
+    options.reject_before = ctx
+        .get_query("timestamp")
+        .and_then(|s| s.parse().ok())
+        .map(|i| Duration::from_secs(i).into());
+    options.required_nonce = ctx.get_query("nonce").map(|s| s.to_owned());
+    Ok((token, options))
+}
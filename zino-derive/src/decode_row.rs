@@ -21,19 +21,33 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
             {
                 let name = ident.to_string();
                 let mut ignore = false;
+                let mut encrypted = false;
                 'inner: for attr in field.attrs.iter() {
                     let arguments = parser::parse_schema_attr(attr);
-                    for (key, _value) in arguments.iter() {
+                    for (key, value) in arguments.iter() {
                         if key == "ignore" || key == "write_only" {
                             ignore = true;
                             break 'inner;
+                        } else if key == "encrypted" {
+                            encrypted = !value.as_ref().is_some_and(|v| v == "false");
                         }
                     }
                 }
                 if ignore {
                     continue;
                 }
-                if type_name == "Map" {
+                if encrypted && type_name == "String" {
+                    decode_model_fields.push(quote! {
+                        let value = orm::decode::<String>(row, #name)?;
+                        model.#ident = orm::decrypt_value(&value)?;
+                    });
+                } else if encrypted && type_name == "Option<String>" {
+                    decode_model_fields.push(quote! {
+                        if let Some(value) = orm::decode::<Option<String>>(row, #name)? {
+                            model.#ident = Some(orm::decrypt_value(&value)?);
+                        }
+                    });
+                } else if type_name == "Map" {
                     decode_model_fields.push(quote! {
                         if let JsonValue::Object(map) = orm::decode(row, #name)? {
                             model.#ident = map;
@@ -7,9 +7,51 @@ use syn::{Data, DeriveInput, Fields};
 pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
     // Parsing struct attributes
     let mut composite_constraints = Vec::new();
+    let mut populated_path_queries = Vec::new();
     for attr in input.attrs.iter() {
         for (key, value) in parser::parse_schema_attr(attr).into_iter() {
             if let Some(value) = value
+                && key == "reverse_reference"
+                && let Some((name, reference)) = value.split_once(':')
+                && let Some((model, column)) = reference.split_once('.')
+            {
+                let name = name.trim();
+                let column = column.trim();
+                let model_ident = format_ident!("{}", model.trim());
+                populated_path_queries.push(quote! {
+                    if let Some((fields, nested_paths)) = Self::parse_populate_paths(paths, #name) {
+                        let mut query = if fields.is_empty() {
+                            <#model_ident>::default_snapshot_query()
+                        } else {
+                            let mut query = <#model_ident>::default_query();
+                            query.allow_fields(&fields);
+                            query
+                        };
+                        query.add_field(<#model_ident>::PRIMARY_KEY_NAME);
+                        for path in nested_paths.iter() {
+                            if let Some(field) = path.split(['.', '(']).next() {
+                                query.add_field(field.trim());
+                            }
+                        }
+                        query.add_filter("translate", translate_enabled);
+                        if let Some(tenant_id) = tenant_id {
+                            query.set_tenant_id(tenant_id.clone());
+                        }
+                        <#model_ident>::populate_reverse(&mut query, models, #column, #name).await?;
+                        if !nested_paths.is_empty() {
+                            let mut populated_models = Self::take_populated_models(models, #name);
+                            Box::pin(<#model_ident>::populate_paths(
+                                &mut populated_models,
+                                &nested_paths,
+                                translate_enabled,
+                                tenant_id,
+                            ))
+                            .await?;
+                            Self::restore_populated_models(models, #name, populated_models);
+                        }
+                    }
+                });
+            } else if let Some(value) = value
                 && key == "unique_on"
             {
                 let mut fields = Vec::new();
@@ -551,6 +593,7 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
             populated_queries.push(quote! {
                 let mut models = Self::find::<Map>(query).await?;
                 for model in models.iter_mut() {
+                    Self::decrypt_model(model)?;
                     Self::after_decode(model).await?;
                     translate_enabled.then(|| Self::translate_model(model));
                 }
@@ -559,6 +602,7 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
                 let mut model = Self::find_by_id::<Map>(id)
                     .await?
                     .ok_or_else(|| zino_core::warn!("404 Not Found: cannot find the model `{}`", id))?;
+                Self::decrypt_model(&mut model)?;
                 Self::after_decode(&mut model).await?;
                 Self::translate_model(&mut model);
             });
@@ -566,6 +610,7 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
             populated_queries.push(quote! {
                 let mut models = Self::find::<Map>(query).await?;
                 for model in models.iter_mut() {
+                    Self::decrypt_model(model)?;
                     Self::after_decode(model).await?;
                     translate_enabled.then(|| Self::translate_model(model));
                 }
@@ -574,19 +619,73 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
                 let mut model = Self::find_by_id::<Map>(id)
                     .await?
                     .ok_or_else(|| zino_core::warn!("404 Not Found: cannot find the model `{}`", id))?;
+                Self::decrypt_model(&mut model)?;
                 Self::after_decode(&mut model).await?;
                 Self::translate_model(&mut model);
+
+                // The references are populated in the tenant of the model.
+                let tenant_id = Self::TENANT_SCOPE
+                    .and_then(|tenant_scope| model.get(tenant_scope))
+                    .filter(|value| !value.is_null())
+                    .cloned();
             });
             for (model, ref_fields) in model_references.into_iter() {
                 let model_ident = format_ident!("{}", model);
+                for field in ref_fields.iter() {
+                    let populated_field = format!("{field}_populated");
+                    populated_path_queries.push(quote! {
+                        if let Some((fields, nested_paths)) = Self::parse_populate_paths(paths, #field) {
+                            let mut query = if fields.is_empty() {
+                                <#model_ident>::default_snapshot_query()
+                            } else {
+                                let mut query = <#model_ident>::default_query();
+                                query.allow_fields(&fields);
+                                query
+                            };
+                            query.add_field(<#model_ident>::PRIMARY_KEY_NAME);
+                            for path in nested_paths.iter() {
+                                if let Some(field) = path.split(['.', '(']).next() {
+                                    query.add_field(field.trim());
+                                }
+                            }
+                            query.add_filter("translate", translate_enabled);
+                            if let Some(tenant_id) = tenant_id {
+                                query.set_tenant_id(tenant_id.clone());
+                            }
+                            <#model_ident>::populate(&mut query, models, [#field]).await?;
+                            if !nested_paths.is_empty() {
+                                let mut populated_models =
+                                    Self::take_populated_models(models, #populated_field);
+                                Box::pin(<#model_ident>::populate_paths(
+                                    &mut populated_models,
+                                    &nested_paths,
+                                    translate_enabled,
+                                    tenant_id,
+                                ))
+                                .await?;
+                                Self::restore_populated_models(
+                                    models,
+                                    #populated_field,
+                                    populated_models,
+                                );
+                            }
+                        }
+                    });
+                }
                 let populated_query = quote! {
                     let mut query = #model_ident::default_snapshot_query();
                     query.add_filter("translate", translate_enabled);
+                    if let Some(tenant_id) = tenant_id {
+                        query.set_tenant_id(tenant_id.clone());
+                    }
                     #model_ident::populate(&mut query, &mut models, [#(#ref_fields),*]).await?;
                 };
                 let populated_one_query = quote! {
                     let mut query = #model_ident::default_query();
                     query.add_filter("translate", true);
+                    if let Some(tenant_id) = tenant_id.as_ref() {
+                        query.set_tenant_id(tenant_id.clone());
+                    }
                     #model_ident::populate_one(&mut query, &mut model, [#(#ref_fields),*]).await?;
                 };
                 populated_queries.push(populated_query);
@@ -604,6 +703,21 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
     let model_primary_key_type = format_ident!("{}", primary_key_type);
     let model_primary_key = format_ident!("{}", primary_key_name);
     let model_user_id_type = format_ident!("{}", user_id_type);
+    let populate_paths_method = if populated_path_queries.is_empty() {
+        None
+    } else {
+        Some(quote! {
+            async fn populate_paths(
+                models: &mut Vec<ZinoMap>,
+                paths: &[&str],
+                translate_enabled: bool,
+                tenant_id: Option<&zino_core::JsonValue>,
+            ) -> Result<(), ZinoError> {
+                #(#populated_path_queries)*
+                Ok(())
+            }
+        })
+    };
     quote! {
         use zino_core::{
             model::Query,
@@ -651,12 +765,27 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
 
             async fn fetch(query: &Query) -> Result<Vec<ZinoMap>, ZinoError> {
                 let translate_enabled = query.translate_enabled();
+                let tenant_id = query.tenant_id();
+                let populate_paths = query.populate_paths();
+                if !populate_paths.is_empty() {
+                    let mut models = Self::find::<Map>(query).await?;
+                    for model in models.iter_mut() {
+                        Self::decrypt_model(model)?;
+                        Self::after_decode(model).await?;
+                        translate_enabled.then(|| Self::translate_model(model));
+                    }
+                    Self::populate_paths(&mut models, &populate_paths, translate_enabled, tenant_id)
+                        .await?;
+                    return Ok(models);
+                }
                 #(#populated_queries)*
             }
 
             async fn fetch_by_id(id: &#model_primary_key_type) -> Result<ZinoMap, ZinoError> {
                 #(#populated_one_queries)*
             }
+
+            #populate_paths_method
         }
     }
 }
@@ -78,3 +78,98 @@ pub(super) fn parse_schema_attr(attr: &Attribute) -> Vec<(String, Option<String>
     }
     arguments
 }
+
+/// Parses the arguments of a nested declaration such as `cache(ttl = "60s")` in an attribute.
+/// Returns `None` if there is no such declaration.
+pub(super) fn parse_nested_attr(
+    attr: &Attribute,
+    name: &str,
+) -> Option<Vec<(String, Option<String>)>> {
+    if attr.path().is_ident("schema")
+        && let Ok(nested) = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+    {
+        for meta in nested {
+            if !meta.path().is_ident(name) {
+                continue;
+            }
+
+            let mut arguments = Vec::new();
+            if let Meta::List(list) = meta
+                && let Ok(nested) =
+                    list.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+            {
+                for meta in nested {
+                    if let Some(ident) = meta.path().get_ident() {
+                        let key = ident.to_string();
+                        let value = if let Meta::NameValue(name_value) = meta
+                            && let Expr::Lit(expr_lit) = name_value.value
+                            && let Lit::Str(ref lit_str) = expr_lit.lit
+                        {
+                            Some(lit_str.value())
+                        } else {
+                            None
+                        };
+                        arguments.push((key, value));
+                    }
+                }
+            }
+            return Some(arguments);
+        }
+    }
+    None
+}
+
+/// Parses the `index(...)` declarations in an attribute, and returns a list of
+/// the fields and the other arguments for each index.
+pub(super) fn parse_index_attr(
+    attr: &Attribute,
+) -> Vec<(Vec<String>, Vec<(String, Option<String>)>)> {
+    let mut indexes = Vec::new();
+    if attr.path().is_ident("schema") {
+        if let Ok(nested) = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated) {
+            for meta in nested {
+                if let Meta::List(list) = meta
+                    && list.path.is_ident("index")
+                    && let Ok(nested) =
+                        list.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+                {
+                    let mut fields = Vec::new();
+                    let mut arguments = Vec::new();
+                    for meta in nested {
+                        let Some(ident) = meta.path().get_ident() else {
+                            continue;
+                        };
+                        let key = ident.to_string();
+                        if let Meta::NameValue(name_value) = meta {
+                            match name_value.value {
+                                Expr::Array(expr_array) if key == "fields" => {
+                                    for expr in expr_array.elems {
+                                        if let Expr::Lit(expr_lit) = expr
+                                            && let Lit::Str(ref lit_str) = expr_lit.lit
+                                        {
+                                            fields.push(lit_str.value());
+                                        }
+                                    }
+                                }
+                                Expr::Lit(expr_lit) => {
+                                    if let Lit::Str(ref lit_str) = expr_lit.lit {
+                                        arguments.push((key, Some(lit_str.value())));
+                                    } else if let Lit::Bool(ref lit_bool) = expr_lit.lit {
+                                        arguments.push((key, Some(lit_bool.value.to_string())));
+                                    }
+                                }
+                                _ => (),
+                            }
+                        } else {
+                            arguments.push((key, None));
+                        }
+                    }
+                    if !fields.is_empty() {
+                        indexes.push((fields, arguments));
+                    }
+                }
+            }
+        }
+    }
+    indexes
+}
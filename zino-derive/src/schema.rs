@@ -2,7 +2,8 @@ use super::parser;
 use convert_case::{Case, Casing};
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
-use syn::{Data, DeriveInput, Fields};
+use syn::{Data, DeriveInput, Error, Fields};
+use zino_core::datetime;
 
 // Integer types
 const INTEGER_TYPES: [&str; 10] = [
@@ -36,9 +37,66 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
     let mut writer_name = String::from("main");
     let mut table_name = None;
     let mut model_comment = None;
+    let mut soft_delete = false;
+    let mut tenant_scope = None;
+    let mut audit = false;
+    let mut access_control = false;
+    let mut cache_ttl = None;
+    let mut indexes = Vec::new();
     for attr in input.attrs.iter() {
+        if let Some(arguments) = parser::parse_nested_attr(attr, "cache") {
+            let ttl = arguments
+                .into_iter()
+                .find_map(|(key, value)| (key == "ttl").then_some(value).flatten());
+            match datetime::parse_duration(ttl.as_deref().unwrap_or("60s")) {
+                Ok(ttl) if !ttl.is_zero() => {
+                    cache_ttl = Some(u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX));
+                }
+                Ok(_) => {
+                    let message = "the cache `ttl` should be positive";
+                    return Error::new_spanned(attr, message).to_compile_error();
+                }
+                Err(err) => {
+                    let message = format!("the cache `ttl` should be a valid duration: {err}");
+                    return Error::new_spanned(attr, message).to_compile_error();
+                }
+            }
+        }
+        for (fields, arguments) in parser::parse_index_attr(attr).into_iter() {
+            let mut index_setters = Vec::new();
+            for (key, value) in arguments.into_iter() {
+                match key.as_str() {
+                    "unique" => {
+                        let unique = !value.is_some_and(|v| v == "false");
+                        index_setters.push(quote! { index.set_unique(#unique); });
+                    }
+                    "name" => {
+                        if let Some(value) = value {
+                            index_setters.push(quote! { index.set_name(#value); });
+                        }
+                    }
+                    "where" => {
+                        if let Some(value) = value {
+                            index_setters.push(quote! { index.set_filter(#value); });
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            indexes.push(quote! {{
+                let mut index = zino_core::model::Index::new(vec![#(#fields),*]);
+                #(#index_setters)*
+                index
+            }});
+        }
         for (key, value) in parser::parse_schema_attr(attr).into_iter() {
-            if let Some(value) = value {
+            if key == "soft_delete" {
+                soft_delete = !value.is_some_and(|v| v == "false");
+            } else if key == "audit" {
+                audit = !value.is_some_and(|v| v == "false");
+            } else if key == "access_control" {
+                access_control = !value.is_some_and(|v| v == "false");
+            } else if let Some(value) = value {
                 match key.as_str() {
                     "model_name" => {
                         model_name = value;
@@ -55,6 +113,9 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
                     "comment" => {
                         model_comment = Some(value);
                     }
+                    "tenant_scope" => {
+                        tenant_scope = Some(value);
+                    }
                     _ => (),
                 }
             }
@@ -257,14 +318,27 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
     let schema_fields = format_ident!("{}_FIELDS", model_name_upper_snake);
     let schema_read_only_fields = format_ident!("{}_READ_ONLY_FIELDS", model_name_upper_snake);
     let schema_write_only_fields = format_ident!("{}_WRITE_ONLY_FIELDS", model_name_upper_snake);
+    let schema_indexes = format_ident!("{}_INDEXES", model_name_upper_snake);
     let schema_reader = format_ident!("{}_READER", model_name_upper_snake);
     let schema_writer = format_ident!("{}_WRITER", model_name_upper_snake);
     let avro_schema = format_ident!("{}_AVRO_SCHEMA", model_name_upper_snake);
     let num_columns = columns.len();
     let num_read_only_fields = read_only_fields.len();
     let num_write_only_fields = write_only_fields.len();
+    let num_indexes = indexes.len();
     let quote_table_name = parser::quote_option_string(table_name);
     let quote_model_comment = parser::quote_option_string(model_comment);
+    let quote_tenant_scope = parser::quote_option_string(tenant_scope);
+    let quote_cache_ttl = if let Some(ttl_millis) = cache_ttl {
+        quote! {
+            #[inline]
+            fn cache_ttl() -> Option<std::time::Duration> {
+                Some(std::time::Duration::from_millis(#ttl_millis))
+            }
+        }
+    } else {
+        quote! {}
+    };
     quote! {
         use zino_core::{
             error::Error as ZinoError,
@@ -303,6 +377,8 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
             std::sync::LazyLock::new(|| [#(#read_only_fields),*]);
         static #schema_write_only_fields: std::sync::LazyLock<[&str; #num_write_only_fields]> =
             std::sync::LazyLock::new(|| [#(#write_only_fields),*]);
+        static #schema_indexes: std::sync::LazyLock<[zino_core::model::Index; #num_indexes]> =
+            std::sync::LazyLock::new(|| [#(#indexes),*]);
         static #schema_reader: std::sync::OnceLock<&ConnectionPool> = std::sync::OnceLock::new();
         static #schema_writer: std::sync::OnceLock<&ConnectionPool> = std::sync::OnceLock::new();
 
@@ -314,6 +390,10 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
             const READER_NAME: &'static str = #reader_name;
             const WRITER_NAME: &'static str = #writer_name;
             const TABLE_NAME: Option<&'static str> = #quote_table_name;
+            const SOFT_DELETE: bool = #soft_delete;
+            const TENANT_SCOPE: Option<&'static str> = #quote_tenant_scope;
+            const AUDIT: bool = #audit;
+            const ACCESS_CONTROL: bool = #access_control;
 
             #[inline]
             fn primary_key(&self) -> &Self::PrimaryKey {
@@ -355,13 +435,20 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
                 #schema_write_only_fields.as_slice()
             }
 
+            #[inline]
+            fn indexes() -> &'static [zino_core::model::Index<'static>] {
+                #schema_indexes.as_slice()
+            }
+
+            #quote_cache_ttl
+
             async fn acquire_reader() -> Result<&'static ConnectionPool, ZinoError> {
                 use zino_core::{bail, warn};
-                if let Some(connection_pool) = #schema_reader.get() {
-                    Ok(*connection_pool)
+                if #schema_reader.get().is_some() {
+                    Self::init_reader().await
                 } else {
                     let model_name = Self::MODEL_NAME;
-                    let connection_pool = Self::init_reader()?;
+                    let connection_pool = Self::init_reader().await?;
                     if let Err(err) = Self::create_table().await {
                         connection_pool.store_availability(false);
                         bail!(
@@ -3,10 +3,11 @@
 use crate::group::Group;
 use serde::{Deserialize, Serialize};
 use zino_core::{
+    auth::PolicyEngine,
     datetime::DateTime,
     error::Error,
     extension::JsonObjectExt,
-    model::{Model, ModelHooks},
+    model::{Model, ModelHooks, QueryContext},
     validation::Validation,
     Map, Uuid,
 };
@@ -119,6 +120,28 @@ impl Model for Policy {
     }
 }
 
+impl Policy {
+    /// Fetches the data of the active policies.
+    pub async fn active_policies() -> Result<Vec<Map>, Error> {
+        let mut query = Self::default_query();
+        query.add_filter("status", "Active");
+        query.set_limit(0);
+        Self::find::<Map>(&query).await
+    }
+
+    /// Loads the active policies into the shared policy engine,
+    /// returning the number of policies. It also registers the policy loader,
+    /// so that the policies are reloaded periodically.
+    pub async fn sync_policies() -> Result<usize, Error> {
+        let engine = PolicyEngine::shared();
+        engine.set_loader(|| Box::pin(Self::active_policies()));
+
+        let policies = Self::active_policies().await?;
+        engine.load(&policies);
+        Ok(policies.len())
+    }
+}
+
 impl ModelHooks for Policy {
     #[cfg(feature = "maintainer-id")]
     type Extension = UserSession<Uuid, String>;
@@ -141,4 +164,21 @@ impl ModelHooks for Policy {
         }
         Ok(())
     }
+
+    async fn after_save(ctx: &QueryContext, _data: Self::Data) -> Result<(), Error> {
+        if ctx.is_success() {
+            Self::sync_policies().await?;
+        } else {
+            ctx.record_error("fail to save a model into the table");
+        }
+        Ok(())
+    }
+
+    async fn after_delete(self, ctx: &QueryContext, _data: Self::Data) -> Result<(), Error> {
+        if ctx.is_success() {
+            Self::sync_policies().await?;
+        }
+        ctx.emit_metrics("delete");
+        Ok(())
+    }
 }
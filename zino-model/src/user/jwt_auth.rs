@@ -1,12 +1,13 @@
+use super::LockoutPolicy;
 use std::{fmt::Display, str::FromStr};
 use zino_core::{
-    auth::JwtClaims,
+    auth::{JwtClaims, RevocationStore},
     bail,
     datetime::DateTime,
     error::Error,
     extension::JsonObjectExt,
-    model::Query,
-    orm::{ModelAccessor, ModelHelper},
+    model::{Mutation, Query},
+    orm::{AuditLog, ModelAccessor, ModelHelper},
     warn, Map, Uuid,
 };
 
@@ -29,6 +30,10 @@ where
     const LOGIN_AT_FIELD: Option<&'static str> = None;
     /// Login-IP field name.
     const LOGIN_IP_FIELD: Option<&'static str> = None;
+    /// Failed-login-count field name.
+    const FAILED_LOGIN_COUNT_FIELD: Option<&'static str> = None;
+    /// Locked-until field name.
+    const LOCKED_UNTIL_FIELD: Option<&'static str> = None;
 
     /// Returns the standard claims parsed from the `content` field.
     /// See [the spec](https://openid.net/specs/openid-connect-core-1_0.html#StandardClaims).
@@ -83,6 +88,7 @@ where
     }
 
     /// Generates the access token and refresh token.
+    /// The login attempts are throttled by the [`LockoutPolicy`].
     async fn generate_token(body: Map) -> Result<(K, Map), Error> {
         let account = body
             .get_str("account")
@@ -90,6 +96,14 @@ where
         let passowrd = body
             .get_str("password")
             .ok_or_else(|| warn!("401 Unauthorized: the user `password` should be specified"))?;
+        let lockout_policy = LockoutPolicy::shared();
+        if let Some(duration) = lockout_policy.retry_after(account) {
+            bail!(
+                "429 Too Many Requests: try to log in again after {} seconds",
+                duration.as_secs().max(1)
+            );
+        }
+
         let mut query = Query::default();
         let mut fields = vec![Self::PRIMARY_KEY_NAME, Self::PASSWORD_FIELD];
         if let Some(role_field) = Self::ROLE_FIELD {
@@ -104,22 +118,60 @@ where
         if let Some(login_ip_field) = Self::LOGIN_IP_FIELD {
             fields.push(login_ip_field);
         }
+        if let Some(failed_login_count_field) = Self::FAILED_LOGIN_COUNT_FIELD {
+            fields.push(failed_login_count_field);
+        }
+        if let Some(locked_until_field) = Self::LOCKED_UNTIL_FIELD {
+            fields.push(locked_until_field);
+        }
         query.allow_fields(&fields);
         query.add_filter("status", Map::from_entry("$nin", vec!["Locked", "Deleted"]));
         query.add_filter(Self::ACCOUNT_FIELD, account);
 
-        let mut user: Map = Self::find_one(&query)
-            .await?
-            .ok_or_else(|| warn!("404 Not Found: invalid user account or password"))?;
+        let Some(mut user) = Self::find_one::<Map>(&query).await? else {
+            let failed_attempts = lockout_policy.record_failure(account);
+            tracing::warn!(
+                account,
+                failed_attempts,
+                "fail to log in with an invalid account"
+            );
+            bail!("404 Not Found: invalid user account or password");
+        };
+        if let Some(locked_until_field) = Self::LOCKED_UNTIL_FIELD
+            && let Some(Ok(locked_until)) = user.parse_datetime(locked_until_field)
+            && let Some(duration) = locked_until.span_after_now()
+        {
+            bail!(
+                "429 Too Many Requests: the user is locked, try to log in again after {} seconds",
+                duration.as_secs().max(1)
+            );
+        }
+
         let encrypted_password = user
             .get_str(Self::PASSWORD_FIELD)
             .ok_or_else(|| warn!("404 Not Found: the user password is absent"))?;
+
+        // Cann't use `get_str` because the primary key may be an integer
+        let user_id = user
+            .parse_string(Self::PRIMARY_KEY_NAME)
+            .ok_or_else(|| warn!("404 Not Found: the user id is absent"))?
+            .into_owned();
+        let failed_login_count = Self::FAILED_LOGIN_COUNT_FIELD
+            .and_then(|field| user.get_u8(field))
+            .unwrap_or_default();
         if Self::verify_password(passowrd, encrypted_password)? {
-            // Cann't use `get_str` because the primary key may be an integer
-            let user_id = user
-                .parse_string(Self::PRIMARY_KEY_NAME)
-                .ok_or_else(|| warn!("404 Not Found: the user id is absent"))?;
-            let mut claims = JwtClaims::new(user_id.as_ref());
+            lockout_policy.reset(account);
+            if let Some(failed_login_count_field) = Self::FAILED_LOGIN_COUNT_FIELD
+                && failed_login_count > 0
+            {
+                let mut query = Query::default();
+                query.add_filter(Self::PRIMARY_KEY_NAME, user_id.as_str());
+
+                let mut mutation = Mutation::new(Map::from_entry(failed_login_count_field, 0));
+                Self::update_one(&query, &mut mutation).await?;
+            }
+
+            let mut claims = JwtClaims::new(user_id.as_str());
 
             let user_id = user_id.parse()?;
             if let Some(role_field) = Self::ROLE_FIELD
@@ -145,11 +197,61 @@ where
             }
             Ok((user_id, data))
         } else {
+            let failed_attempts = lockout_policy.record_failure(account);
+            Self::record_failed_login(&user_id, failed_login_count, failed_attempts).await?;
             Err(warn!("fail to generate access token"))
         }
     }
 
-    /// Refreshes the access token.
+    /// Records a failed login of the user. It increments the failed login count,
+    /// locks the user for the lock duration if the count reaches the limit of
+    /// the [`LockoutPolicy`], and writes an audit log with the `login_failed` action.
+    /// The failed login count is reset when the user is locked, so the user can
+    /// try again after the lock expires.
+    async fn record_failed_login(
+        user_id: &str,
+        failed_login_count: u8,
+        failed_attempts: u32,
+    ) -> Result<(), Error> {
+        let failed_login_count = failed_login_count.saturating_add(1);
+        let locked = LockoutPolicy::shared().should_lock(failed_login_count);
+        tracing::warn!(
+            user_id,
+            failed_login_count,
+            failed_attempts,
+            locked,
+            "fail to log in with an invalid password"
+        );
+
+        let Some(failed_login_count_field) = Self::FAILED_LOGIN_COUNT_FIELD else {
+            return Ok(());
+        };
+        let mut updates = Map::new();
+        if locked && let Some(locked_until_field) = Self::LOCKED_UNTIL_FIELD {
+            let locked_until = DateTime::now() + LockoutPolicy::shared().lock_duration();
+            updates.upsert(locked_until_field, locked_until.to_utc_timestamp());
+            updates.upsert(failed_login_count_field, 0);
+        } else {
+            updates.upsert("$inc", Map::from_entry(failed_login_count_field, 1));
+        }
+
+        let mut query = Query::default();
+        query.add_filter(Self::PRIMARY_KEY_NAME, user_id);
+
+        let mut mutation = Mutation::new(updates.clone());
+        Self::update_one(&query, &mut mutation).await?;
+
+        let mut audit_log = AuditLog::new(user_id, "login_failed");
+        audit_log.set_user_id(user_id);
+        audit_log.set_mutation(updates);
+        if let Err(err) = audit_log.record::<Self>().await {
+            tracing::error!(user_id, "fail to record the failed login: {err}");
+        }
+        Ok(())
+    }
+
+    /// Refreshes the access token and rotates the refresh token.
+    /// If a refresh token is reused, all the refresh tokens in the family will be revoked.
     async fn refresh_token(claims: &JwtClaims) -> Result<Map, Error> {
         if !claims.data().is_empty() {
             bail!("401 Unauthorized: the JWT token is not a refresh token");
@@ -158,6 +260,24 @@ where
         let Some(user_id) = claims.subject() else {
             bail!("401 Unauthorized: the JWT token does not have a subject");
         };
+        let (Some(token_id), Some(family_id)) = (claims.jwt_id(), claims.nonce()) else {
+            bail!("401 Unauthorized: the refresh token does not belong to a token family");
+        };
+
+        let store = RevocationStore::shared();
+        if store.is_revoked(family_id).await? {
+            bail!("401 Unauthorized: the refresh token has been revoked");
+        }
+        if !store.try_revoke(token_id, claims.expires_at()).await? {
+            let expires_at = DateTime::now() + JwtClaims::refresh_interval();
+            store.revoke(family_id, expires_at).await?;
+            tracing::warn!(
+                user_id,
+                family_id,
+                "the reuse of a refresh token is detected"
+            );
+            bail!("401 Unauthorized: the refresh token has been used");
+        }
 
         let mut query = Query::default();
         let mut fields = vec![Self::PRIMARY_KEY_NAME];
@@ -191,15 +311,52 @@ where
 
         let mut data = Map::new();
         data.upsert("expires_in", claims.expires_in().as_secs());
+        data.upsert("refresh_token", claims.rotate_refresh_token(family_id)?);
         data.upsert("access_token", claims.access_token()?);
         Ok(data)
     }
 
+    /// Revokes the JWT token. For a refresh token, all the refresh tokens
+    /// in the family will also be revoked.
+    async fn revoke_token(claims: &JwtClaims) -> Result<(), Error> {
+        let Some(token_id) = claims.jwt_id() else {
+            bail!("401 Unauthorized: the JWT token does not have an ID");
+        };
+
+        let store = RevocationStore::shared();
+        store.revoke(token_id, claims.expires_at()).await?;
+        if claims.data().is_empty()
+            && let Some(family_id) = claims.nonce()
+        {
+            let expires_at = DateTime::now() + JwtClaims::refresh_interval();
+            store.revoke(family_id, expires_at).await?;
+        }
+        Ok(())
+    }
+
+    /// Logs out the user by revoking the access token,
+    /// and the refresh token specified by the `refresh_token` field in the body.
+    async fn logout(claims: &JwtClaims, body: &Map) -> Result<(), Error> {
+        if let Some(refresh_token) = body.get_str("refresh_token") {
+            let refresh_claims = JwtClaims::verify(refresh_token)?;
+            if refresh_claims.subject() != claims.subject() {
+                bail!("401 Unauthorized: the refresh token does not belong to the user");
+            }
+            Self::revoke_token(&refresh_claims).await?;
+        }
+        Self::revoke_token(claims).await
+    }
+
     /// Verfifies the JWT claims.
     async fn verify_jwt_claims(claims: &JwtClaims) -> Result<bool, Error> {
         let Some(user_id) = claims.subject() else {
             bail!("401 Unauthorized: the JWT token does not have a subject");
         };
+        if let Some(token_id) = claims.jwt_id()
+            && RevocationStore::shared().is_revoked(token_id).await?
+        {
+            bail!("401 Unauthorized: the JWT token has been revoked");
+        }
 
         let mut query = Query::default();
         let mut fields = vec![Self::PRIMARY_KEY_NAME];
@@ -291,4 +448,6 @@ where
 impl JwtAuthService<Uuid> for super::User {
     const LOGIN_AT_FIELD: Option<&'static str> = Some("current_login_at");
     const LOGIN_IP_FIELD: Option<&'static str> = Some("current_login_ip");
+    const FAILED_LOGIN_COUNT_FIELD: Option<&'static str> = Some("failed_login_count");
+    const LOCKED_UNTIL_FIELD: Option<&'static str> = Some("locked_until");
 }
@@ -0,0 +1,168 @@
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+use zino_core::{datetime::DateTime, extension::TomlTableExt, state::State};
+
+/// A lockout policy for the failed logins.
+///
+/// The policy is specified by the `[user.lockout]` configuration:
+///
+/// ```toml
+/// [user.lockout]
+/// max-failed-attempts = 5
+/// base-delay = "1s"
+/// max-delay = "15m"
+/// lock-duration = "30m"
+/// ```
+///
+/// After `n` consecutive failed logins of an account, the login attempts are throttled
+/// for `base-delay * 2^(n-1)` which is capped at `max-delay`. The user is locked
+/// for `lock-duration` when the failed login count reaches `max-failed-attempts`,
+/// and a zero value disables it.
+#[derive(Debug)]
+pub struct LockoutPolicy {
+    /// Max number of failed attempts before the user is locked.
+    max_failed_attempts: u8,
+    /// Base delay of the exponential back-off.
+    base_delay: Duration,
+    /// Max delay of the exponential back-off.
+    max_delay: Duration,
+    /// Duration of the lock.
+    lock_duration: Duration,
+    /// Failed attempts and the last failed time of the accounts.
+    failed_attempts: Mutex<HashMap<String, (u32, DateTime)>>,
+}
+
+impl LockoutPolicy {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(max_failed_attempts: u8, base_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            max_failed_attempts,
+            base_delay,
+            max_delay,
+            lock_duration: Duration::from_secs(60 * 30),
+            failed_attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the shared lockout policy.
+    #[inline]
+    pub fn shared() -> &'static Self {
+        LazyLock::force(&SHARED_LOCKOUT_POLICY)
+    }
+
+    /// Sets the duration of the lock.
+    #[inline]
+    pub fn set_lock_duration(&mut self, lock_duration: Duration) {
+        self.lock_duration = lock_duration;
+    }
+
+    /// Returns the duration of the lock.
+    #[inline]
+    pub fn lock_duration(&self) -> Duration {
+        self.lock_duration
+    }
+
+    /// Returns `true` if the user should be locked for the failed login count.
+    #[inline]
+    pub fn should_lock(&self, failed_login_count: u8) -> bool {
+        self.max_failed_attempts > 0 && failed_login_count >= self.max_failed_attempts
+    }
+
+    /// Returns the back-off delay after the number of consecutive failed attempts.
+    pub fn backoff_delay(&self, failed_attempts: u32) -> Duration {
+        if failed_attempts == 0 {
+            return Duration::ZERO;
+        }
+        let factor = 1u32.checked_shl(failed_attempts - 1).unwrap_or(u32::MAX);
+        self.base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    /// Returns the remaining time before the account can try to log in again.
+    pub fn retry_after(&self, account: &str) -> Option<Duration> {
+        let failed_attempts = self.failed_attempts.lock().ok()?;
+        let (attempts, failed_at) = failed_attempts.get(account)?;
+        let retry_at = *failed_at + self.backoff_delay(*attempts);
+        retry_at
+            .span_after_now()
+            .filter(|duration| !duration.is_zero())
+    }
+
+    /// Records a failed login of the account and returns the number of consecutive failures.
+    pub fn record_failure(&self, account: &str) -> u32 {
+        let now = DateTime::now();
+        let Ok(mut failed_attempts) = self.failed_attempts.lock() else {
+            return 0;
+        };
+        failed_attempts.retain(|_, (_, failed_at)| *failed_at + self.max_delay > now);
+
+        let entry = failed_attempts
+            .entry(account.to_owned())
+            .or_insert((0, now));
+        entry.0 = entry.0.saturating_add(1);
+        entry.1 = now;
+        entry.0
+    }
+
+    /// Resets the failed attempts of the account.
+    #[inline]
+    pub fn reset(&self, account: &str) {
+        if let Ok(mut failed_attempts) = self.failed_attempts.lock() {
+            failed_attempts.remove(account);
+        }
+    }
+}
+
+/// Shared lockout policy.
+static SHARED_LOCKOUT_POLICY: LazyLock<LockoutPolicy> = LazyLock::new(|| {
+    let config = State::shared()
+        .get_config("user")
+        .and_then(|config| config.get_table("lockout"));
+    let max_failed_attempts = config
+        .and_then(|config| config.get_u8("max-failed-attempts"))
+        .unwrap_or(5);
+    let base_delay = config
+        .and_then(|config| config.get_duration("base-delay"))
+        .unwrap_or_else(|| Duration::from_secs(1));
+    let max_delay = config
+        .and_then(|config| config.get_duration("max-delay"))
+        .unwrap_or_else(|| Duration::from_secs(60 * 15));
+    let mut policy = LockoutPolicy::new(max_failed_attempts, base_delay, max_delay);
+    if let Some(lock_duration) = config.and_then(|config| config.get_duration("lock-duration")) {
+        policy.set_lock_duration(lock_duration);
+    }
+    policy
+});
+
+#[cfg(test)]
+mod tests {
+    use super::LockoutPolicy;
+    use std::time::Duration;
+
+    #[test]
+    fn it_throttles_failed_logins() {
+        let policy = LockoutPolicy::new(3, Duration::from_secs(1), Duration::from_secs(10));
+        assert_eq!(policy.backoff_delay(0), Duration::ZERO);
+        assert_eq!(policy.backoff_delay(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff_delay(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff_delay(5), Duration::from_secs(10));
+        assert_eq!(policy.backoff_delay(100), Duration::from_secs(10));
+        assert!(!policy.should_lock(2));
+        assert!(policy.should_lock(3));
+        assert_eq!(policy.lock_duration(), Duration::from_secs(60 * 30));
+
+        assert!(policy.retry_after("alice").is_none());
+        assert_eq!(policy.record_failure("alice"), 1);
+        assert_eq!(policy.record_failure("alice"), 2);
+        assert!(policy.retry_after("alice").is_some());
+        assert!(policy.retry_after("bob").is_none());
+
+        policy.reset("alice");
+        assert!(policy.retry_after("alice").is_none());
+    }
+}
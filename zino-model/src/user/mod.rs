@@ -8,11 +8,11 @@ use zino_core::{
     bail,
     datetime::DateTime,
     error::Error,
-    extension::JsonObjectExt,
-    model::{Model, ModelHooks},
-    orm::ModelHelper,
+    extension::{JsonObjectExt, JsonValueExt},
+    model::{Model, ModelHooks, Mutation, Query},
+    orm::{ModelHelper, Schema},
     validation::Validation,
-    Map, Uuid,
+    JsonValue, Map, Uuid,
 };
 use zino_derive::{DecodeRow, ModelAccessor, Schema};
 
@@ -20,9 +20,13 @@ use zino_derive::{DecodeRow, ModelAccessor, Schema};
 use crate::tag::Tag;
 
 mod jwt_auth;
+mod lockout;
+mod password_policy;
 mod status;
 
 pub use jwt_auth::JwtAuthService;
+pub use lockout::LockoutPolicy;
+pub use password_policy::PasswordPolicy;
 pub use status::UserStatus;
 
 #[cfg(feature = "visibility")]
@@ -32,6 +36,15 @@ mod visibility;
 pub use visibility::UserVisibility;
 
 /// The `user` model.
+///
+/// # Upgrading
+///
+/// The `email` field is encrypted with a blind index `email_hash`.
+/// For an existing table, generate and apply a migration by `Migration::diff::<User>(false)`
+/// to add the `email_hash` column, then call [`User::backfill_email_hashes()`]
+/// to encrypt the plaintext emails and fill in their blind indexes.
+/// The `password_history` and `locked_until` columns should also be added
+/// for the password policy and the lockout policy.
 #[derive(Debug, Clone, Default, Serialize, Deserialize, DecodeRow, Schema, ModelAccessor)]
 #[serde(default)]
 pub struct User {
@@ -64,16 +77,20 @@ pub struct User {
     account: String,
     #[schema(not_null, write_only)]
     password: String,
+    #[schema(write_only, read_only)]
+    password_history: String, // JSON array of the previous encrypted passwords
     nickname: String,
     #[schema(format = "uri")]
     avatar: String,
     #[schema(format = "uri")]
     website: String,
-    #[schema(format = "email")]
+    #[schema(format = "email", encrypted, blind_index = "email_hash")]
     email: String,
+    #[schema(write_only, index_type = "hash")]
+    email_hash: String,
     location: String,
     locale: String,
-    #[schema(format = "phone_number")]
+    #[schema(format = "phone_number", encrypted)]
     mobile: String,
     #[schema(snapshot, nonempty, unique_items, index_type = "gin")]
     roles: Vec<String>,
@@ -88,6 +105,7 @@ pub struct User {
     current_login_ip: String,
     login_count: u32,
     failed_login_count: u8,
+    locked_until: DateTime,
 
     // Extensions.
     content: Map,
@@ -137,9 +155,13 @@ impl Model for User {
             self.account = account.into_owned();
         }
         if let Some(password) = data.parse_string("password") {
-            match User::encrypt_password(&password) {
-                Ok(password) => self.password = password,
-                Err(err) => validation.record_fail("password", err),
+            if let Err(err) = self.check_password(&password) {
+                validation.record_fail("password", err);
+            } else {
+                match User::encrypt_password(&password) {
+                    Ok(password) => self.set_password(password),
+                    Err(err) => validation.record_fail("password", err),
+                }
             }
         }
         if let Some(roles) = data.parse_str_array("roles") {
@@ -194,6 +216,17 @@ impl ModelHooks for User {
         }
         Ok(())
     }
+
+    async fn after_validation(&mut self, data: &mut Map) -> Result<(), Error> {
+        if data.contains_key("password") {
+            // Updates with the encrypted password
+            data.upsert("password", self.password.as_str());
+            if !self.password_history.is_empty() {
+                self.save_password_history().await?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl User {
@@ -220,6 +253,104 @@ impl User {
         Ok(())
     }
 
+    /// Checks the new password against the password policy and the password history.
+    /// The passwords hashed by the client can only be checked against the history.
+    pub fn check_password(&self, password: &str) -> Result<(), Error> {
+        let policy = PasswordPolicy::shared();
+        if !User::is_hashed_password(password)
+            && let Err(err) = policy.validate(password)
+        {
+            bail!("the password {}", err.message());
+        }
+
+        let history_size = policy.history_size();
+        if history_size > 0 && !self.password.is_empty() {
+            let previous_passwords = self.previous_passwords();
+            let recent_passwords = [self.password.as_str()]
+                .into_iter()
+                .chain(previous_passwords.iter().map(|s| s.as_str()))
+                .take(history_size);
+            for encrypted_password in recent_passwords {
+                if User::verify_password(password, encrypted_password)? {
+                    bail!(
+                        "the password has been used in the last {} passwords",
+                        history_size
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the `password` field with an encrypted password,
+    /// and keeps the previous one in the password history.
+    pub fn set_password(&mut self, encrypted_password: String) {
+        let history_size = PasswordPolicy::shared().history_size();
+        if history_size > 0 && !self.password.is_empty() {
+            let mut password_history = vec![self.password.clone()];
+            password_history.extend(self.previous_passwords());
+            password_history.truncate(history_size);
+            self.password_history = JsonValue::from(password_history).to_string();
+        }
+        self.password = encrypted_password;
+    }
+
+    /// Returns the previous encrypted passwords in the password history.
+    fn previous_passwords(&self) -> Vec<String> {
+        self.password_history
+            .parse::<JsonValue>()
+            .ok()
+            .and_then(|value| {
+                value
+                    .parse_str_array()
+                    .map(|v| v.into_iter().map(|s| s.to_owned()).collect())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Saves the password history. It is a read-only column which can not be updated
+    /// by the mutations, so that it will never be modified by the clients.
+    async fn save_password_history(&self) -> Result<(), Error> {
+        let table_name = Self::table_name();
+        let primary_key_name = Self::PRIMARY_KEY_NAME;
+        let id = self.id;
+        let sql = format!(
+            "UPDATE {table_name} SET password_history = #{{password_history}} \
+                WHERE {primary_key_name} = '{id}';"
+        );
+        let params = Map::from_entry("password_history", self.password_history.as_str());
+        Self::execute(&sql, Some(&params)).await?;
+        Ok(())
+    }
+
+    /// Encrypts the plaintext emails and fills in the `email_hash` blind indexes
+    /// for the users created before the `email` field was encrypted.
+    /// It returns the number of updated users.
+    pub async fn backfill_email_hashes() -> Result<u64, Error> {
+        let mut query = Query::default();
+        query.allow_fields(&["id", "email"]);
+        query.add_filter("email_hash", "");
+        query.disable_scopes();
+
+        let mut num_updated = 0;
+        let users: Vec<Map> = Self::find(&query).await?;
+        for user in users {
+            if let Some(id) = user.get_str("id")
+                && let Some(email) = user.get_str("email")
+                && !email.is_empty()
+            {
+                let mut query = Query::default();
+                query.add_filter("id", id);
+                query.disable_scopes();
+
+                let mut mutation = Mutation::new(Map::from_entry("email", email));
+                let ctx = Self::update_one(&query, &mut mutation).await?;
+                num_updated += ctx.rows_affected().unwrap_or_default();
+            }
+        }
+        Ok(num_updated)
+    }
+
     /// Returns the `union_id` field.
     #[inline]
     pub fn union_id(&self) -> &str {
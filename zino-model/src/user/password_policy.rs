@@ -0,0 +1,217 @@
+use sha1::{Digest, Sha1};
+use std::{collections::HashSet, env, fs, path::PathBuf, sync::LazyLock};
+use zino_core::{bail, error::Error, extension::TomlTableExt, state::State};
+
+/// A password policy for the length, complexity, history and breach checks.
+///
+/// The policy is specified by the `[user.password-policy]` configuration:
+///
+/// ```toml
+/// [user.password-policy]
+/// min-length = 8
+/// max-length = 128
+/// require-uppercase = true
+/// require-lowercase = true
+/// require-digit = true
+/// require-symbol = false
+/// history-size = 5
+/// breached-list = "./local/breached-passwords.txt"
+/// ```
+///
+/// The breached list is a local file of SHA-1 hashes in hex, one per line. A line can also be
+/// in the `{hash}:{count}` format, which is compatible with the *Have I Been Pwned* downloads.
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    /// Min length of the password.
+    min_length: usize,
+    /// Max length of the password.
+    max_length: usize,
+    /// A flag to require an uppercase letter.
+    require_uppercase: bool,
+    /// A flag to require a lowercase letter.
+    require_lowercase: bool,
+    /// A flag to require a digit.
+    require_digit: bool,
+    /// A flag to require a symbol.
+    require_symbol: bool,
+    /// Number of the previous passwords which can not be reused.
+    history_size: usize,
+    /// SHA-1 hashes of the breached passwords in uppercase hex.
+    breached_hashes: HashSet<String>,
+}
+
+impl Default for PasswordPolicy {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            require_uppercase: false,
+            require_lowercase: false,
+            require_digit: false,
+            require_symbol: false,
+            history_size: 0,
+            breached_hashes: HashSet::new(),
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Creates a new instance with the default rules.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the shared password policy.
+    #[inline]
+    pub fn shared() -> &'static Self {
+        LazyLock::force(&SHARED_PASSWORD_POLICY)
+    }
+
+    /// Sets the length limits.
+    #[inline]
+    pub fn set_length(&mut self, min_length: usize, max_length: usize) {
+        self.min_length = min_length;
+        self.max_length = max_length;
+    }
+
+    /// Sets the complexity requirements for the uppercase letters, lowercase letters,
+    /// digits and symbols.
+    #[inline]
+    pub fn set_complexity(&mut self, uppercase: bool, lowercase: bool, digit: bool, symbol: bool) {
+        self.require_uppercase = uppercase;
+        self.require_lowercase = lowercase;
+        self.require_digit = digit;
+        self.require_symbol = symbol;
+    }
+
+    /// Sets the number of the previous passwords which can not be reused.
+    #[inline]
+    pub fn set_history_size(&mut self, history_size: usize) {
+        self.history_size = history_size;
+    }
+
+    /// Adds the SHA-1 hashes of the breached passwords.
+    pub fn add_breached_hashes<'a>(&mut self, hashes: impl IntoIterator<Item = &'a str>) {
+        for hash in hashes {
+            let hash = hash.split_once(':').map_or(hash, |(hash, _)| hash).trim();
+            if !hash.is_empty() && !hash.starts_with('#') {
+                self.breached_hashes.insert(hash.to_ascii_uppercase());
+            }
+        }
+    }
+
+    /// Returns the number of the previous passwords which can not be reused.
+    #[inline]
+    pub fn history_size(&self) -> usize {
+        self.history_size
+    }
+
+    /// Returns `true` if the password is in the breached list.
+    pub fn is_breached(&self, password: &str) -> bool {
+        if self.breached_hashes.is_empty() {
+            return false;
+        }
+
+        let hash = format!("{:X}", Sha1::digest(password.as_bytes()));
+        self.breached_hashes.contains(&hash)
+    }
+
+    /// Validates the raw password.
+    pub fn validate(&self, password: &str) -> Result<(), Error> {
+        let length = password.chars().count();
+        if length < self.min_length {
+            bail!("should have at least {} characters", self.min_length);
+        }
+        if length > self.max_length {
+            bail!("should have at most {} characters", self.max_length);
+        }
+        if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            bail!("should contain an uppercase letter");
+        }
+        if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            bail!("should contain a lowercase letter");
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            bail!("should contain a digit");
+        }
+        if self.require_symbol && password.chars().all(|c| c.is_alphanumeric()) {
+            bail!("should contain a symbol");
+        }
+        if self.is_breached(password) {
+            bail!("has appeared in a data breach");
+        }
+        Ok(())
+    }
+}
+
+/// Shared password policy.
+static SHARED_PASSWORD_POLICY: LazyLock<PasswordPolicy> = LazyLock::new(|| {
+    let mut policy = PasswordPolicy::new();
+    let Some(config) = State::shared()
+        .get_config("user")
+        .and_then(|config| config.get_table("password-policy"))
+    else {
+        return policy;
+    };
+
+    let min_length = config.get_usize("min-length").unwrap_or(policy.min_length);
+    let max_length = config.get_usize("max-length").unwrap_or(policy.max_length);
+    policy.set_length(min_length, max_length);
+    policy.set_complexity(
+        config.get_bool("require-uppercase").unwrap_or_default(),
+        config.get_bool("require-lowercase").unwrap_or_default(),
+        config.get_bool("require-digit").unwrap_or_default(),
+        config.get_bool("require-symbol").unwrap_or_default(),
+    );
+    if let Some(history_size) = config.get_usize("history-size") {
+        policy.set_history_size(history_size);
+    }
+    if let Some(breached_list) = config.get_str("breached-list") {
+        let mut path = PathBuf::from(breached_list);
+        if path.is_relative()
+            && let Ok(dir) = env::var("CARGO_MANIFEST_DIR")
+                .map(PathBuf::from)
+                .or_else(|_| env::current_dir())
+        {
+            path = dir.join(path);
+        }
+        match fs::read_to_string(&path) {
+            Ok(hashes) => {
+                policy.add_breached_hashes(hashes.lines());
+                tracing::info!(
+                    num_hashes = policy.breached_hashes.len(),
+                    "the breached password hashes have been loaded"
+                );
+            }
+            Err(err) => {
+                let path = path.display();
+                tracing::error!("fail to read the breached password list `{path}`: {err}");
+            }
+        }
+    }
+    policy
+});
+
+#[cfg(test)]
+mod tests {
+    use super::PasswordPolicy;
+
+    #[test]
+    fn it_validates_passwords() {
+        let mut policy = PasswordPolicy::new();
+        policy.set_complexity(true, true, true, false);
+        assert!(policy.validate("Pa55").is_err());
+        assert!(policy.validate("password123").is_err());
+        assert!(policy.validate("PASSWORD123").is_err());
+        assert!(policy.validate("Password").is_err());
+        assert!(policy.validate("Password123").is_ok());
+
+        let hash = format!("{:X}", <sha1::Sha1 as sha1::Digest>::digest(b"Password123"));
+        let line = format!("{}:42", hash.to_ascii_lowercase());
+        policy.add_breached_hashes(["# breached passwords", line.as_str()]);
+        assert!(policy.is_breached("Password123"));
+        assert!(policy.validate("Password123").is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{middleware, ActixResponse, Request, RouterConfigure};
+use crate::{endpoint, middleware, ActixResponse, Request, RouterConfigure};
 use actix_files::{Files, NamedFile};
 use actix_web::{
     dev::{fn_service, ServiceRequest, ServiceResponse},
@@ -12,6 +12,7 @@ use std::{fs, path::PathBuf, time::Duration};
 use utoipa_rapidoc::RapiDoc;
 use zino_core::{
     application::{Application, ServerTag, StaticRecord},
+    auth::JwtKey,
     extension::TomlTableExt,
     response::Response,
     schedule::{AsyncCronJob, Job, JobScheduler},
@@ -41,6 +42,16 @@ impl Application for ActixCluster {
 
     fn run(self, async_jobs: StaticRecord<AsyncCronJob>) {
         let runtime = Runtime::new().expect("fail to build Tokio runtime for `ActixCluster`");
+        #[cfg(feature = "orm")]
+        match runtime.block_on(zino_core::orm::Seeder::seed_on_boot()) {
+            Ok(true) => return,
+            Ok(false) => (),
+            Err(err) => {
+                tracing::error!("fail to run the seeder: {err}");
+                std::process::exit(1);
+            }
+        }
+
         let mut scheduler = JobScheduler::new();
         for (cron_expr, exec) in async_jobs {
             scheduler.add(Job::new_async(cron_expr, exec));
@@ -112,6 +123,18 @@ impl Application for ActixCluster {
                     public_dir = default_public_dir;
                 }
 
+                // Loads the JWT keys eagerly so that a misconfiguration fails at startup.
+                JwtKey::signing_key();
+
+                // JWKS endpoint for the shared JWT keys.
+                let mut jwks_route = None;
+                if !JwtKey::shared_keys().is_empty() {
+                    jwks_route = app_state
+                        .get_config("jwt")
+                        .and_then(|config| config.get_str("jwks-route"))
+                        .or(Some("/.well-known/jwks.json"));
+                }
+
                 HttpServer::new(move || {
                     let index_file_handler = web::get()
                         .to(|| async { NamedFile::open_async("./public/index.html").await });
@@ -135,6 +158,11 @@ impl Application for ActixCluster {
                             let res = Response::new(StatusCode::NOT_FOUND);
                             ActixResponse::from(res).respond_to(&req.into())
                         }));
+                    if let Some(path) = jwks_route {
+                        let jwks_handler = web::get()
+                            .to(|| async { ActixResponse::from(endpoint::jwks_response()) });
+                        app = app.route(path, jwks_handler);
+                    }
                     for route in default_routes {
                         app = app.configure(route);
                     }
@@ -187,6 +215,7 @@ impl Application for ActixCluster {
                         .app_data(JsonConfig::default().limit(body_limit))
                         .app_data(PayloadConfig::default().limit(body_limit))
                         .wrap(Compress::default())
+                        .wrap(middleware::SessionInitializer::default())
                         .wrap(middleware::RequestContextInitializer::default())
                         .wrap(middleware::tracing_middleware())
                         .wrap(middleware::cors_middleware())
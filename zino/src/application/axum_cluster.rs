@@ -1,4 +1,4 @@
-use crate::{endpoint, middleware, AxumExtractor};
+use crate::{endpoint, middleware, AxumExtractor, AxumResponse};
 use axum::{
     error_handling::HandleErrorLayer,
     extract::{rejection::LengthLimitError, DefaultBodyLimit},
@@ -25,6 +25,7 @@ use tower_http::{
 use utoipa_rapidoc::RapiDoc;
 use zino_core::{
     application::{Application, ServerTag, StaticRecord},
+    auth::JwtKey,
     extension::TomlTableExt,
     response::{FullResponse, Response},
     schedule::{AsyncCronJob, Job, JobScheduler},
@@ -60,6 +61,16 @@ impl Application for AxumCluster {
             .enable_all()
             .build()
             .expect("fail to build Tokio runtime for `AxumCluster`");
+        #[cfg(feature = "orm")]
+        match runtime.block_on(zino_core::orm::Seeder::seed_on_boot()) {
+            Ok(true) => return,
+            Ok(false) => (),
+            Err(err) => {
+                tracing::error!("fail to run the seeder: {err}");
+                std::process::exit(1);
+            }
+        }
+
         let mut scheduler = JobScheduler::new();
         for (cron_expr, exec) in async_jobs {
             scheduler.add(Job::new_async(cron_expr, exec));
@@ -130,6 +141,18 @@ impl Application for AxumCluster {
                     public_dir = default_public_dir;
                 }
 
+                // Loads the JWT keys eagerly so that a misconfiguration fails at startup.
+                JwtKey::signing_key();
+
+                // JWKS endpoint for the shared JWT keys.
+                let mut jwks_route = None;
+                if !JwtKey::shared_keys().is_empty() {
+                    jwks_route = app_state
+                        .get_config("jwt")
+                        .and_then(|config| config.get_str("jwks-route"))
+                        .or(Some("/.well-known/jwks.json"));
+                }
+
                 let mut app = Router::new();
                 if public_dir.exists() {
                     let index_file = public_dir.join("index.html");
@@ -161,6 +184,10 @@ impl Application for AxumCluster {
                 if let Some(path) = websocket_route {
                     app = app.route(path, routing::get(endpoint::websocket_handler));
                 }
+                if let Some(path) = jwks_route {
+                    let jwks_handler = || async { AxumResponse::from(endpoint::jwks_response()) };
+                    app = app.route(path, routing::get(jwks_handler));
+                }
                 for route in &default_routes {
                     app = app.merge(route.clone());
                 }
@@ -226,6 +253,7 @@ impl Application for AxumCluster {
                             .layer(LazyLock::force(&middleware::TRACING_MIDDLEWARE))
                             .layer(LazyLock::force(&middleware::CORS_MIDDLEWARE))
                             .layer(from_fn(middleware::request_context))
+                            .layer(from_fn(middleware::resume_session))
                             .layer(from_fn(middleware::extract_etag))
                             .layer(HandleErrorLayer::new(|err: BoxError| async move {
                                 let status_code = if err.is::<Elapsed>() {
@@ -20,6 +20,12 @@ pub trait DefaultController<K, U = K> {
     /// Lists models.
     async fn list(req: Self::Request) -> Self::Result;
 
+    /// Aggregates models.
+    async fn aggregate(req: Self::Request) -> Self::Result;
+
+    /// Searches models by the full-text search.
+    async fn search(req: Self::Request) -> Self::Result;
+
     /// Logically deletes a model.
     async fn soft_delete(req: Self::Request) -> Self::Result;
 
@@ -46,19 +52,40 @@ pub trait DefaultController<K, U = K> {
 
     /// Gets the model definition.
     async fn definition(req: Self::Request) -> Self::Result;
+
+    /// Lists the revisions of a model in the audit logs.
+    async fn history(req: Self::Request) -> Self::Result;
+
+    /// Restores a model to a prior version in the audit logs.
+    async fn restore(req: Self::Request) -> Self::Result;
 }
 
+#[cfg(any(feature = "actix", feature = "axum"))]
+#[cfg(feature = "orm")]
+use futures::StreamExt;
+
+#[cfg(any(feature = "actix", feature = "axum"))]
+#[cfg(feature = "orm")]
+use std::pin::pin;
+
 #[cfg(any(feature = "actix", feature = "axum"))]
 #[cfg(feature = "orm")]
 use zino_core::{
-    extension::JsonObjectExt,
-    model::{ModelHooks, Mutation, Query},
-    orm::{ModelAccessor, ModelHelper},
+    auth::{PolicyEngine, UserSession},
+    error::Error,
+    extension::{JsonObjectExt, JsonValueExt},
+    model::{Aggregation, ModelHooks, Mutation, Query},
+    orm::{self, AuditLog, DatabaseConnection, ModelAccessor, ModelHelper},
     request::RequestContext,
     response::{ExtractRejection, Rejection, StatusCode},
-    JsonValue, Map,
+    warn, JsonValue, Map,
 };
 
+/// Min number of models to be imported by the bulk load.
+#[cfg(any(feature = "actix", feature = "axum"))]
+#[cfg(feature = "orm")]
+const BULK_LOAD_THRESHOLD: usize = 10000;
+
 #[cfg(any(feature = "actix", feature = "axum"))]
 #[cfg(feature = "orm")]
 impl<K, U, M: ModelAccessor<K, U>> DefaultController<K, U> for M
@@ -77,13 +104,24 @@ where
         if !validation.is_success() {
             return Err(Rejection::bad_request(validation).context(&req).into());
         }
+        if Self::ACCESS_CONTROL || Self::TENANT_SCOPE.is_some() {
+            let context = serde_json::to_value(&model)
+                .extract(&req)?
+                .into_map_opt()
+                .unwrap_or_default();
+            check_model_tenant::<Self, K, U>(&req, &context)?;
+            authorize_access::<Self, K, U>(&req, "new", None, &context).await?;
+        }
 
         let mut model_snapshot = model.snapshot();
         Self::after_decode(&mut model_snapshot)
             .await
             .extract(&req)?;
 
-        let ctx = model.insert().await.extract(&req)?;
+        let ctx = model
+            .insert()
+            .await
+            .map_err(|err| reject_database_error(&req, err))?;
         if let Some(last_insert_id) = ctx.last_insert_id()
             && model_snapshot.get_i64("id") == Some(0)
         {
@@ -102,7 +140,23 @@ where
 
     async fn delete(req: Self::Request) -> Self::Result {
         let id = req.parse_param::<K>("id")?;
-        Self::soft_delete_by_id(&id).await.extract(&req)?;
+        authorize_model_access::<Self, K, U>(&req, "delete", &id).await?;
+
+        if Self::AUDIT {
+            let audit_log = Self::new_audit_log(&id, "soft_delete")
+                .await
+                .extract(&req)?;
+            let pool = Self::acquire_writer().await.extract(&req)?.pool();
+            let mut transaction = pool.begin().await.extract(&req)?;
+            Self::soft_delete_by_id_with(&mut transaction, &id)
+                .await
+                .extract(&req)?;
+            record_audit_log::<Self, K, U>(&req, &mut transaction, &id, audit_log).await?;
+            transaction.commit().await.extract(&req)?;
+            Self::invalidate_cache();
+        } else {
+            Self::soft_delete_by_id(&id).await.extract(&req)?;
+        }
 
         let res = crate::Response::default().context(&req);
         Ok(res.into())
@@ -112,13 +166,51 @@ where
         let id = req.parse_param::<K>("id")?;
         let mut body = req.parse_body().await?;
 
+        // The `If-Match` header takes precedence over the `version` field in the body.
+        if let Some(etag) = req.get_header("if-match")
+            && etag != "*"
+        {
+            let version = etag
+                .trim_start_matches("W/")
+                .trim_matches('"')
+                .parse::<u64>()
+                .map_err(|err| Rejection::from_validation_entry("if-match", err).context(&req))?;
+            body.upsert("version", version);
+        }
+        authorize_model_access::<Self, K, U>(&req, "update", &id).await?;
+
+        let audit_log = if Self::AUDIT {
+            let mut audit_log = Self::new_audit_log(&id, "update").await.extract(&req)?;
+            audit_log.set_mutation(body.clone());
+            Some(audit_log)
+        } else {
+            None
+        };
+
         let extension = req.get_data::<<Self as ModelHooks>::Extension>();
-        let (validation, model) = Self::update_by_id(&id, &mut body, extension)
-            .await
-            .extract(&req)?;
+        let (validation, model) = if let Some(audit_log) = audit_log {
+            let pool = Self::acquire_writer().await.extract(&req)?.pool();
+            let mut transaction = pool.begin().await.extract(&req)?;
+            let (validation, model) =
+                Self::update_by_id_with(&mut transaction, &id, &mut body, extension)
+                    .await
+                    .map_err(|err| reject_database_error(&req, err))?;
+            if validation.is_success() {
+                record_audit_log::<Self, K, U>(&req, &mut transaction, &id, audit_log).await?;
+                transaction.commit().await.extract(&req)?;
+                Self::invalidate_cache();
+            }
+            (validation, model)
+        } else {
+            Self::update_by_id(&id, &mut body, extension)
+                .await
+                .map_err(|err| reject_database_error(&req, err))?
+        };
         let mut res = crate::Response::from(validation).context(&req);
         if res.is_success() {
+            let version = model.next_version();
             let model_filters = model.next_version_filters();
+            res.insert_header("etag", format!(r#""{version}""#));
             res.set_json_data(Map::data_entry(model_filters));
         }
         Ok(res.into())
@@ -126,68 +218,234 @@ where
 
     async fn view(req: Self::Request) -> Self::Result {
         let id = req.parse_param::<K>("id")?;
-        let model = Self::fetch_by_id(&id).await.extract(&req)?;
+        let mut model = Self::fetch_by_id(&id).await.extract(&req)?;
+        check_model_tenant::<Self, K, U>(&req, &model)?;
+        authorize_access::<Self, K, U>(&req, "view", Some(&id), &model).await?;
+        if let Some(populate) = req.get_query("populate") {
+            let mut query = Self::default_query();
+            let validation = query.read_map(&Map::from_entry("populate", populate));
+            if !validation.is_success() {
+                return Err(Rejection::bad_request(validation).context(&req).into());
+            }
+
+            let populate_paths = query.populate_paths();
+            if !populate_paths.is_empty() {
+                let tenant_id = session_tenant_id::<Self>(&req).map(JsonValue::from);
+                let mut models = vec![model];
+                Self::populate_paths(&mut models, &populate_paths, true, tenant_id.as_ref())
+                    .await
+                    .extract(&req)?;
+                model = models.pop().unwrap_or_default();
+            }
+        }
+
         let mut res = crate::Response::default().context(&req);
+        if let Some(version) = model.get_u64("version") {
+            res.insert_header("etag", format!(r#""{version}""#));
+        }
         res.set_json_data(Map::data_entry(model));
         Ok(res.into())
     }
 
     async fn list(req: Self::Request) -> Self::Result {
+        authorize_access::<Self, K, U>(&req, "list", None, &Map::new()).await?;
+
         let mut query = Self::default_list_query();
+        scope_query_tenant::<Self, K, U>(&req, &mut query);
         let extension = req.get_data::<<Self as ModelHooks>::Extension>();
         Self::before_list(&mut query, extension.as_ref())
             .await
             .extract(&req)?;
 
         let mut res = req.query_validation(&mut query)?;
-        let models = if query.populate_enabled() {
-            let mut models = Self::fetch(&query).await.extract(&req)?;
-            for model in models.iter_mut() {
-                Self::before_respond(model, extension.as_ref())
-                    .await
-                    .extract(&req)?;
-            }
-            models
+        Self::check_cursor(&query)
+            .map_err(|err| Rejection::from_validation_entry("cursor", err).context(&req))?;
+
+        // The sort fields and the primary key are required to encode the cursors,
+        // and they are removed from the response if they have not been requested.
+        let primary_key_name = Self::PRIMARY_KEY_NAME;
+        let cursor_fields = query
+            .sort_order()
+            .iter()
+            .map(|(field, _)| field.as_ref() as &str)
+            .chain([primary_key_name])
+            .filter(|&field| {
+                let fields = query.fields();
+                !fields.is_empty() && Self::has_column(field) && !fields.iter().any(|s| s == field)
+            })
+            .map(|field| field.to_owned())
+            .collect::<Vec<_>>();
+        for field in cursor_fields.iter() {
+            query.add_field(field);
+        }
+
+        // One more row is fetched to check whether there are more rows.
+        let limit = query.limit();
+        if limit > 0 {
+            query.set_limit(limit + 1);
+        }
+        let mut models = if query.populate_enabled() {
+            Self::fetch(&query).await.extract(&req)?
         } else {
-            let mut models = Self::find(&query).await.extract(&req)?;
-            let translate_enabled = query.translate_enabled();
-            for model in models.iter_mut() {
-                Self::after_decode(model).await.extract(&req)?;
-                translate_enabled.then(|| Self::translate_model(model));
-                Self::before_respond(model, extension.as_ref())
-                    .await
-                    .extract(&req)?;
+            Self::find_as::<Map>(&query).await.extract(&req)?
+        };
+        query.set_limit(limit);
+
+        let has_more = limit > 0 && models.len() > limit;
+        if has_more {
+            if query.cursor().is_some_and(|(_, backward)| backward) {
+                // The rows for a backward cursor have been reversed.
+                models.remove(0);
+            } else {
+                models.truncate(limit);
             }
-            models
+        }
+
+        // Cursors are encoded before the models are modified for the response.
+        let (has_next, has_prev) = match query.cursor() {
+            Some((_, true)) => (true, has_more),
+            Some((_, false)) => (has_more, true),
+            None => (has_more, false),
+        };
+        let encode_cursor = |model: &Map| {
+            query
+                .encode_cursor(model, primary_key_name)
+                .map_err(|err| Rejection::from_validation_entry("fields", err).context(&req))
         };
+        let next_cursor = models
+            .last()
+            .filter(|_| has_next)
+            .map(encode_cursor)
+            .transpose()?;
+        let prev_cursor = models
+            .first()
+            .filter(|_| has_prev)
+            .map(encode_cursor)
+            .transpose()?;
+        for model in models.iter_mut() {
+            for field in cursor_fields.iter() {
+                model.remove(field);
+            }
+            Self::before_respond(model, extension.as_ref())
+                .await
+                .extract(&req)?;
+        }
 
         let mut data = Map::data_entries(models);
         if let Some(page_size) = req.get_query("page_size").and_then(|s| s.parse().ok())
             && req.get_query("total_rows").is_none()
+            && query.cursor().is_none()
         {
             let total_rows = Self::count(&query).await.extract(&req)?;
             let page_count = total_rows.div_ceil(page_size);
             data.upsert("total_rows", total_rows);
             data.upsert("page_count", page_count);
         }
+        data.upsert("next_cursor", next_cursor);
+        data.upsert("prev_cursor", prev_cursor);
         res.set_json_data(data);
         Ok(res.into())
     }
 
+    async fn aggregate(req: Self::Request) -> Self::Result {
+        authorize_access::<Self, K, U>(&req, "aggregate", None, &Map::new()).await?;
+
+        let mut query = Self::default_list_query();
+        scope_query_tenant::<Self, K, U>(&req, &mut query);
+        let extension = req.get_data::<<Self as ModelHooks>::Extension>();
+        Self::before_list(&mut query, extension.as_ref())
+            .await
+            .extract(&req)?;
+
+        let (pipeline, filters): (Map, Map) = req
+            .parse_query::<Map>()?
+            .into_iter()
+            .partition(|(key, _)| Aggregation::is_pipeline_key(key));
+        let validation = query.read_map(&filters);
+        if !validation.is_success() {
+            return Err(Rejection::bad_request(validation).context(&req).into());
+        }
+
+        let mut aggregation = Aggregation::default();
+        let validation = aggregation.read_map(&pipeline);
+        if !validation.is_success() {
+            return Err(Rejection::bad_request(validation).context(&req).into());
+        }
+
+        let data = Self::aggregate::<Map>(&query, &aggregation)
+            .await
+            .extract(&req)?;
+        let mut res = crate::Response::default().context(&req);
+        res.set_json_data(Map::data_entries(data));
+        Ok(res.into())
+    }
+
+    async fn search(req: Self::Request) -> Self::Result {
+        let Some(search) = req.get_query("q").filter(|s| !s.trim().is_empty()) else {
+            let err = warn!("the search text should be nonempty");
+            return Err(Rejection::from_validation_entry("q", err)
+                .context(&req)
+                .into());
+        };
+
+        authorize_access::<Self, K, U>(&req, "search", None, &Map::new()).await?;
+
+        let mut query = Self::default_list_query();
+        scope_query_tenant::<Self, K, U>(&req, &mut query);
+        let extension = req.get_data::<<Self as ModelHooks>::Extension>();
+        Self::before_list(&mut query, extension.as_ref())
+            .await
+            .extract(&req)?;
+
+        let mut res = req.query_validation(&mut query)?;
+        let language = req.get_query("language");
+        let mut models = Self::search(&query, search, language).await.extract(&req)?;
+        for model in models.iter_mut() {
+            Self::before_respond(model, extension.as_ref())
+                .await
+                .extract(&req)?;
+        }
+        res.set_json_data(Map::data_entries(models));
+        Ok(res.into())
+    }
+
     async fn soft_delete(req: Self::Request) -> Self::Result {
         let id = req.parse_param::<K>("id")?;
-        Self::delete_by_id(&id).await.extract(&req)?;
+        authorize_model_access::<Self, K, U>(&req, "soft_delete", &id).await?;
+
+        if Self::AUDIT {
+            let audit_log = Self::new_audit_log(&id, "delete").await.extract(&req)?;
+            let pool = Self::acquire_writer().await.extract(&req)?.pool();
+            let mut transaction = pool.begin().await.extract(&req)?;
+            Self::delete_by_id_with(&mut transaction, &id)
+                .await
+                .extract(&req)?;
+            record_audit_log::<Self, K, U>(&req, &mut transaction, &id, audit_log).await?;
+            transaction.commit().await.extract(&req)?;
+            Self::invalidate_cache();
+        } else {
+            Self::delete_by_id(&id).await.extract(&req)?;
+        }
 
         let res = crate::Response::default().context(&req);
         Ok(res.into())
     }
 
     async fn batch_insert(mut req: Self::Request) -> Self::Result {
+        let action = "batch_insert";
         let data = req.parse_body::<Vec<Map>>().await?;
         let extension = req.get_data::<<Self as ModelHooks>::Extension>();
         let mut models = Vec::with_capacity(data.len());
         let mut validations = Vec::new();
+        let tenant_id = session_tenant_id::<Self>(&req);
+        if Self::TENANT_SCOPE.is_some() && tenant_id.is_none() {
+            let err = warn!("the tenant of the user session is unknown");
+            return Err(Rejection::forbidden(err).context(&req).into());
+        }
         for (index, mut map) in data.into_iter().enumerate() {
+            if let Some(tenant_scope) = Self::TENANT_SCOPE {
+                map.upsert(tenant_scope, tenant_id.clone());
+            }
             Self::before_extract()
                 .await
                 .map_err(|err| Rejection::from_error(err).context(&req))?;
@@ -201,6 +459,7 @@ where
                 validation = model.check_constraints().await.extract(&req)?;
             }
             if validation.is_success() {
+                authorize_access::<Self, K, U>(&req, action, None, &map).await?;
                 model.after_validation(&mut map).await.extract(&req)?;
                 if let Some(ref extension) = extension {
                     model
@@ -220,7 +479,9 @@ where
             res.set_json_data(validations);
             Ok(res.into())
         } else {
-            let ctx = Self::insert_many(models).await.extract(&req)?;
+            let ctx = Self::insert_many(models)
+                .await
+                .map_err(|err| reject_database_error(&req, err))?;
             let data = Map::from_entry("rows_affected", ctx.rows_affected());
             let mut res = crate::Response::default().context(&req);
             res.set_code(StatusCode::CREATED);
@@ -237,7 +498,26 @@ where
             let primary_key_values = Map::from_entry("$in", data);
             Map::from_entry(Self::PRIMARY_KEY_NAME, primary_key_values)
         };
-        let query = Query::new(filters);
+        let mut query = Query::new(filters);
+        scope_query_tenant::<Self, K, U>(&req, &mut query);
+        if Self::ACCESS_CONTROL {
+            let primary_key_name = Self::PRIMARY_KEY_NAME;
+            let mut query = query.clone();
+            query.set_limit(0);
+
+            let models = Self::find::<Map>(&query).await.extract(&req)?;
+            for model in models {
+                let resource_id = model.parse_string(primary_key_name);
+                authorize_resource_access::<Self, K, U>(
+                    &req,
+                    "batch_delete",
+                    resource_id.as_deref(),
+                    &model,
+                )
+                .await?;
+            }
+        }
+
         let ctx = Self::delete_many(&query).await.extract(&req)?;
         let data = Map::from_entry("rows_affected", ctx.rows_affected());
         let mut res = crate::Response::default().context(&req);
@@ -253,8 +533,30 @@ where
         let mut rows_affected = 0;
         for mut map in data.into_iter() {
             if let Some(id) = map.remove(primary_key_name) {
-                let query = Query::new(Map::from_entry(primary_key_name, id));
+                let resource_id = id.to_string_unquoted();
+                let mut query = Query::new(Map::from_entry(primary_key_name, id));
+                scope_query_tenant::<Self, K, U>(&req, &mut query);
+                if Self::ACCESS_CONTROL {
+                    let model = Self::find_one::<Map>(&query)
+                        .await
+                        .extract(&req)?
+                        .unwrap_or_default();
+                    authorize_resource_access::<Self, K, U>(
+                        &req,
+                        "batch_update",
+                        Some(&resource_id),
+                        &model,
+                    )
+                    .await?;
+                }
+
+                // Neither the primary key nor the tenant of a model can be updated
                 let mut mutation = Mutation::new(map);
+                mutation.allow_fields(Self::fields());
+                mutation.deny_fields(&[primary_key_name]);
+                if let Some(tenant_scope) = Self::TENANT_SCOPE {
+                    mutation.deny_fields(&[tenant_scope]);
+                }
                 let ctx = Self::update_one(&query, &mut mutation)
                     .await
                     .extract(&req)?;
@@ -268,11 +570,41 @@ where
     }
 
     async fn import(mut req: Self::Request) -> Self::Result {
+        let action = "import";
         let is_upsert_mode = req.get_query("mode").is_some_and(|s| s == "upsert");
         let data = req.parse_body::<Vec<Map>>().await?;
+        if data.is_empty() {
+            let mut res = crate::Response::default().context(&req);
+            res.set_json_data(Map::from_entry("rows_affected", 0));
+            return Ok(res.into());
+        }
+        let conflict_target = if let Some(fields) = req.get_query("conflict_target") {
+            let fields = fields.split(',').map(|s| s.trim()).collect::<Vec<_>>();
+            if let Some(field) = fields
+                .iter()
+                .find(|&&field| !Self::fields().contains(&field))
+            {
+                let err = warn!("the conflict target `{}` is not a column", field);
+                return Err(Rejection::from_validation_entry("conflict_target", err)
+                    .context(&req)
+                    .into());
+            }
+            Some(fields)
+        } else {
+            None
+        };
+
         let extension = req.get_data::<<Self as ModelHooks>::Extension>();
-        let mut rows_affected = 0;
+        let mut models = Vec::with_capacity(data.len());
+        let tenant_id = session_tenant_id::<Self>(&req);
+        if Self::TENANT_SCOPE.is_some() && tenant_id.is_none() {
+            let err = warn!("the tenant of the user session is unknown");
+            return Err(Rejection::forbidden(err).context(&req).into());
+        }
         for (index, mut map) in data.into_iter().enumerate() {
+            if let Some(tenant_scope) = Self::TENANT_SCOPE {
+                map.upsert(tenant_scope, tenant_id.clone());
+            }
             Self::before_extract()
                 .await
                 .map_err(|err| Rejection::from_error(err).context(&req))?;
@@ -286,6 +618,7 @@ where
                 validation = model.check_constraints().await.extract(&req)?;
             }
             if validation.is_success() {
+                authorize_access::<Self, K, U>(&req, action, None, &map).await?;
                 model.after_validation(&mut map).await.extract(&req)?;
                 if let Some(ref extension) = extension {
                     model
@@ -293,12 +626,7 @@ where
                         .await
                         .map_err(|err| Rejection::from_error(err).context(&req))?;
                 }
-                if is_upsert_mode {
-                    model.upsert().await.extract(&req)?;
-                } else {
-                    model.insert().await.extract(&req)?;
-                }
-                rows_affected += 1;
+                models.push(model);
             } else {
                 let mut map = validation.into_map();
                 map.upsert("index", index);
@@ -309,6 +637,16 @@ where
             }
         }
 
+        let result = if is_upsert_mode {
+            Self::upsert_many(models, conflict_target.as_deref()).await
+        } else if models.len() >= BULK_LOAD_THRESHOLD {
+            Self::bulk_load(models).await
+        } else {
+            Self::insert_many(models).await
+        };
+        let ctx = result.map_err(|err| reject_database_error(&req, err))?;
+
+        let rows_affected = ctx.rows_affected().unwrap_or_default();
         let data = Map::from_entry("rows_affected", rows_affected);
         let mut res = crate::Response::default().context(&req);
         res.set_json_data(data);
@@ -316,35 +654,69 @@ where
     }
 
     async fn export(req: Self::Request) -> Self::Result {
+        authorize_access::<Self, K, U>(&req, "export", None, &Map::new()).await?;
+
         let mut query = Self::default_query();
+        scope_query_tenant::<Self, K, U>(&req, &mut query);
         let extension = req.get_data::<<Self as ModelHooks>::Extension>();
         Self::before_list(&mut query, extension.as_ref())
             .await
             .extract(&req)?;
 
         let mut res = req.query_validation(&mut query)?;
-        let mut models = Self::find(&query).await.extract(&req)?;
-        let translate_enabled = query.translate_enabled();
-        for model in models.iter_mut() {
-            Self::after_decode(model).await.extract(&req)?;
-            translate_enabled.then(|| Self::translate_model(model));
-            Self::before_respond(model, extension.as_ref())
-                .await
-                .extract(&req)?;
-        }
 
+        // The number of rows is capped since the response body is buffered.
+        let max_rows = orm::max_rows();
+        let mut stream = pin!(Self::find_stream::<Map>(&query).take(max_rows));
         let format = req.get_query("format").unwrap_or("json");
-        match format {
-            "csv" => res.set_csv_response(models),
-            "jsonlines" => res.set_jsonlines_response(models),
-            "msgpack" => res.set_msgpack_response(models),
-            _ => res.set_json_response(models),
+        if matches!(format, "csv" | "jsonlines") {
+            // The rows are encoded one by one without collecting the models.
+            let mut buffer = Vec::new();
+            let mut fields = None;
+            while let Some(result) = stream.next().await {
+                let mut model = result.extract(&req)?;
+                Self::before_respond(&mut model, extension.as_ref())
+                    .await
+                    .extract(&req)?;
+                buffer = if format == "csv" {
+                    let with_headers = fields.is_none();
+                    let fields = fields.get_or_insert_with(|| model.keys().cloned().collect());
+                    model
+                        .to_csv_record(fields, with_headers, buffer)
+                        .extract(&req)?
+                } else {
+                    JsonValue::from(model).to_jsonlines(buffer).extract(&req)?
+                };
+            }
+            res.set_bytes_response(buffer);
+            if format == "csv" {
+                res.set_content_type("text/csv; charset=utf-8");
+            } else {
+                res.set_content_type("application/jsonlines; charset=utf-8");
+            }
+        } else {
+            let mut models = Vec::new();
+            while let Some(result) = stream.next().await {
+                let mut model = result.extract(&req)?;
+                Self::before_respond(&mut model, extension.as_ref())
+                    .await
+                    .extract(&req)?;
+                models.push(model);
+            }
+            if format == "msgpack" {
+                res.set_msgpack_response(models);
+            } else {
+                res.set_json_response(models);
+            }
         }
         Ok(res.into())
     }
 
     async fn tree(req: Self::Request) -> Self::Result {
+        authorize_access::<Self, K, U>(&req, "tree", None, &Map::new()).await?;
+
         let mut query = Self::default_list_query();
+        scope_query_tenant::<Self, K, U>(&req, &mut query);
         let extension = req.get_data::<<Self as ModelHooks>::Extension>();
         Self::before_list(&mut query, extension.as_ref())
             .await
@@ -357,13 +729,7 @@ where
         let mut models = if query.populate_enabled() {
             Self::fetch(&query).await.extract(&req)?
         } else {
-            let mut models = Self::find(&query).await.extract(&req)?;
-            let translate_enabled = query.translate_enabled();
-            for model in models.iter_mut() {
-                Self::after_decode(model).await.extract(&req)?;
-                translate_enabled.then(|| Self::translate_model(model));
-            }
-            models
+            Self::find_as::<Map>(&query).await.extract(&req)?
         };
 
         let primary_key_name = Self::PRIMARY_KEY_NAME;
@@ -372,6 +738,7 @@ where
             .filter_map(|model| model.get(primary_key_name).cloned())
             .collect::<Vec<_>>();
         let mut query = Self::default_snapshot_query();
+        scope_query_tenant::<Self, K, U>(&req, &mut query);
         query.allow_fields(&["parent_id"]);
         query.add_filter("parent_id", Map::from_entry("$in", values));
         query.add_filter("status", Map::from_entry("$ne", "Deleted"));
@@ -396,6 +763,8 @@ where
     }
 
     async fn schema(req: Self::Request) -> Self::Result {
+        authorize_access::<Self, K, U>(&req, "schema", None, &Map::new()).await?;
+
         let schema = serde_json::to_value(Self::schema()).extract(&req)?;
         let mut res = crate::Response::default().context(&req);
         res.set_json_response(schema);
@@ -403,6 +772,8 @@ where
     }
 
     async fn definition(req: Self::Request) -> Self::Result {
+        authorize_access::<Self, K, U>(&req, "definition", None, &Map::new()).await?;
+
         let action = req.get_query("action").unwrap_or("insert");
         let columns = Self::columns();
         let mut definition = Map::new();
@@ -444,4 +815,229 @@ where
         res.set_json_response(data);
         Ok(res.into())
     }
+
+    async fn history(req: Self::Request) -> Self::Result {
+        let id = req.parse_param::<K>("id")?;
+        authorize_model_access::<Self, K, U>(&req, "history", &id).await?;
+
+        let audit_logs = AuditLog::list::<Self>(&id.to_string())
+            .await
+            .extract(&req)?;
+
+        let mut res = crate::Response::default().context(&req);
+        res.set_json_data(Map::data_entries(audit_logs));
+        Ok(res.into())
+    }
+
+    async fn restore(req: Self::Request) -> Self::Result {
+        let id = req.parse_param::<K>("id")?;
+        let version = req
+            .get_query("version")
+            .unwrap_or_default()
+            .parse::<u64>()
+            .map_err(|err| Rejection::from_validation_entry("version", err).context(&req))?;
+        authorize_model_access::<Self, K, U>(&req, "restore", &id).await?;
+
+        let mut audit_log = Self::new_audit_log(&id, "restore").await.extract(&req)?;
+        let pool = Self::acquire_writer().await.extract(&req)?.pool();
+        let mut transaction = pool.begin().await.extract(&req)?;
+        let (model, updates) = Self::restore_by_id_with(&mut transaction, &id, version)
+            .await
+            .extract(&req)?;
+        audit_log.set_mutation(updates);
+        record_audit_log::<Self, K, U>(&req, &mut transaction, &id, audit_log).await?;
+        transaction.commit().await.extract(&req)?;
+        Self::invalidate_cache();
+
+        let mut res = crate::Response::default().context(&req);
+        let version = model.next_version();
+        let model_filters = model.next_version_filters();
+        res.insert_header("etag", format!(r#""{version}""#));
+        res.set_json_data(Map::data_entry(model_filters));
+        Ok(res.into())
+    }
+}
+
+/// Records the audit log with the model data after the changes,
+/// the acting user and the request ID. The audit log is written in the same transaction
+/// as the changes, so that the changes are rolled back if it fails.
+#[cfg(any(feature = "actix", feature = "axum"))]
+#[cfg(feature = "orm")]
+async fn record_audit_log<M, K, U>(
+    req: &crate::Request,
+    conn: &mut DatabaseConnection,
+    id: &K,
+    mut audit_log: AuditLog,
+) -> Result<(), Rejection>
+where
+    M: ModelAccessor<K, U>,
+    K: Default + std::fmt::Display + PartialEq,
+    U: Default + std::fmt::Display + PartialEq,
+{
+    if let Some(session) = user_session::<M>(req) {
+        audit_log.set_user_id(session.user_id());
+    }
+    audit_log.set_request_id(req.request_id());
+    if let Ok(model) = M::try_get_model_with(conn, id).await {
+        audit_log.set_current(model.into_map());
+    }
+    audit_log.record_with::<M>(conn).await.extract(req)
+}
+
+/// Enforces the access policies for the models declared with `#[schema(access_control)]`.
+/// The resource is `{model_name}:{id}` for a single model, or `{model_name}` otherwise,
+/// and the model data is used as the context for the conditions.
+///
+/// The policies are enforced in all the actions of the default controller, where the action
+/// is the method name. For the actions on a model list, the context is empty.
+/// The subject is the user session returned by `ModelHooks::extension_session`.
+#[cfg(any(feature = "actix", feature = "axum"))]
+#[cfg(feature = "orm")]
+async fn authorize_access<M, K, U>(
+    req: &crate::Request,
+    action: &str,
+    id: Option<&K>,
+    context: &Map,
+) -> Result<(), Rejection>
+where
+    M: ModelAccessor<K, U>,
+    K: Default + std::fmt::Display + PartialEq,
+    U: Default + std::fmt::Display + PartialEq,
+{
+    let resource_id = id.map(|id| id.to_string());
+    authorize_resource_access::<M, K, U>(req, action, resource_id.as_deref(), context).await
+}
+
+/// Enforces the access policies for the resource ID.
+#[cfg(any(feature = "actix", feature = "axum"))]
+#[cfg(feature = "orm")]
+async fn authorize_resource_access<M, K, U>(
+    req: &crate::Request,
+    action: &str,
+    resource_id: Option<&str>,
+    context: &Map,
+) -> Result<(), Rejection>
+where
+    M: ModelAccessor<K, U>,
+    K: Default + std::fmt::Display + PartialEq,
+    U: Default + std::fmt::Display + PartialEq,
+{
+    if !M::ACCESS_CONTROL {
+        return Ok(());
+    }
+
+    let Some(session) = user_session::<M>(req) else {
+        let err = warn!("the user session is absent");
+        return Err(Rejection::unauthorized(err).context(req));
+    };
+    let engine = PolicyEngine::shared();
+    if let Err(err) = engine.reload_if_stale().await {
+        tracing::error!("fail to reload the access policies: {err}");
+    }
+
+    let model_name = M::model_name();
+    let resource = if let Some(id) = resource_id {
+        format!("{model_name}:{id}")
+    } else {
+        model_name.to_owned()
+    };
+    if engine.evaluate(&session, action, &resource, context) {
+        Ok(())
+    } else {
+        let err = warn!("the action `{}` is denied for `{}`", action, resource);
+        Err(Rejection::forbidden(err).context(req))
+    }
+}
+
+/// Enforces the access policies for a model of the primary key,
+/// where the model data in the table is used as the context.
+#[cfg(any(feature = "actix", feature = "axum"))]
+#[cfg(feature = "orm")]
+async fn authorize_model_access<M, K, U>(
+    req: &crate::Request,
+    action: &str,
+    id: &K,
+) -> Result<(), Rejection>
+where
+    M: ModelAccessor<K, U>,
+    K: Default + std::fmt::Display + PartialEq,
+    U: Default + std::fmt::Display + PartialEq,
+{
+    if !M::ACCESS_CONTROL && M::TENANT_SCOPE.is_none() {
+        return Ok(());
+    }
+
+    let model = M::find_by_id::<Map>(id)
+        .await
+        .extract(req)?
+        .unwrap_or_default();
+    check_model_tenant::<M, K, U>(req, &model)?;
+    authorize_access::<M, K, U>(req, action, Some(id), &model).await
+}
+
+/// Returns the user session in the extension data of the model.
+#[cfg(any(feature = "actix", feature = "axum"))]
+#[cfg(feature = "orm")]
+fn user_session<M: ModelHooks>(req: &crate::Request) -> Option<UserSession<String>> {
+    req.get_data::<<M as ModelHooks>::Extension>()
+        .and_then(|extension| M::extension_session(&extension))
+}
+
+/// Returns the tenant ID of the user session.
+#[cfg(any(feature = "actix", feature = "axum"))]
+#[cfg(feature = "orm")]
+fn session_tenant_id<M: ModelHooks>(req: &crate::Request) -> Option<String> {
+    user_session::<M>(req).and_then(|session| session.tenant_id().cloned())
+}
+
+/// Constrains the query by the tenant ID of the user session for the models with a tenant scope.
+/// The query matches no rows if the tenant ID is absent. The tenant ID is also set
+/// for the models without a tenant scope, so that their references can be populated.
+#[cfg(any(feature = "actix", feature = "axum"))]
+#[cfg(feature = "orm")]
+fn scope_query_tenant<M, K, U>(req: &crate::Request, query: &mut Query)
+where
+    M: ModelAccessor<K, U>,
+    K: Default + std::fmt::Display + PartialEq,
+    U: Default + std::fmt::Display + PartialEq,
+{
+    if let Some(tenant_id) = session_tenant_id::<M>(req) {
+        query.set_tenant_id(tenant_id);
+    }
+}
+
+/// Checks that the model data belongs to the tenant of the user session
+/// for the models with a tenant scope. A model of another tenant is reported as not found.
+#[cfg(any(feature = "actix", feature = "axum"))]
+#[cfg(feature = "orm")]
+fn check_model_tenant<M, K, U>(req: &crate::Request, model: &Map) -> Result<(), Rejection>
+where
+    M: ModelAccessor<K, U>,
+    K: Default + std::fmt::Display + PartialEq,
+    U: Default + std::fmt::Display + PartialEq,
+{
+    if let Some(tenant_scope) = M::TENANT_SCOPE {
+        let tenant_id = session_tenant_id::<M>(req);
+        let model_tenant_id = model
+            .get(tenant_scope)
+            .filter(|value| !value.is_null())
+            .map(|value| value.to_string_unquoted());
+        if tenant_id.is_none() || tenant_id != model_tenant_id {
+            let err = warn!("the model does not exist in the tenant");
+            return Err(Rejection::not_found(err).context(req));
+        }
+    }
+    Ok(())
+}
+
+/// Converts the database error into a rejection,
+/// where a unique violation is reported as the validation errors of the fields.
+#[cfg(any(feature = "actix", feature = "axum"))]
+#[cfg(feature = "orm")]
+fn reject_database_error(req: &crate::Request, err: Error) -> Rejection {
+    if let Some(validation) = orm::check_unique_violation(&err) {
+        Rejection::bad_request(validation).context(req)
+    } else {
+        Rejection::from_error(err).context(req)
+    }
 }
@@ -0,0 +1,14 @@
+use zino_core::{auth::JwtKey, response::StatusCode};
+
+/// Returns the JSON Web Key Set of the shared JWT keys.
+pub(crate) fn jwks_response() -> crate::Response {
+    let mut res = crate::Response::new(StatusCode::OK);
+    match serde_json::to_vec(&JwtKey::jwks()) {
+        Ok(bytes) => {
+            res.set_content_type("application/json");
+            res.set_bytes_data(bytes);
+        }
+        Err(err) => res.set_error_message(err),
+    }
+    res
+}
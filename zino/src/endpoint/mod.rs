@@ -1,3 +1,11 @@
+cfg_if::cfg_if! {
+    if #[cfg(any(feature = "actix", feature = "axum"))] {
+        mod jwks;
+
+        pub(crate) use self::jwks::jwks_response;
+    }
+}
+
 cfg_if::cfg_if! {
     if #[cfg(feature = "axum")] {
         mod axum_sse;
@@ -51,7 +51,10 @@ where
             req.extensions_mut().insert(ctx);
         }
 
+        // The read-your-writes window of the database services is scoped to the request.
         let fut = self.service.call(req.into());
+        #[cfg(feature = "orm")]
+        let fut = zino_core::orm::scope_writes(fut);
         Box::pin(async move {
             let res = fut.await?;
             Ok(res)
@@ -0,0 +1,91 @@
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderValue, SET_COOKIE},
+    Error, HttpMessage,
+};
+use std::{
+    future::{ready, Future, Ready},
+    pin::Pin,
+    rc::Rc,
+};
+use zino_core::{auth::GlobalSessionStore, request::RequestContext};
+
+#[derive(Default)]
+pub struct SessionInitializer;
+
+impl<S, B> Transform<S, ServiceRequest> for SessionInitializer
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = SessionMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(SessionMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct SessionMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for SessionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        Box::pin(async move {
+            if !GlobalSessionStore::is_enabled() {
+                return service.call(req).await;
+            }
+
+            let req = crate::Request::from(req);
+            let session_store = GlobalSessionStore::shared();
+            let session_id = req
+                .get_cookie(GlobalSessionStore::cookie_name())
+                .map(|cookie| cookie.value().to_owned());
+            let session = if let Some(session_id) = session_id {
+                session_store.resume(&session_id).await
+            } else {
+                None
+            };
+
+            let req = ServiceRequest::from(req);
+            if let Some(session) = session.clone() {
+                req.extensions_mut().insert(session);
+            }
+
+            let mut res = service.call(req).await?;
+            if let Some(mut session) = session {
+                // Skips the session cookie if it has been set by the handler.
+                let cookie_prefix = format!("{}=", GlobalSessionStore::cookie_name());
+                let has_cookie = res.headers().get_all(SET_COOKIE).any(|value| {
+                    value
+                        .to_str()
+                        .is_ok_and(|cookie| cookie.starts_with(&cookie_prefix))
+                });
+                if !has_cookie
+                    && session_store.refresh(&mut session).await
+                    && let Ok(value) = HeaderValue::from_str(&session.to_cookie().to_string())
+                {
+                    res.headers_mut().append(SET_COOKIE, value);
+                }
+            }
+            Ok(res)
+        })
+    }
+}
@@ -8,5 +8,10 @@ pub(crate) async fn request_context(req: crate::Request, next: Next<Body>) -> Re
     if let Some(ctx) = new_context {
         req.extensions_mut().insert(ctx);
     }
-    next.run(req).await
+
+    // The read-your-writes window of the database services is scoped to the request.
+    let fut = next.run(req);
+    #[cfg(feature = "orm")]
+    let fut = zino_core::orm::scope_writes(fut);
+    fut.await
 }
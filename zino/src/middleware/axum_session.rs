@@ -0,0 +1,43 @@
+use axum::{
+    body::Body,
+    http::{self, header::SET_COOKIE, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use zino_core::{auth::GlobalSessionStore, request::RequestContext};
+
+pub(crate) async fn resume_session(req: crate::Request, next: Next<Body>) -> Response {
+    if !GlobalSessionStore::is_enabled() {
+        return next.run(req.into()).await;
+    }
+
+    let session_store = GlobalSessionStore::shared();
+    let session = if let Some(cookie) = req.get_cookie(GlobalSessionStore::cookie_name()) {
+        session_store.resume(cookie.value()).await
+    } else {
+        None
+    };
+
+    let mut req = http::Request::from(req);
+    if let Some(session) = session.clone() {
+        req.extensions_mut().insert(session);
+    }
+
+    let mut res = next.run(req).await;
+    if let Some(mut session) = session {
+        // Skips the session cookie if it has been set by the handler.
+        let cookie_prefix = format!("{}=", GlobalSessionStore::cookie_name());
+        let has_cookie = res.headers().get_all(SET_COOKIE).iter().any(|value| {
+            value
+                .to_str()
+                .is_ok_and(|cookie| cookie.starts_with(&cookie_prefix))
+        });
+        if !has_cookie
+            && session_store.refresh(&mut session).await
+            && let Ok(value) = HeaderValue::try_from(session.to_cookie().to_string())
+        {
+            res.headers_mut().append(SET_COOKIE, value);
+        }
+    }
+    res
+}